@@ -43,6 +43,19 @@ function loadBindingWithRetry(maxRetries = 3) {
   throw lastError
 }
 
+// There is one event loop per process, so tests share the app
+let sharedApp: { app: any; error: any } | null = null
+function overlayApp() {
+  if (!sharedApp) {
+    try {
+      sharedApp = { app: new (loadBindingWithRetry().OverlayApp)(), error: null }
+    } catch (error) {
+      sharedApp = { app: null, error }
+    }
+  }
+  return sharedApp
+}
+
 test('native binding loads correctly', () => {
   try {
     console.log('=== Starting native binding test ===')
@@ -120,6 +133,1187 @@ test('basic color creation works', () => {
   }
 })
 
+test('color parsing and conversions work', () => {
+  const binding = loadBindingWithRetry()
+
+  expect(binding.parseColor('#f00')).toEqual({ r: 255, g: 0, b: 0, a: 255 })
+  expect(binding.parseColor('#11223344')).toEqual({ r: 17, g: 34, b: 51, a: 68 })
+  expect(binding.parseColor('rgba(0 128 255 / 50%)')).toEqual({ r: 0, g: 128, b: 255, a: 128 })
+  expect(binding.parseColor('hsl(120, 100%, 50%)')).toEqual({ r: 0, g: 255, b: 0, a: 255 })
+  expect(binding.parseColor('RebeccaPurple')).toEqual({ r: 102, g: 51, b: 153, a: 255 })
+  expect(() => binding.parseColor('#12345')).toThrow('expected 3, 4, 6 or 8 hex digits')
+  expect(() => binding.parseColor('rgb(1, 2)')).toThrow('rgb() expects 3 or 4 components, got 2')
+
+  const color = { r: 200, g: 100, b: 50, a: 255 }
+  expect(binding.colorFromHsl(binding.colorToHsl(color))).toEqual(color)
+  expect(binding.colorFromHsv(binding.colorToHsv(color))).toEqual(color)
+  expect(binding.colorFromOklab(binding.colorToOklab(color))).toEqual(color)
+  expect(binding.colorFromLinear(binding.colorToLinear(color))).toEqual(color)
+})
+
+test('in-place buffer drawing mutates the caller buffer', () => {
+  const binding = loadBindingWithRetry()
+  const red = { r: 255, g: 0, b: 0, a: 255 }
+
+  const pixels = new Uint8Array(2 * 2 * 4)
+  binding.fillBufferColorInPlace(pixels, { width: 2, height: 2 }, red)
+  expect(Array.from(pixels.slice(0, 4))).toEqual([255, 0, 0, 255])
+
+  // Rows padded to 12 bytes
+  const padded = Buffer.alloc(12 + 8)
+  binding.drawPixelInPlace(padded, { width: 2, height: 2, stride: 12 }, 1, 1, red)
+  expect(Array.from(padded.subarray(16, 20))).toEqual([255, 0, 0, 255])
+  expect(() => binding.drawPixelInPlace(Buffer.alloc(8), { width: 2, height: 2 }, 0, 0, red)).toThrow(
+    'expected at least 16 bytes, got 8',
+  )
+
+  const batch = Buffer.alloc(4 * 4 * 4)
+  binding.drawBatch(batch, { width: 4, height: 4 }, [
+    { kind: binding.DrawCommandKind.Rectangle, x: 1, y: 1, width: 2, height: 2, color: red },
+    { kind: binding.DrawCommandKind.Pixel, x: 0, y: 0, color: red },
+  ])
+  expect(batch[0]).toBe(255)
+  expect(batch[(1 * 4 + 1) * 4]).toBe(255)
+  expect(() =>
+    binding.drawBatch(batch, { width: 4, height: 4 }, [
+      { kind: binding.DrawCommandKind.Fill, color: red },
+      { kind: binding.DrawCommandKind.Circle, x: 1, y: 1, color: red },
+    ]),
+  ).toThrow('Command 1 (Circle): missing `radius`')
+})
+
+test('in-place drawing with huge coordinates only walks the buffer', () => {
+  const binding = loadBindingWithRetry()
+  const red = { r: 255, g: 0, b: 0, a: 255 }
+  const layout = { width: 2, height: 2 }
+  const started = Date.now()
+
+  const line = Buffer.alloc(2 * 2 * 4)
+  binding.drawLineInPlace(line, layout, -2_000_000_000, 1, 2_000_000_000, 1, red)
+  expect([line[3], line[7], line[11], line[15]]).toEqual([0, 0, 255, 255])
+
+  // The left edge of a circle centered far outside the buffer
+  const circle = Buffer.alloc(2 * 2 * 4)
+  binding.drawCircleInPlace(circle, layout, 2_000_000_000, 0, 2_000_000_000, red)
+  expect(circle[3]).toBe(255)
+  binding.drawCircleInPlace(circle, layout, 0, 0, 2 ** 32 - 1, red)
+
+  const filled = Buffer.alloc(2 * 2 * 4)
+  binding.drawBatch(filled, layout, [
+    { kind: binding.DrawCommandKind.FillCircle, x: 0, y: 0, radius: 2 ** 32 - 1, color: red },
+  ])
+  expect([filled[3], filled[7], filled[11], filled[15]]).toEqual([255, 255, 255, 255])
+
+  expect(Date.now() - started).toBeLessThan(1000)
+})
+
+test('canvas draws paths with state, transform and clip', () => {
+  const binding = loadBindingWithRetry()
+  const alphaAt = (data: Buffer, width: number, x: number, y: number) => data[(y * width + x) * 4 + 3]
+
+  const canvas = new binding.Canvas(10, 10)
+  canvas.setFillStyle({ r: 255, g: 0, b: 0, a: 255 })
+  canvas.save()
+  canvas.translate(2, 2)
+  canvas.clipRect(0, 0, 3, 3)
+  canvas.fillRect(0, 0, 8, 8)
+  canvas.restore()
+  expect(canvas.getTransform()).toEqual([1, 0, 0, 1, 0, 0])
+  let data = canvas.toBuffer()
+  expect(alphaAt(data, 10, 2, 2)).toBe(255)
+  expect(alphaAt(data, 10, 4, 4)).toBe(255)
+  expect(alphaAt(data, 10, 5, 5)).toBe(0)
+
+  canvas.beginPath()
+  canvas.rect(0, 0, 10, 10)
+  canvas.rect(6, 6, 3, 3)
+  canvas.fill(binding.FillRule.EvenOdd)
+  data = canvas.toBuffer()
+  expect(alphaAt(data, 10, 0, 9)).toBe(255)
+  expect(alphaAt(data, 10, 7, 7)).toBe(0)
+
+  // Borrowed buffers are drawn into directly
+  const pixels = Buffer.alloc(4 * 4 * 4)
+  const borrowed = binding.Canvas.fromBuffer(pixels, { width: 4, height: 4 })
+  borrowed.beginPath()
+  borrowed.moveTo(0, 0.5)
+  borrowed.lineTo(4, 0.5)
+  borrowed.stroke()
+  expect(alphaAt(pixels, 4, 1, 0)).toBe(255)
+  expect(alphaAt(pixels, 4, 1, 1)).toBe(0)
+  expect(() => borrowed.arc(0, 0, -1, 0, Math.PI)).toThrow('Arc radius must not be negative')
+
+  // Window canvases keep the error code of the window they draw into
+  const { app } = overlayApp()
+  if (app) {
+    const window = app.createWindow({ ...binding.createWindowConfig(), width: 4, height: 4 })
+    const frame = window.getCanvas()
+    window.close()
+    try {
+      frame.fillRect(0, 0, 1, 1)
+      throw new Error('Drawing into a closed window succeeded')
+    } catch (error: any) {
+      expect(error.code).toBe(binding.ErrorCode.WindowDestroyed)
+    }
+  }
+})
+
+test('binary draw command encoding round trips and validates', () => {
+  const binding = loadBindingWithRetry()
+  const red = { r: 255, g: 0, b: 0, a: 255 }
+  const encoded = binding.encodeDrawCommands([
+    { kind: binding.DrawCommandKind.Rectangle, x: 1, y: 1, width: 2, height: 2, color: red },
+    { kind: binding.DrawCommandKind.Line, x: 0, y: 0, x2: 3, y2: 0, color: red },
+  ])
+  // opcode + 4 args + rgba, then opcode + 4 args + rgba
+  expect(encoded.length).toBe(42)
+
+  const pixels = Buffer.alloc(4 * 4 * 4)
+  binding.drawBatchEncoded(pixels, { width: 4, height: 4 }, encoded)
+  expect(pixels[3]).toBe(255)
+  expect(pixels[(2 * 4 + 2) * 4 + 3]).toBe(255)
+  expect(pixels[(3 * 4 + 3) * 4 + 3]).toBe(0)
+
+  expect(() =>
+    binding.drawBatchEncoded(pixels, { width: 4, height: 4 }, Buffer.concat([encoded, Buffer.from([9])])),
+  ).toThrow('Command 2 (byte 42): unknown opcode 9')
+  expect(() => binding.drawBatchEncoded(pixels, { width: 4, height: 4 }, encoded.subarray(0, 30))).toThrow(
+    'Command 1 (byte 21): truncated',
+  )
+
+  // Sizes past the range of the 32-bit arguments are rejected instead of wrapping
+  const huge = { kind: binding.DrawCommandKind.Circle, x: 0, y: 0, radius: 2 ** 31, color: red }
+  expect(() => binding.encodeDrawCommands([{ kind: binding.DrawCommandKind.Fill, color: red }, huge])).toThrow(
+    "Command 1 (Circle): `radius` is 2147483648, more than the binary encoding's maximum of 2147483647",
+  )
+  expect(binding.encodeDrawCommands([{ ...huge, radius: 2 ** 31 - 1 }]).length).toBe(17)
+})
+
+test('huge draw commands return promptly', () => {
+  const binding = loadBindingWithRetry()
+  const red = { r: 255, g: 0, b: 0, a: 255 }
+  const max = 2 ** 31 - 1
+  const commands = [
+    { kind: binding.DrawCommandKind.Line, x: -max, y: 0, x2: max, y2: 0, color: red },
+    { kind: binding.DrawCommandKind.Circle, x: 0, y: 0, radius: max, color: red },
+    { kind: binding.DrawCommandKind.FillCircle, x: 0, y: 0, radius: max, color: red },
+  ]
+  const encoded = binding.encodeDrawCommands(commands)
+  const started = Date.now()
+
+  const pixels = Buffer.alloc(4 * 4 * 4)
+  binding.drawBatchEncoded(pixels, { width: 4, height: 4 }, encoded)
+  expect(pixels[(3 * 4 + 3) * 4 + 3]).toBe(255)
+
+  // Window drawing runs under the window lock, so it must stay just as quick
+  const { app } = overlayApp()
+  if (app) {
+    const window = app.createWindow({ ...binding.createWindowConfig(), width: 8, height: 8 })
+    window.drawCommands(commands)
+    window.drawCommandsEncoded(encoded)
+    window.close()
+  } else {
+    console.warn('⚠ No display available, skipping huge window draw commands')
+  }
+
+  expect(Date.now() - started).toBeLessThan(1000)
+})
+
+test('parallel rendering is deterministic across thread counts', () => {
+  const binding = loadBindingWithRetry()
+  const gradient = {
+    kind: binding.GradientKind.Radial,
+    x0: 300,
+    y0: 200,
+    radius: 250,
+    stops: [
+      { offset: 0, color: { r: 255, g: 0, b: 0, a: 255 } },
+      { offset: 1, color: { r: 0, g: 0, b: 255, a: 90 } },
+    ],
+    dither: true,
+  }
+  const render = (threads: number) => {
+    binding.setRenderThreads(threads)
+    const layout = { width: 640, height: 480 }
+    const filled = binding.fillBufferGradient(Buffer.alloc(640 * 480 * 4), 640, 480, gradient)
+    binding.drawRectangleInPlace(filled, layout, -10, 20, 500, 400, { r: 0, g: 255, b: 0, a: 128 })
+    return binding.premultiplyBuffer(filled)
+  }
+
+  const single = render(1)
+  expect(binding.getRenderThreads()).toBe(1)
+  expect(render(4).equals(single)).toBe(true)
+  binding.setRenderThreads(0)
+
+  // Huge counts are capped instead of starting a thread per row
+  const cpus = binding.getRenderThreads()
+  binding.setRenderThreads(1e9)
+  expect(binding.getRenderThreads()).toBe(cpus * 4)
+  binding.setRenderThreads(0)
+})
+
+test('vectorized pixel kernels match per-pixel results', () => {
+  const binding = loadBindingWithRetry()
+  expect(['avx2', 'sse2', 'neon', 'scalar']).toContain(binding.getSimdSupport())
+
+  // 37 pixels so both the vector body and the scalar tail are exercised
+  const pixels = Buffer.alloc(37 * 4)
+  for (let i = 0; i < pixels.length; i++) pixels[i] = (i * 97 + 13) & 0xff
+
+  const swapped = binding.swizzleBufferRb(pixels)
+  for (let i = 0; i < pixels.length; i += 4) {
+    expect([swapped[i], swapped[i + 1], swapped[i + 2], swapped[i + 3]]).toEqual([
+      pixels[i + 2],
+      pixels[i + 1],
+      pixels[i],
+      pixels[i + 3],
+    ])
+  }
+
+  const premultiplied = binding.premultiplyBuffer(pixels)
+  for (let i = 0; i < pixels.length; i += 4) {
+    const a = pixels[i + 3]
+    const color = { r: pixels[i], g: pixels[i + 1], b: pixels[i + 2], a }
+    const expected = binding.premultiplyColor(color)
+    expect([premultiplied[i], premultiplied[i + 1], premultiplied[i + 2], premultiplied[i + 3]]).toEqual([
+      expected.r,
+      expected.g,
+      expected.b,
+      expected.a,
+    ])
+  }
+})
+
+test('premultiplied buffers round trip and blending rounds to nearest', () => {
+  const binding = loadBindingWithRetry()
+
+  // Every alpha level, with channels no larger than alpha so premultiplying keeps them
+  const premultiplied = Buffer.alloc(256 * 4)
+  for (let a = 0; a < 256; a++) premultiplied.set([a, Math.floor(a / 2), (a * 7) % (a + 1), a], a * 4)
+  const straight = binding.unpremultiplyBuffer(premultiplied)
+  expect(binding.premultiplyBuffer(straight)).toEqual(premultiplied)
+
+  // Straight colors only survive exactly where alpha keeps every channel distinct
+  const opaque = Buffer.from([10, 128, 250, 255, 0, 0, 0, 0])
+  expect(binding.unpremultiplyBuffer(binding.premultiplyBuffer(opaque))).toEqual(opaque)
+  const faint = Buffer.from([200, 100, 50, 16])
+  const back = binding.unpremultiplyBuffer(binding.premultiplyBuffer(faint))
+  for (let i = 0; i < 3; i++) expect(Math.abs(back[i] - faint[i])).toBeLessThanOrEqual(8)
+  expect(back[3]).toBe(16)
+
+  const white = { r: 255, g: 255, b: 255, a: 255 }
+  const black = { r: 0, g: 0, b: 0, a: 255 }
+  expect(binding.blendColors({ ...white, a: 128 }, black)).toEqual({ r: 128, g: 128, b: 128, a: 255 })
+  // Blending over transparency keeps the color instead of darkening it
+  expect(binding.blendColors({ r: 255, g: 0, b: 0, a: 64 }, { r: 0, g: 0, b: 0, a: 0 })).toEqual({
+    r: 255,
+    g: 0,
+    b: 0,
+    a: 64,
+  })
+  expect(binding.blendColorsPremultiplied({ r: 128, g: 0, b: 0, a: 128 }, white)).toEqual({
+    r: 255,
+    g: 127,
+    b: 127,
+    a: 255,
+  })
+
+  // Halfway values round up
+  expect(binding.lerpColors(black, white, 0.5)).toEqual({ r: 128, g: 128, b: 128, a: 255 })
+  expect(binding.lerpColors({ r: 0, g: 0, b: 0, a: 0 }, { r: 10, g: 1, b: 3, a: 255 }, 0.25)).toEqual({
+    r: 3,
+    g: 0,
+    b: 1,
+    a: 64,
+  })
+  // t is clamped to 0..1
+  expect(binding.lerpColors(black, white, 2)).toEqual(white)
+})
+
+test('pixel formats convert to and from RGBA', () => {
+  const binding = loadBindingWithRetry()
+  const F = binding.PixelFormat
+  const rgba = Buffer.from([255, 0, 0, 255, 0, 128, 255, 100])
+
+  expect(Array.from(binding.convertPixels(rgba, 2, 1, F.Rgba8, F.Bgra8))).toEqual([0, 0, 255, 255, 255, 128, 0, 100])
+  expect(Array.from(binding.convertPixels(rgba, 2, 1, F.Rgba8, F.Rgb8))).toEqual([255, 0, 0, 0, 128, 255])
+  expect(Array.from(binding.convertPixels(Buffer.from([0x00, 0xf8]), 1, 1, F.Rgb565, F.Rgba8))).toEqual([
+    255, 0, 0, 255,
+  ])
+  expect(Array.from(binding.convertPixels(Buffer.from([9, 128]), 1, 1, F.GrayAlpha8, F.Rgba8))).toEqual([
+    9, 9, 9, 128,
+  ])
+
+  // Two RGB rows padded to 8 bytes
+  const padded = Buffer.from([1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12])
+  expect(Array.from(binding.convertPixels(padded, 2, 2, F.Rgb8, F.Rgba8, 8))).toEqual([
+    1, 2, 3, 255, 4, 5, 6, 255, 7, 8, 9, 255, 10, 11, 12, 255,
+  ])
+  expect(() => binding.convertPixels(padded, 2, 2, F.Rgb8, F.Rgba8, 5)).toThrow('Stride 5 is smaller')
+  expect(() => binding.convertPixels(padded, 3, 2, F.Rgb8, F.Rgba8, 9)).toThrow('expected at least 18 bytes')
+
+  // Buffer functions draw in the layout's format and leave row padding alone
+  const red = { r: 255, g: 0, b: 0, a: 255 }
+  const rgbRows = Buffer.from(padded)
+  binding.drawPixelInPlace(rgbRows, { width: 2, height: 2, stride: 8, format: F.Rgb8 }, 1, 1, red)
+  expect(Array.from(rgbRows)).toEqual([1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 255, 0, 0])
+  const bgra = Buffer.alloc(2 * 2 * 4)
+  binding.drawRectangleInPlace(bgra, { width: 2, height: 2, format: F.Bgra8 }, 0, 0, 1, 2, red)
+  expect(Array.from(bgra.subarray(0, 8))).toEqual([0, 0, 255, 255, 0, 0, 0, 0])
+  const rgb565 = Buffer.from([0x1f, 0x00, 0xe0, 0x07])
+  binding.fillBufferColorInPlace(rgb565, { width: 1, height: 1, stride: 4, format: F.Rgb565 }, red)
+  expect(Array.from(rgb565)).toEqual([0x00, 0xf8, 0xe0, 0x07])
+  expect(() => binding.fillBufferColorInPlace(rgb565, { width: 2, height: 2, format: F.Rgb565 }, red)).toThrow(
+    'expected at least 8 bytes',
+  )
+  expect(Array.from(binding.fillBufferColor(Buffer.alloc(6), red, F.Bgr8))).toEqual([0, 0, 255, 0, 0, 255])
+})
+
+test('YUV frames convert to RGBA', () => {
+  const binding = loadBindingWithRetry()
+  const { I420, Nv12 } = binding.YuvLayout
+
+  // Limited-range white and black
+  const white = binding.convertYuvToRgba({
+    layout: I420,
+    width: 2,
+    height: 2,
+    y: Buffer.alloc(4, 235),
+    u: Buffer.from([128]),
+    v: Buffer.from([128]),
+  })
+  expect(Array.from(white.subarray(0, 4))).toEqual([255, 255, 255, 255])
+
+  // NV12 with a padded Y plane; left half red, right half green
+  const rgba = binding.convertYuvToRgba({
+    layout: Nv12,
+    width: 4,
+    height: 1,
+    y: Buffer.from([81, 81, 145, 145, 0, 0]),
+    uv: Buffer.from([90, 240, 54, 34]),
+    yStride: 6,
+  })
+  expect(Array.from(rgba.subarray(0, 4))).toEqual([254, 0, 0, 255])
+  expect(Array.from(rgba.subarray(8, 12))).toEqual([0, 255, 1, 255])
+
+  const full = binding.convertYuvToRgba({
+    layout: I420,
+    width: 1,
+    height: 1,
+    y: Buffer.from([0]),
+    u: Buffer.from([128]),
+    v: Buffer.from([128]),
+    matrix: binding.YuvMatrix.Bt709,
+    range: binding.YuvRange.Full,
+  })
+  expect(Array.from(full)).toEqual([0, 0, 0, 255])
+
+  expect(() => binding.convertYuvToRgba({ layout: Nv12, width: 2, height: 2, y: Buffer.alloc(4) })).toThrow(
+    'NV12 frames require a `uv` plane',
+  )
+  expect(() =>
+    binding.convertYuvToRgba({
+      layout: I420,
+      width: 4,
+      height: 2,
+      y: Buffer.alloc(8),
+      u: Buffer.alloc(2),
+      v: Buffer.alloc(1),
+    }),
+  ).toThrow('V plane too small')
+})
+
+test('filters blur, shadow and recolor buffers in place', () => {
+  const binding = loadBindingWithRetry()
+  const K = binding.FilterKind
+  const layout = { width: 5, height: 5 }
+  const pixel = (buffer: Buffer, x: number, y: number) =>
+    Array.from(buffer.subarray((y * 5 + x) * 4, (y * 5 + x + 1) * 4))
+  const dot = (x: number, y: number) => {
+    const buffer = Buffer.alloc(5 * 5 * 4)
+    buffer.set([255, 0, 0, 255], (y * 5 + x) * 4)
+    return buffer
+  }
+
+  // Blurring keeps the color and spreads coverage
+  const blurred = dot(2, 2)
+  binding.applyFilters(blurred, layout, [{ kind: K.BoxBlur, radius: 1 }])
+  expect(pixel(blurred, 1, 1)).toEqual([255, 0, 0, 28])
+  expect(pixel(blurred, 0, 0)).toEqual([0, 0, 0, 0])
+
+  const shadowed = dot(1, 1)
+  binding.applyFilters(shadowed, layout, [
+    { kind: K.DropShadow, offsetX: 2, offsetY: 2, color: { r: 0, g: 0, b: 0, a: 128 } },
+  ])
+  expect(pixel(shadowed, 1, 1)).toEqual([255, 0, 0, 255])
+  expect(pixel(shadowed, 3, 3)).toEqual([0, 0, 0, 128])
+
+  const outlined = dot(2, 2)
+  binding.applyFilters(outlined, layout, [{ kind: K.Outline, radius: 1, color: { r: 0, g: 0, b: 255, a: 255 } }])
+  expect(pixel(outlined, 2, 1)).toEqual([0, 0, 255, 255])
+  expect(pixel(outlined, 1, 1)).toEqual([0, 0, 0, 0])
+  // Outlines grow by a disc, not a square
+  const ring = dot(2, 2)
+  binding.applyFilters(ring, layout, [{ kind: K.Outline, radius: 2, color: { r: 0, g: 0, b: 255, a: 255 } }])
+  expect(pixel(ring, 2, 0)[3]).toBe(255)
+  expect(pixel(ring, 1, 1)[3]).toBe(255)
+  expect(pixel(ring, 0, 0)[3]).toBe(0)
+  expect(() => binding.applyFilters(ring, layout, [{ kind: K.GaussianBlur, radius: 1e6 }])).toThrow(
+    '`radius` must be a number from 0 to 256',
+  )
+  expect(() =>
+    binding.applyFilters(ring, layout, [{ kind: K.DropShadow, offsetX: 5000, color: { r: 0, g: 0, b: 0, a: 255 } }]),
+  ).toThrow('`offsetX` must be between -1024 and 1024')
+
+  // Images drawn into a window keep the shadow that falls past their edges
+  const { app } = overlayApp()
+  if (app) {
+    const window = app.createWindow({ ...binding.createWindowConfig(), width: 5, height: 5 })
+    window.clearFrame({ r: 0, g: 255, b: 0, a: 255 })
+    const image = { data: Buffer.alloc(4 * 4, 255), width: 2, height: 2 }
+    window.drawImage(1, 1, image, [
+      { kind: K.DropShadow, offsetX: 2, offsetY: 2, color: { r: 0, g: 0, b: 0, a: 128 } },
+    ])
+    const frame = window.getCanvas().toBuffer()
+    expect(pixel(frame, 1, 1)).toEqual([255, 255, 255, 255])
+    expect(pixel(frame, 4, 4)).toEqual([0, 127, 0, 255])
+    expect(pixel(frame, 0, 0)).toEqual([0, 255, 0, 255])
+    window.close()
+  }
+
+  // Color filters leave alpha alone; the region limits the second pixel
+  const colors = Buffer.from([200, 100, 50, 255, 10, 20, 30, 128])
+  binding.applyFilters(colors, { width: 2, height: 1 }, [{ kind: K.Grayscale }], { x: 0, y: 0, width: 1, height: 1 })
+  expect(Array.from(colors)).toEqual([124, 124, 124, 255, 10, 20, 30, 128])
+  const swapRb = [0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0]
+  binding.applyFilters(colors, { width: 2, height: 1 }, [{ kind: K.ColorMatrix, matrix: swapRb }])
+  expect(Array.from(colors)).toEqual([124, 124, 124, 255, 30, 20, 10, 128])
+
+  const invalid = [{ kind: K.Grayscale }, { kind: K.Tint }]
+  expect(() => binding.applyFilters(colors, { width: 2, height: 1 }, invalid)).toThrow(
+    'Filter 1 (Tint): missing `color`',
+  )
+  expect(() => binding.applyFilters(colors, { width: 2, height: 1 }, [{ kind: K.ColorMatrix, matrix: [1] }])).toThrow(
+    '`matrix` must have 20 values',
+  )
+})
+
+test('overlay windows expose a clip stack', () => {
+  const binding = loadBindingWithRetry()
+  const methods = ['pushClipRect', 'pushClipRoundedRect', 'pushClipMask', 'popClip', 'resetClip', 'getClipDepth']
+  for (const method of methods) {
+    expect(typeof binding.OverlayWindow.prototype[method]).toBe('function')
+  }
+})
+
+test('clips limit drawing into buffers', () => {
+  const binding = loadBindingWithRetry()
+  const layout = { width: 8, height: 8 }
+  const red = { r: 255, g: 0, b: 0, a: 255 }
+  const blue = { r: 0, g: 0, b: 255, a: 255 }
+  const pixel = (data: Buffer, x: number, y: number) => Array.from(data.subarray((y * 8 + x) * 4, (y * 8 + x) * 4 + 4))
+  const fill = [{ kind: binding.DrawCommandKind.Fill, color: red }]
+
+  // Nested clips intersect
+  const nested = new binding.BufferClip(8, 8)
+  nested.pushRect({ x: 0, y: 0, width: 6, height: 6 })
+  nested.pushRect({ x: 3, y: 3, width: 5, height: 5 })
+  expect(nested.getDepth()).toBe(2)
+  let data = Buffer.alloc(8 * 8 * 4)
+  nested.drawBatch(data, layout, fill)
+  expect(pixel(data, 3, 3)).toEqual([255, 0, 0, 255])
+  expect(pixel(data, 5, 5)).toEqual([255, 0, 0, 255])
+  expect(pixel(data, 6, 6)).toEqual([0, 0, 0, 0])
+  expect(pixel(data, 2, 2)).toEqual([0, 0, 0, 0])
+  expect(nested.pop()).toBe(true)
+  nested.reset()
+  expect(nested.getDepth()).toBe(0)
+  expect(nested.pop()).toBe(false)
+
+  // Rounded corners blend by coverage, and only rows the draw wrote to change
+  const rounded = new binding.BufferClip(8, 8)
+  rounded.pushRoundedRect({ x: 0, y: 0, width: 8, height: 8 }, 4)
+  data = Buffer.alloc(8 * 8 * 4)
+  binding.fillBufferColorInPlace(data, layout, blue)
+  rounded.drawBatch(data, layout, [{ kind: binding.DrawCommandKind.Line, x: 0, y: 0, x2: 7, y2: 0, color: red }])
+  expect(pixel(data, 0, 0)).toEqual([0, 0, 255, 255])
+  expect(pixel(data, 1, 0)).toEqual([51, 0, 204, 255])
+  expect(pixel(data, 4, 0)).toEqual([246, 0, 9, 255])
+  expect(pixel(data, 1, 1)).toEqual([0, 0, 255, 255])
+  expect(() => rounded.pushRoundedRect({ x: 0, y: 0, width: 8, height: 8 }, -1)).toThrow(
+    'Corner radius must be a non-negative number',
+  )
+
+  // Masks scale drawing by their coverage, with rows `stride` bytes apart
+  const masked = new binding.BufferClip(8, 8)
+  masked.pushMask({ data: Buffer.from([255, 0, 9, 128, 255, 9]), x: 1, y: 1, width: 2, height: 2, stride: 3 })
+  data = Buffer.alloc(8 * 8 * 4)
+  masked.drawBatch(data, layout, fill)
+  expect([pixel(data, 1, 1), pixel(data, 2, 1), pixel(data, 1, 2), pixel(data, 2, 2)]).toEqual([
+    [255, 0, 0, 255],
+    [0, 0, 0, 0],
+    [128, 0, 0, 128],
+    [255, 0, 0, 255],
+  ])
+  expect(pixel(data, 0, 0)).toEqual([0, 0, 0, 0])
+  const mask = { data: Buffer.alloc(3), x: 0, y: 0, width: 2, height: 2 }
+  expect(() => masked.pushMask(mask)).toThrow('Mask too small for 2x2 (stride 2): expected at least 4 bytes, got 3')
+  expect(() => masked.pushMask({ ...mask, stride: 1 })).toThrow('Mask stride 1 is smaller than its width 2')
+  expect(masked.getDepth()).toBe(1)
+
+  expect(() => masked.drawBatch(Buffer.alloc(16), { width: 2, height: 2 }, fill)).toThrow(
+    'Buffer is 2x2, but the clip was made for 8x8',
+  )
+})
+
+test('registered images are drawn by id with eviction and stats', () => {
+  const binding = loadBindingWithRetry()
+  const image = (width: number, height: number, rgba: number[]) => ({
+    data: Buffer.alloc(width * height * 4).fill(Buffer.from(rgba)),
+    width,
+    height,
+  })
+  binding.clearImages()
+  binding.setSpriteCacheLimit(64 * 1024 * 1024)
+  binding.registerImage('red', image(2, 2, [255, 0, 0, 255]))
+  binding.registerImage('blue', image(1, 1, [0, 0, 255, 128]))
+  binding.registerImage('banner', image(300, 10, [0, 255, 0, 255]))
+
+  const stats = binding.getSpriteStats()
+  expect(stats.count).toBe(3)
+  expect(stats.atlasSprites).toBe(2)
+  expect(stats.atlasPages).toBe(1)
+  expect(stats.spriteBytes).toBe(16 + 4 + 12000)
+
+  const buffer = Buffer.alloc(4 * 4 * 4)
+  const layout = { width: 4, height: 4 }
+  binding.drawSpriteInPlace(buffer, layout, 'red', 1, 1)
+  binding.drawSpriteInPlace(buffer, layout, 'blue', 0, 0, { width: 4, height: 1 })
+  expect(Array.from(buffer.subarray(20, 24))).toEqual([255, 0, 0, 255])
+  expect(Array.from(buffer.subarray(12, 16))).toEqual([0, 0, 255, 128])
+
+  const gray = Buffer.from([10, 10, 10, 255])
+  binding.drawSpriteInPlace(gray, { width: 1, height: 1 }, 'blue', 0, 0, { blend: true, opacity: 0.5 })
+  expect(Array.from(gray)).toEqual([7, 7, 71, 255])
+  expect(() => binding.drawSpriteInPlace(buffer, layout, 'missing', 0, 0)).toThrow("Image 'missing' is not registered")
+
+  // The limit counts whole atlas pages; the least recently drawn image goes first
+  const page = 1024 * 1024 * 4
+  expect(stats.atlasBytes).toBe(page)
+  expect(stats.cachedBytes).toBe(page + 12000)
+  binding.setSpriteCacheLimit(page + 1000)
+  expect(binding.getSpriteStats().count).toBe(2)
+  expect(binding.getSpriteStats().cachedBytes).toBe(page)
+  expect(() => binding.drawSpriteInPlace(buffer, layout, 'banner', 0, 0)).toThrow('not registered')
+  expect(binding.unregisterImage('red')).toBe(true)
+  expect(binding.unregisterImage('red')).toBe(false)
+
+  // A page alone is over a smaller limit, so its images are evicted and new
+  // ones are stored on their own
+  binding.setSpriteCacheLimit(1000)
+  binding.registerImage('dot', image(2, 2, [255, 255, 255, 255]))
+  const small = binding.getSpriteStats()
+  expect([small.count, small.atlasSprites, small.atlasPages]).toEqual([1, 0, 0])
+  expect(small.cachedBytes).toBe(16)
+  binding.drawSpriteInPlace(buffer, layout, 'dot', 0, 0)
+  expect(Array.from(buffer.subarray(0, 4))).toEqual([255, 255, 255, 255])
+
+  binding.clearImages()
+  binding.setSpriteCacheLimit(64 * 1024 * 1024)
+  expect(binding.getSpriteStats().atlasBytes).toBe(0)
+})
+
+test('layers composite the same on the CPU and GPU backends', () => {
+  const binding = loadBindingWithRetry()
+  const frame = Buffer.alloc(4 * 4 * 4).fill(Buffer.from([200, 0, 0, 255]))
+  const layout = { width: 4, height: 4 }
+  binding.registerImage('layer', { data: Buffer.from([0, 255, 0, 255]), width: 1, height: 1 })
+  const layers = [
+    { x: 1, y: 1, width: 2, height: 2, color: { r: 0, g: 0, b: 255, a: 128 } },
+    { x: 0, y: 0, width: 1, height: 1, image: 'layer', opacity: 0.5 },
+  ]
+
+  const cpu = binding.compositeLayers(frame, layout, layers, { opacity: 0.5, backend: binding.CompositorBackend.Cpu })
+  expect(Array.from(cpu.subarray(0, 4))).toEqual([100, 128, 0, 128])
+  expect(Array.from(cpu.subarray(20, 24))).toEqual([100, 0, 128, 128])
+  expect(Array.from(cpu.subarray(60, 64))).toEqual([200, 0, 0, 128])
+  expect(Array.from(frame.subarray(0, 4))).toEqual([200, 0, 0, 255])
+
+  // Without an adapter the GPU backend falls back to the CPU
+  const gpu = binding.compositeLayers(frame, layout, layers, { opacity: 0.5, backend: binding.CompositorBackend.Gpu })
+  expect(gpu.length).toBe(cpu.length)
+  for (let i = 0; i < cpu.length; i++) {
+    expect(Math.abs(gpu[i] - cpu[i])).toBeLessThanOrEqual(2)
+  }
+
+  // Any adapter, software ones like llvmpipe included, must be usable
+  const adapter = binding.getGpuAdapter()
+  if (binding.enumerateGpuAdapters().length > 0) {
+    expect(adapter).not.toBeNull()
+    expect(typeof adapter.name).toBe('string')
+    expect(typeof adapter.deviceType).toBe('string')
+  } else {
+    expect(adapter).toBeNull()
+  }
+  // Listing adapters leaves the compositing device working
+  const again = binding.compositeLayers(frame, layout, layers, { opacity: 0.5, backend: binding.CompositorBackend.Gpu })
+  expect(again.length).toBe(cpu.length)
+
+  // Frames wider than the adapter's textures are composited on the CPU
+  const wide = { width: 20000, height: 1 }
+  const wideFrame = Buffer.alloc(20000 * 4).fill(Buffer.from([200, 0, 0, 255]))
+  const wideCpu = binding.compositeLayers(wideFrame, wide, layers, { backend: binding.CompositorBackend.Cpu })
+  const wideGpu = binding.compositeLayers(wideFrame, wide, layers, { backend: binding.CompositorBackend.Gpu })
+  expect(wideGpu.length).toBe(wideCpu.length)
+  expect(Math.max(...wideGpu.map((value, i) => Math.abs(value - wideCpu[i])))).toBeLessThanOrEqual(2)
+  expect(() => binding.compositeLayers(Buffer.alloc(3), layout, [])).toThrow('Buffer too small')
+  expect(typeof binding.OverlayWindow.prototype.setCompositor).toBe('function')
+  binding.unregisterImage('layer')
+})
+
+test('scale modes place the frame on the window', () => {
+  const binding = loadBindingWithRetry()
+  const { ScaleMode } = binding
+  const place = (mode: number) => binding.getScalePlacement(mode, 2, 1, 5, 4)
+  expect(place(ScaleMode.MatchWindow)).toEqual({ x: 0, y: 0, width: 2, height: 1 })
+  expect(place(ScaleMode.Stretch)).toEqual({ x: 0, y: 0, width: 5, height: 4 })
+  expect(place(ScaleMode.Fit)).toEqual({ x: 0, y: 0, width: 5, height: 3 })
+  expect(place(ScaleMode.Integer)).toEqual({ x: 0, y: 1, width: 4, height: 2 })
+  expect(place(ScaleMode.Fixed)).toEqual({ x: 1, y: 1, width: 2, height: 1 })
+  // Frames larger than the window are cropped, never scaled below 1x
+  expect(binding.getScalePlacement(ScaleMode.Integer, 8, 8, 4, 4)).toEqual({ x: -2, y: -2, width: 8, height: 8 })
+  expect(typeof binding.OverlayWindow.prototype.setScaleMode).toBe('function')
+})
+
+test('resize policies and the resize hook are exposed', () => {
+  const binding = loadBindingWithRetry()
+  expect(binding.ResizePolicy.Clear).toBeDefined()
+  expect(binding.ResizePolicy.PreserveTopLeft).toBeDefined()
+  expect(binding.ResizePolicy.Rescale).toBeDefined()
+  for (const method of ['setResizePolicy', 'getResizePolicy', 'onResize']) {
+    expect(typeof binding.OverlayWindow.prototype[method]).toBe('function')
+  }
+  expect(binding.createWindowConfig().resizePolicy).toBe(binding.ResizePolicy.PreserveTopLeft)
+})
+
+test('resize policies carry the frame contents over', () => {
+  const binding = loadBindingWithRetry()
+  const { ResizePolicy } = binding
+  // 2x2 frame with one distinct pixel per corner
+  const [a, b, c, d] = [
+    [255, 0, 0, 255],
+    [0, 255, 0, 255],
+    [0, 0, 255, 255],
+    [255, 255, 255, 128],
+  ]
+  const frame = Buffer.from([...a, ...b, ...c, ...d])
+  const pixels = (data: Buffer) =>
+    Array.from({ length: data.length / 4 }, (_, i) => Array.from(data.subarray(i * 4, i * 4 + 4)))
+  const none = [0, 0, 0, 0]
+
+  // The overlap stays in place and new pixels are transparent
+  expect(pixels(binding.resizeFrameBuffer(frame, 2, 2, 3, 1, ResizePolicy.PreserveTopLeft))).toEqual([a, b, none])
+  expect(pixels(binding.resizeFrameBuffer(frame, 2, 2, 1, 3, ResizePolicy.PreserveTopLeft))).toEqual([a, c, none])
+
+  // Each new pixel samples the old pixel under its center
+  expect(pixels(binding.resizeFrameBuffer(frame, 2, 2, 4, 2, ResizePolicy.Rescale))).toEqual([a, a, b, b, c, c, d, d])
+  expect(pixels(binding.resizeFrameBuffer(frame, 2, 2, 1, 1, ResizePolicy.Rescale))).toEqual([d])
+
+  expect(pixels(binding.resizeFrameBuffer(frame, 2, 2, 2, 1, ResizePolicy.Clear))).toEqual([none, none])
+  expect(() => binding.resizeFrameBuffer(frame, 3, 2, 1, 1, ResizePolicy.Clear)).toThrow('Buffer too small')
+})
+
+test('window shapes reduce to merged rectangles', () => {
+  const binding = loadBindingWithRetry()
+  expect(binding.computeRoundedRegion(8, 6, 3)).toEqual([
+    { x: 1, y: 0, width: 6, height: 1 },
+    { x: 0, y: 1, width: 8, height: 4 },
+    { x: 1, y: 5, width: 6, height: 1 },
+  ])
+  expect(binding.computeRoundedRegion(3, 2, 0)).toEqual([{ x: 0, y: 0, width: 3, height: 2 }])
+
+  const alpha = Buffer.from([0, 200, 200, 0, 0, 200, 200, 0, 255, 255, 0, 0])
+  const rects = [{ x: 10, y: 10, width: 2, height: 2 }]
+  expect(binding.computeShapeRegion({ mask: { data: alpha, width: 4, height: 3 }, rects })).toEqual([
+    { x: 1, y: 0, width: 2, height: 2 },
+    { x: 0, y: 2, width: 2, height: 1 },
+    { x: 10, y: 10, width: 2, height: 2 },
+  ])
+  const bits = Buffer.from([0b10110000, 0b11000000])
+  const mask = { data: bits, width: 4, height: 2, format: binding.MaskFormat.Bit1 }
+  expect(binding.computeShapeRegion({ mask })).toEqual([
+    { x: 0, y: 0, width: 1, height: 1 },
+    { x: 2, y: 0, width: 2, height: 1 },
+    { x: 0, y: 1, width: 2, height: 1 },
+  ])
+
+  expect(() => binding.computeShapeRegion({ mask: { data: Buffer.alloc(3), width: 4, height: 1 } })).toThrow(
+    'Mask too small',
+  )
+  expect(() => binding.computeRoundedRegion(2, 2, -1)).toThrow('Corner radius must be a non-negative number')
+  expect(typeof binding.OverlayWindow.prototype.setShape).toBe('function')
+  expect(typeof binding.OverlayWindow.prototype.setCornerRadius).toBe('function')
+})
+
+test('window configs are validated before a window is created', () => {
+  const binding = loadBindingWithRetry()
+  const config = binding.createWindowConfig()
+  const check = (overrides: object) => () => binding.validateWindowConfig({ ...config, ...overrides })
+  expect(check({})).not.toThrow()
+  const icon = { data: Buffer.alloc(16), width: 2, height: 2 }
+  expect(check({ icon, theme: binding.WindowTheme.Dark, enabledButtons: { maximize: false } })).not.toThrow()
+
+  expect(check({ minimized: true, maximized: true })).toThrow('`minimized` and `maximized` can\'t both be set')
+  expect(check({ minimized: true, visible: false })).toThrow('`minimized` requires `visible`')
+  const minSize = { width: 900, height: 10 }
+  expect(check({ minSize, maxSize: { width: 800, height: 700 } })).toThrow('is larger than `maxSize`')
+  expect(check({ minSize })).toThrow('Size 800x600 is smaller than `minSize` 900x10')
+  expect(check({ resizeIncrements: { width: 0, height: 2 } })).toThrow('`resizeIncrements` must be at least 1x1')
+  expect(check({ icon: { data: Buffer.alloc(3), width: 2, height: 2 } })).toThrow('Invalid `icon`')
+  expect(check({ parentWindow: 0 })).toThrow('`parentWindow` must be a non-zero native window handle')
+})
+
+test('icons are converted into square size variants', () => {
+  const binding = loadBindingWithRetry()
+  // Opaque red beside transparent blue
+  const wide = binding.createIconVariants({ data: Buffer.from([255, 0, 0, 255, 0, 0, 255, 0]), width: 2, height: 1 })
+  expect(wide.map((variant: any) => [variant.width, variant.height])).toEqual([[16, 16]])
+  const pixel = (variant: any, x: number, y: number) => {
+    const offset = (y * variant.width + x) * 4
+    return [...variant.data.subarray(offset, offset + 4)]
+  }
+  expect(pixel(wide[0], 0, 3)).toEqual([0, 0, 0, 0])
+  expect(pixel(wide[0], 0, 4)).toEqual([255, 0, 0, 255])
+  expect(pixel(wide[0], 15, 8)).toEqual([0, 0, 0, 0])
+
+  // Downscaling averages the covered area without darkening toward transparency
+  const checker = Buffer.alloc(32 * 32 * 4)
+  for (let i = 0; i < 32 * 32; i++) {
+    if ((i % 32) % 2 === 0) checker.set([0, 0, 255, 255], i * 4)
+  }
+  const variants = binding.createIconVariants({ data: checker, width: 32, height: 32 })
+  expect(variants.map((variant: any) => variant.width)).toEqual([16, 24, 32])
+  expect(pixel(variants[0], 5, 5)).toEqual([0, 0, 255, 128])
+
+  const large = binding.createIconVariants({ data: Buffer.alloc(300 * 300 * 4), width: 300, height: 300 })
+  expect(large.map((variant: any) => variant.width)).toEqual([16, 24, 32, 48, 64, 256])
+  expect(() => binding.createIconVariants({ data: Buffer.alloc(0), width: 0, height: 1 })).toThrow('at least 1x1')
+  expect(() => binding.createIconVariants({ data: Buffer.alloc(3), width: 2, height: 2 })).toThrow('Buffer too small')
+
+  expect(typeof binding.isTaskbarProgressSupported()).toBe('boolean')
+  expect(binding.ProgressState.Indeterminate).toBeDefined()
+  expect(typeof binding.OverlayWindow.prototype.setIcon).toBe('function')
+  expect(typeof binding.OverlayWindow.prototype.setProgress).toBe('function')
+})
+
+test('platform capabilities are reported', () => {
+  const binding = loadBindingWithRetry()
+  const capabilities = binding.getPlatformCapabilities()
+  expect(capabilities.platform).toBeString()
+  expect(['win32', 'appkit', 'x11', 'wayland', 'other']).toContain(capabilities.backend)
+  expect(capabilities.windowCreation).toBe(capabilities.backend === 'win32')
+  for (const feature of [
+    'captureExclusion',
+    'skipTaskbar',
+    'clickThrough',
+    'transparency',
+    'alwaysOnBottom',
+    'perPixelHitTest',
+    'windowIcon',
+    'taskbarProgress',
+  ]) {
+    expect(typeof capabilities[feature]).toBe('boolean')
+    // Features are only advertised where windows can be created to use them
+    if (!capabilities.windowCreation) expect(capabilities[feature]).toBe(false)
+  }
+  expect(capabilities.taskbarProgress).toBe(binding.isTaskbarProgressSupported())
+  expect(capabilities.captureExclusion).toBe(capabilities.backend === 'win32')
+
+  expect(typeof binding.OverlayApp.prototype.getCapabilities).toBe('function')
+  expect(typeof binding.OverlayApp.prototype.setStrictMode).toBe('function')
+  expect(typeof binding.OverlayApp.prototype.isStrictMode).toBe('function')
+})
+
+test('errors carry a stable code', () => {
+  const binding = loadBindingWithRetry()
+  expect(binding.ErrorCode.BufferSizeMismatch).toBe('BufferSizeMismatch')
+  const codeOf = (fn: () => unknown) => {
+    try {
+      fn()
+    } catch (error: any) {
+      expect(error).toBeInstanceOf(Error)
+      return error.code
+    }
+  }
+
+  const { app, error } = overlayApp()
+  // There is one event loop per process, and none without a display
+  expect(codeOf(() => new binding.OverlayApp())).toBe(binding.ErrorCode.EventLoopUnavailable)
+  if (app) {
+    expect(codeOf(() => app.run())).toBe(binding.ErrorCode.NoWindows)
+  } else {
+    expect(error).toBeInstanceOf(Error)
+    expect(error.code).toBe(binding.ErrorCode.EventLoopUnavailable)
+  }
+  expect(typeof binding.OverlayWindow.prototype.close).toBe('function')
+})
+
+test('panics are thrown as errors and poisoned state recovers', () => {
+  const binding = loadBindingWithRetry()
+  if (!binding.injectPanic) {
+    console.warn('⚠ Built without the test-hooks feature, skipping panic injection')
+    return
+  }
+  const image = { data: Buffer.alloc(16, 255), width: 2, height: 2 }
+  const thrown = (fn: () => unknown) => {
+    try {
+      fn()
+    } catch (error: any) {
+      return error
+    }
+    throw new Error('Expected the call to throw')
+  }
+  binding.clearImages()
+  binding.registerImage('kept', image)
+
+  binding.injectPanic(binding.PanicSite.SpriteRegistry)
+  const panic = thrown(() => binding.registerImage('panics', image))
+  expect(panic).toBeInstanceOf(Error)
+  expect(panic.message).toContain('Injected panic')
+  // The registry was emptied, which the next call reports once
+  expect(thrown(() => binding.getSpriteStats()).code).toBe(binding.ErrorCode.StatePoisoned)
+  expect(binding.getSpriteStats().count).toBe(0)
+  binding.registerImage('kept', image)
+  expect(binding.getSpriteStats().count).toBe(1)
+
+  const canvas = new binding.Canvas(4, 4)
+  canvas.setFillStyle({ r: 255, g: 0, b: 0, a: 255 })
+  binding.injectPanic(binding.PanicSite.Drawing)
+  expect(thrown(() => canvas.fillRect(0, 0, 2, 2)).message).toContain('Injected panic')
+  canvas.fillRect(0, 0, 2, 2)
+  expect(canvas.toBuffer()[3]).toBe(255)
+
+  // Disarmed injections don't fire
+  binding.injectPanic(binding.PanicSite.Drawing)
+  binding.injectPanic(null)
+  canvas.fillRect(0, 0, 1, 1)
+  binding.clearImages()
+})
+
+test('panics with a window locked leave the window usable', () => {
+  const binding = loadBindingWithRetry()
+  const { app } = overlayApp()
+  if (!binding.injectPanic || !app) {
+    console.warn('⚠ Needs a display and the test-hooks feature, skipping window panics')
+    return
+  }
+  const codeOf = (fn: () => unknown) => {
+    try {
+      fn()
+    } catch (error: any) {
+      return error.code
+    }
+    return null
+  }
+  const red = { r: 255, g: 0, b: 0, a: 255 }
+  const window = app.createWindow({ ...binding.createWindowConfig(), width: 8, height: 8 })
+
+  // Drawing panics while the window is locked, and the next call reports the repair
+  binding.injectPanic(binding.PanicSite.Drawing)
+  expect(codeOf(() => window.clearFrame(red))).toBe(binding.ErrorCode.GenericFailure)
+  expect(codeOf(() => window.clearFrame(red))).toBe(binding.ErrorCode.StatePoisoned)
+  window.clearFrame(red)
+
+  // A redraw panics in the event loop, which keeps running
+  binding.injectPanic(binding.PanicSite.EventLoop)
+  window.requestRedraw()
+  for (let i = 0; i < 10; i++) app.pollEvents()
+  binding.injectPanic(null)
+  // The event loop may have repaired the state before JS locks it
+  expect([null, binding.ErrorCode.StatePoisoned]).toContain(codeOf(() => window.clearFrame(red)))
+  window.clearFrame(red)
+  expect(window.getFrameSize()).toEqual([8, 8])
+  window.close()
+})
+
+test('render recovery is exposed on windows', () => {
+  const binding = loadBindingWithRetry()
+  // Surface and device loss need a real window and GPU, so only the API is checked here
+  expect(binding.OverlayEvent.RenderError).toBeNumber()
+  expect(binding.OverlayEvent.DeviceLost).toBeNumber()
+  expect(binding.OverlayEvent.DeviceLost).not.toBe(binding.OverlayEvent.RenderError)
+  expect(typeof binding.OverlayWindow.prototype.setRenderRecovery).toBe('function')
+  expect(typeof binding.OverlayWindow.prototype.getRenderStatus).toBe('function')
+})
+
+test('easing curves map progress onto their shape', () => {
+  const { applyEasing, Easing } = loadBindingWithRetry()
+  const all = [Easing.Linear, Easing.EaseIn, Easing.EaseOut, Easing.EaseInOut, Easing.Spring]
+  for (const easing of all) {
+    expect(applyEasing(easing, 0)).toBeCloseTo(0, 10)
+    expect(applyEasing(easing, 1)).toBe(1)
+    // Progress is clamped to 0..1
+    expect(applyEasing(easing, -1)).toBeCloseTo(0, 10)
+    expect(applyEasing(easing, 2)).toBe(1)
+  }
+
+  expect(applyEasing(Easing.Linear, 0.25)).toBeCloseTo(0.25, 10)
+  expect(applyEasing(Easing.EaseIn, 0.5)).toBeCloseTo(0.125, 10)
+  expect(applyEasing(Easing.EaseOut, 0.5)).toBeCloseTo(0.875, 10)
+  expect(applyEasing(Easing.EaseInOut, 0.25)).toBeCloseTo(0.0625, 10)
+  expect(applyEasing(Easing.EaseInOut, 0.5)).toBeCloseTo(0.5, 10)
+  expect(applyEasing(Easing.EaseInOut, 0.75)).toBeCloseTo(0.9375, 10)
+
+  // The cubic curves never leave 0..1 and never move backwards
+  for (const easing of [Easing.EaseIn, Easing.EaseOut, Easing.EaseInOut]) {
+    let previous = 0
+    for (let i = 1; i <= 100; i++) {
+      const value = applyEasing(easing, i / 100)
+      expect(value).toBeGreaterThanOrEqual(previous)
+      expect(value).toBeLessThanOrEqual(1)
+      previous = value
+    }
+  }
+
+  // The spring overshoots its target before settling on it
+  const spring = Array.from({ length: 99 }, (_, i) => applyEasing(Easing.Spring, (i + 1) / 100))
+  expect(Math.max(...spring)).toBeGreaterThan(1)
+  expect(Math.abs(spring[98] - 1)).toBeLessThan(0.01)
+})
+
+test('chained animations run in order and report cancellation', async () => {
+  const binding = loadBindingWithRetry()
+  const { app } = overlayApp()
+  if (!app) {
+    console.warn('⚠ No display available, skipping animation chaining')
+    return
+  }
+  const window = app.createWindow({ ...binding.createWindowConfig(), width: 8, height: 8 })
+  const ended: Array<[number, boolean]> = []
+  const onEnd = (err: Error | null, id: number, cancelled: boolean) => ended.push([id, cancelled])
+  const settle = async (done: () => boolean) => {
+    for (let i = 0; i < 200 && !done(); i++) {
+      app.pollEvents()
+      await new Promise((resolve) => setTimeout(resolve, 5))
+    }
+  }
+  const element = window.addElement({ x: 0, y: 0, width: 2, height: 2, color: { r: 255, g: 0, b: 0, a: 255 } })
+
+  // The second animation starts only once the first completes
+  const first = window.animate(
+    { property: binding.TweenProperty.X, element, to: 4, durationMs: 20, easing: binding.Easing.Linear },
+    onEnd,
+  )
+  const second = window.animate(
+    { property: binding.TweenProperty.X, element, to: 0, durationMs: 20, after: first },
+    onEnd,
+  )
+  await settle(() => ended.length === 2)
+  expect(ended).toEqual([
+    [first, false],
+    [second, false],
+  ])
+  expect(window.isAnimating()).toBe(false)
+
+  // Cancelling a parent cancels its chain and tells both callbacks
+  ended.length = 0
+  const parent = window.animate({ property: binding.TweenProperty.Y, element, to: 4, durationMs: 10000 }, onEnd)
+  const child = window.animate(
+    { property: binding.TweenProperty.Y, element, to: 0, durationMs: 10, after: parent },
+    onEnd,
+  )
+  expect(window.cancelAnimation(parent)).toBe(true)
+  expect(window.cancelAnimation(child)).toBe(false)
+  await settle(() => ended.length === 2)
+  expect(ended).toEqual([
+    [parent, true],
+    [child, true],
+  ])
+  expect(window.isAnimating()).toBe(false)
+
+  // Removing the element cancels its animations too
+  ended.length = 0
+  const orphan = window.animate({ property: binding.TweenProperty.Opacity, element, to: 0, durationMs: 10000 }, onEnd)
+  window.removeElement(element)
+  await settle(() => ended.length === 1)
+  expect(ended).toEqual([[orphan, true]])
+  window.close()
+})
+
+test('window opacity is clamped and fades report completion', async () => {
+  const binding = loadBindingWithRetry()
+  const { app } = overlayApp()
+  if (!app) {
+    console.warn('⚠ No display available, skipping window opacity')
+    return
+  }
+  const window = app.createWindow({ ...binding.createWindowConfig(), width: 8, height: 8 })
+  const events: number[] = []
+  window.onEvent((err: Error | null, event: number) => events.push(event))
+  const settle = async (done: () => boolean) => {
+    for (let i = 0; i < 200 && !done(); i++) {
+      app.pollEvents()
+      await new Promise((resolve) => setTimeout(resolve, 5))
+    }
+  }
+
+  // Out of range values clamp to 0..1 and NaN is rejected
+  window.setOpacity(2)
+  expect(window.getOpacity()).toBe(1)
+  window.setOpacity(-1)
+  expect(window.getOpacity()).toBe(0)
+  window.setOpacity(0.5)
+  expect(window.getOpacity()).toBe(0.5)
+  try {
+    window.setOpacity(NaN)
+    throw new Error('NaN opacity was accepted')
+  } catch (error: any) {
+    expect(error.code).toBe(binding.ErrorCode.InvalidArg)
+  }
+  expect(window.getOpacity()).toBe(0.5)
+
+  // Fades run from the current opacity and emit an event once they land
+  window.fadeIn(20)
+  await settle(() => events.includes(binding.OverlayEvent.FadeInCompleted))
+  expect(events).toContain(binding.OverlayEvent.FadeInCompleted)
+  expect(window.getOpacity()).toBe(1)
+  window.fadeOut(20, binding.Easing.EaseOut)
+  await settle(() => events.includes(binding.OverlayEvent.FadeOutCompleted))
+  expect(window.getOpacity()).toBe(0)
+
+  // A zero-length fade lands immediately, and setOpacity cancels a running one
+  window.fadeIn(0)
+  expect(window.getOpacity()).toBe(1)
+  window.fadeOut(10000)
+  window.setOpacity(0.25)
+  await settle(() => events.includes(binding.OverlayEvent.FadeCancelled))
+  expect(events).toContain(binding.OverlayEvent.FadeCancelled)
+  expect(window.isAnimating()).toBe(false)
+  expect(window.getOpacity()).toBe(0.25)
+  window.close()
+})
+
+test('lines and circles are drawn with gradient paint', () => {
+  const binding = loadBindingWithRetry()
+  const size = 16
+  const pixel = (buffer: Buffer, x: number, y: number) => {
+    const offset = (y * size + x) * 4
+    return [...buffer.subarray(offset, offset + 4)]
+  }
+  const sample = (gradient: object, x: number, y: number) => {
+    const { r, g, b, a } = binding.sampleGradient(gradient, x, y)
+    return [r, g, b, a]
+  }
+  const covered = (buffer: Buffer) => {
+    const points: string[] = []
+    for (let i = 0; i < size * size; i++) if (buffer[i * 4 + 3]) points.push(`${i % size},${Math.floor(i / size)}`)
+    return points
+  }
+  const white = { r: 255, g: 255, b: 255, a: 255 }
+
+  // Every pixel of the line takes the gradient color at its own position
+  const linear = {
+    kind: binding.GradientKind.Linear,
+    x0: 0,
+    y0: 0,
+    x1: 15,
+    y1: 0,
+    stops: [
+      { offset: 0, color: { r: 255, g: 0, b: 0, a: 255 } },
+      { offset: 1, color: { r: 0, g: 0, b: 255, a: 255 } },
+    ],
+  }
+  const params = { x1: 0, y1: 8, x2: 15, y2: 8, bufferWidth: size, bufferHeight: size, color: white }
+  const line = binding.drawLineGradient(Buffer.alloc(size * size * 4), params, linear)
+  const solidLine = binding.drawLine(Buffer.alloc(size * size * 4), params)
+  expect(covered(line)).toEqual(covered(solidLine))
+  // Pixels are sampled at their centers, so the ends are nearly the end colors
+  expect(pixel(line, 0, 8)[0]).toBeGreaterThan(240)
+  expect(pixel(line, 15, 8)[2]).toBeGreaterThan(240)
+  for (let x = 0; x < size; x++) expect(pixel(line, x, 8)).toEqual(sample(linear, x, 8))
+
+  // The circle outline covers the same pixels as a solid one, colored by distance
+  const radial = {
+    kind: binding.GradientKind.Radial,
+    x0: 8,
+    y0: 8,
+    radius: 6,
+    stops: [
+      { offset: 0, color: white },
+      { offset: 1, color: { r: 0, g: 0, b: 0, a: 255 } },
+    ],
+  }
+  const circle = binding.drawCircleGradient(Buffer.alloc(size * size * 4), 8, 8, 5, size, size, radial)
+  const solidCircle = binding.drawCircle(Buffer.alloc(size * size * 4), 8, 8, 5, size, size, white)
+  expect(covered(circle)).toEqual(covered(solidCircle))
+  expect(pixel(circle, 8, 8)).toEqual([0, 0, 0, 0])
+  for (const point of covered(circle)) {
+    const [x, y] = point.split(',').map(Number)
+    expect(pixel(circle, x, y)).toEqual(sample(radial, x, y))
+  }
+
+  // Gradients are validated before anything is drawn
+  expect(() => binding.drawLineGradient(Buffer.alloc(size * size * 4), params, { ...linear, stops: [] })).toThrow()
+  expect(() => binding.drawCircleGradient(Buffer.alloc(4), 0, 0, 1, 1, 1, { ...radial, radius: undefined })).toThrow()
+})
+
+test('gradient stops are interpolated with premultiplied alpha', () => {
+  const binding = loadBindingWithRetry()
+  const red = { r: 255, g: 0, b: 0, a: 255 }
+  // Any color works as the transparent end; it must not tint the fade
+  const transparent = { r: 0, g: 0, b: 255, a: 0 }
+  for (const colorSpace of [binding.ColorSpace.Srgb, binding.ColorSpace.LinearRgb, binding.ColorSpace.Oklab]) {
+    const fade = {
+      kind: binding.GradientKind.Linear,
+      x0: 0,
+      y0: 0,
+      x1: 1,
+      y1: 0,
+      colorSpace,
+      stops: [
+        { offset: 0, color: red },
+        { offset: 1, color: transparent },
+      ],
+    }
+    // The pixel center at x = 0.5 is halfway along the gradient
+    expect(binding.sampleGradient(fade, 0, 0)).toEqual({ r: 255, g: 0, b: 0, a: 127 })
+  }
+})
+
+test('render retries back off, cap and suspend', () => {
+  const binding = loadBindingWithRetry()
+  if (!binding.simulateRenderFailures) {
+    console.warn('⚠ Needs the test-hooks feature, skipping the retry schedule')
+    return
+  }
+
+  // Defaults: doubling from 100 ms, suspended after 5 failures in a row
+  const defaults = binding.simulateRenderFailures({}, 5)
+  expect(defaults.backoffMs).toEqual([100, 200, 400, 800])
+  expect(defaults.status.consecutiveFailures).toBe(5)
+  expect(defaults.status.suspended).toBe(true)
+  expect(defaults.status.lastError).toBe('Simulated failure 5')
+  // Configuring again resumes with the full number of retries
+  expect(defaults.reconfigured.suspended).toBe(false)
+  expect(defaults.reconfigured.consecutiveFailures).toBe(0)
+
+  // The wait stops growing at maxBackoffMs, and 0 retries forever
+  const capped = binding.simulateRenderFailures({ maxRetries: 0, initialBackoffMs: 50, maxBackoffMs: 300 }, 6)
+  expect(capped.backoffMs).toEqual([50, 100, 200, 300, 300, 300])
+  expect(capped.status.suspended).toBe(false)
+  const long = binding.simulateRenderFailures({ maxRetries: 0, initialBackoffMs: 1000, maxBackoffMs: 4294967295 }, 40)
+  expect(long.backoffMs).toHaveLength(40)
+  expect(long.backoffMs[39]).toBe(1000 * 2 ** 16)
+
+  try {
+    binding.simulateRenderFailures({ initialBackoffMs: 500, maxBackoffMs: 100 }, 1)
+    throw new Error('An inverted backoff range was accepted')
+  } catch (error: any) {
+    expect(error.code).toBe(binding.ErrorCode.InvalidArg)
+    expect(error.message).toBe('maxBackoffMs (100) must be at least initialBackoffMs (500)')
+  }
+})
+
 test('CI environment verification', () => {
   try {
     console.log('=== Starting CI environment verification ===')
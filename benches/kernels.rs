@@ -0,0 +1,186 @@
+//! Compares the vectorized buffer functions with the per-pixel `Color`
+//! methods they replace, on one thread. Run with `cargo bench --bench kernels`.
+
+use overlay_napi::{
+  blend_row, get_simd_support, premultiply_in_place, set_render_threads, swizzle_rb_in_place,
+  unpremultiply_in_place, AlphaMode, Color,
+};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 1920;
+const HEIGHT: usize = 1080;
+const PIXELS: usize = WIDTH * HEIGHT;
+
+/// Deterministic pseudo-random pixels (xorshift32)
+fn noise(seed: u32, len: usize) -> Vec<u8> {
+  let mut state = seed;
+  (0..len)
+    .map(|_| {
+      state ^= state << 13;
+      state ^= state >> 17;
+      state ^= state << 5;
+      state as u8
+    })
+    .collect()
+}
+
+/// Random pixels with the color channels premultiplied by alpha
+fn premultiplied_noise(seed: u32, len: usize) -> Vec<u8> {
+  let mut data = noise(seed, len);
+  per_pixel(&mut data, |c| c.premultiply());
+  data
+}
+
+fn color(p: &[u8]) -> Color {
+  Color::new(p[0], p[1], p[2], p[3])
+}
+
+/// Apply a `Color` method to every pixel, as buffers were processed before
+/// the vectorized kernels
+fn per_pixel(pixels: &mut [u8], f: impl Fn(Color) -> Color) {
+  for p in pixels.chunks_exact_mut(4) {
+    p.copy_from_slice(&f(color(p)).to_rgba());
+  }
+}
+
+/// Blend every `src` pixel over `dst` with a `Color` method
+fn per_pixel_blend(src: &[u8], dst: &mut [u8], f: impl Fn(Color, Color) -> Color) {
+  for (s, d) in src.chunks_exact(4).zip(dst.chunks_exact_mut(4)) {
+    d.copy_from_slice(&f(color(s), color(d)).to_rgba());
+  }
+}
+
+/// Every (channel, alpha) pair once, so conversions are checked exhaustively
+fn all_pairs() -> Vec<u8> {
+  (0..=255u8)
+    .flat_map(|a| (0..=255u8).flat_map(move |c| [c, 255 - c, c / 2, a]))
+    .collect()
+}
+
+fn check(name: &str, expected: &[u8], actual: &[u8]) {
+  if let Some(index) = expected.iter().zip(actual).position(|(e, a)| e != a) {
+    let pixel = index / 4 * 4;
+    panic!(
+      "{}: mismatch at pixel {}: expected {:?}, got {:?}",
+      name,
+      index / 4,
+      &expected[pixel..pixel + 4],
+      &actual[pixel..pixel + 4]
+    );
+  }
+}
+
+/// Mean time per call over enough iterations to run for ~0.5s
+fn time(mut run: impl FnMut()) -> Duration {
+  run();
+  let mut iterations = 0u32;
+  let start = Instant::now();
+  while start.elapsed() < Duration::from_millis(500) {
+    run();
+    iterations += 1;
+  }
+  start.elapsed() / iterations
+}
+
+fn report(name: &str, per_pixel: Duration, vector: Duration) {
+  println!(
+    "{:<22} per pixel {:>9.3} ms   {:<6} {:>9.3} ms   {:>5.2}x",
+    name,
+    per_pixel.as_secs_f64() * 1e3,
+    get_simd_support(),
+    vector.as_secs_f64() * 1e3,
+    per_pixel.as_secs_f64() / vector.as_secs_f64()
+  );
+}
+
+fn bench_map(name: &str, input: &[u8], reference: impl Fn(&mut [u8]), kernel: fn(&mut [u8])) {
+  for data in [all_pairs(), input.to_vec()] {
+    let (mut expected, mut actual) = (data.clone(), data);
+    reference(&mut expected);
+    kernel(&mut actual);
+    check(name, &expected, &actual);
+  }
+
+  let mut buffer = input.to_vec();
+  let reference_time = time(|| {
+    buffer.copy_from_slice(input);
+    reference(black_box(&mut buffer));
+  });
+  let vector_time = time(|| {
+    buffer.copy_from_slice(input);
+    kernel(black_box(&mut buffer));
+  });
+  report(name, reference_time, vector_time);
+}
+
+fn bench_blend(
+  name: &str,
+  src: &[u8],
+  dst: &[u8],
+  reference: impl Fn(&[u8], &mut [u8]),
+  kernel: impl Fn(&[u8], &mut [u8]),
+) {
+  let (mut expected, mut actual) = (dst.to_vec(), dst.to_vec());
+  reference(src, &mut expected);
+  kernel(src, &mut actual);
+  check(name, &expected, &actual);
+
+  let mut buffer = dst.to_vec();
+  let reference_time = time(|| {
+    buffer.copy_from_slice(dst);
+    reference(black_box(src), black_box(&mut buffer));
+  });
+  let vector_time = time(|| {
+    buffer.copy_from_slice(dst);
+    kernel(black_box(src), black_box(&mut buffer));
+  });
+  report(name, reference_time, vector_time);
+}
+
+fn main() {
+  // Measure the kernels, not the row-parallel split
+  set_render_threads(1);
+  println!(
+    "{}x{} RGBA frame, kernels: {}",
+    WIDTH,
+    HEIGHT,
+    get_simd_support()
+  );
+
+  let straight = noise(1, PIXELS * 4);
+  let premultiplied = premultiplied_noise(2, PIXELS * 4);
+  bench_map(
+    "premultiply",
+    &straight,
+    |pixels| per_pixel(pixels, |c| c.premultiply()),
+    premultiply_in_place,
+  );
+  bench_map(
+    "unpremultiply",
+    &premultiplied,
+    |pixels| per_pixel(pixels, |c| c.unpremultiply()),
+    unpremultiply_in_place,
+  );
+  bench_map(
+    "swizzle rgba<->bgra",
+    &straight,
+    |pixels| per_pixel(pixels, |c| Color::new(c.b, c.g, c.r, c.a)),
+    swizzle_rb_in_place,
+  );
+
+  bench_blend(
+    "blend straight",
+    &straight,
+    &noise(3, PIXELS * 4),
+    |src, dst| per_pixel_blend(src, dst, |s, d| s.blend(&d)),
+    |src, dst| blend_row(src, dst, AlphaMode::Straight),
+  );
+  bench_blend(
+    "blend premultiplied",
+    &premultiplied,
+    &premultiplied_noise(4, PIXELS * 4),
+    |src, dst| per_pixel_blend(src, dst, |s, d| s.blend_premultiplied(&d)),
+    |src, dst| blend_row(src, dst, AlphaMode::Premultiplied),
+  );
+}
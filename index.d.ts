@@ -1,8 +1,121 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+/**
+ * A clip stack for drawing into caller-owned buffers of one size, clipping
+ * the same way as the `pushClip*` methods of a window
+ */
+export declare class BufferClip {
+  constructor(width: number, height: number)
+  /** Limit drawing to a rectangle, intersected with the current clip */
+  pushRect(rect: Rect): void
+  /** Limit drawing to an anti-aliased rounded rectangle */
+  pushRoundedRect(rect: Rect, radius: number): void
+  /** Limit drawing by an 8-bit coverage mask */
+  pushMask(mask: ClipMask): void
+  /** Remove the most recently pushed clip; returns false when none was set */
+  pop(): boolean
+  /** Remove all clips */
+  reset(): void
+  getDepth(): number
+  /** Apply draw commands to the caller's buffer within the current clip */
+  drawBatch(buffer: Buffer, layout: BufferLayout, commands: Array<DrawCommand>): void
+}
+
+/**
+ * A 2D drawing context in the spirit of the HTML canvas API. Drawing is
+ * source-over blended and not anti-aliased; gradients are sampled in canvas
+ * pixel coordinates.
+ */
+export declare class Canvas {
+  /** Create a canvas over its own transparent RGBA buffer */
+  constructor(width: number, height: number)
+  /** Create a canvas that draws directly into the caller's buffer */
+  static fromBuffer(buffer: Buffer, layout: BufferLayout): Canvas
+  getWidth(): number
+  getHeight(): number
+  /** Copy of the pixels this canvas draws into */
+  toBuffer(): Buffer
+  /** Push the transform, clip and styles onto the state stack */
+  save(): void
+  /** Pop the state stack; does nothing when it is empty */
+  restore(): void
+  translate(x: number, y: number): void
+  scale(x: number, y: number): void
+  /** Rotate clockwise by `angle` radians */
+  rotate(angle: number): void
+  /** Multiply the current transform by `[a c e; b d f]` */
+  transform(a: number, b: number, c: number, d: number, e: number, f: number): void
+  setTransform(a: number, b: number, c: number, d: number, e: number, f: number): void
+  resetTransform(): void
+  /** Current transform as `[a, b, c, d, e, f]` */
+  getTransform(): Array<number>
+  /**
+   * Intersect the clip with a rectangle. Under rotation the rectangle's
+   * bounding box is used.
+   */
+  clipRect(x: number, y: number, width: number, height: number): void
+  setFillStyle(color: Color): void
+  setFillGradient(gradient: Gradient): void
+  setStrokeStyle(color: Color): void
+  setStrokeGradient(gradient: Gradient): void
+  /** Stroke width in user units; non-positive values are ignored */
+  setLineWidth(width: number): void
+  getLineWidth(): number
+  /** Opacity multiplied into every fill and stroke (0..1) */
+  setGlobalAlpha(alpha: number): void
+  getGlobalAlpha(): number
+  /** Discard the current path */
+  beginPath(): void
+  moveTo(x: number, y: number): void
+  lineTo(x: number, y: number): void
+  quadraticCurveTo(cpx: number, cpy: number, x: number, y: number): void
+  bezierCurveTo(cp1X: number, cp1Y: number, cp2X: number, cp2Y: number, x: number, y: number): void
+  /**
+   * Circular arc around `(x, y)`, connected to the current point by a line.
+   * Angles are in radians, clockwise unless `counterclockwise` is set.
+   */
+  arc(
+    x: number,
+    y: number,
+    radius: number,
+    startAngle: number,
+    endAngle: number,
+    counterclockwise?: boolean | undefined | null,
+  ): void
+  /** Add a closed rectangle subpath */
+  rect(x: number, y: number, width: number, height: number): void
+  closePath(): void
+  /** Fill the current path with the fill style */
+  fill(rule?: FillRule | undefined | null): void
+  /** Stroke the current path with the stroke style and line width */
+  stroke(): void
+  /** Fill a rectangle without touching the current path */
+  fillRect(x: number, y: number, width: number, height: number): void
+  /** Stroke a rectangle without touching the current path */
+  strokeRect(x: number, y: number, width: number, height: number): void
+  /** Make a rectangle fully transparent */
+  clearRect(x: number, y: number, width: number, height: number): void
+}
+
 /** Application manager for the overlay system */
 export declare class OverlayApp {
+  /**
+   * Fails with the `EventLoopUnavailable` error code when there is no
+   * display, or an event loop was already created in this process
+   */
   constructor()
+  /**
+   * Which platform features work on the current platform and windowing
+   * backend
+   */
+  getCapabilities(): Capabilities
+  /**
+   * In strict mode, calls to features the platform doesn't support fail with
+   * an `InvalidArg` error instead of doing nothing. Applies to existing and
+   * new windows.
+   */
+  setStrictMode(strict: boolean): void
+  isStrictMode(): boolean
   /** Create a new window with the given configuration */
   createWindow(config: WindowConfig): OverlayWindow
   /** Poll events once and return (non-blocking) */
@@ -15,8 +128,17 @@ export declare class OverlayApp {
 export declare class OverlayWindow {
   /** Register an event callback */
   onEvent(callback: (err: Error | null, arg: OverlayEvent) => any): void
-  /** Update frame with optimized buffer handling */
-  updateFrame(buffer: Buffer): void
+  /**
+   * Register a callback called with the new frame size after the window is
+   * resized. The next present waits until it returns (up to 100 ms), so a
+   * synchronous repaint shows without a stale or blank frame.
+   */
+  onResize(callback: (err: Error | null, arg: WindowSize) => any): void
+  /**
+   * Update frame with optimized buffer handling. Pixels in other formats or
+   * with padded rows are converted into the frame natively.
+   */
+  updateFrame(buffer: Buffer, format?: PixelFormat | undefined | null, stride?: number | undefined | null): void
   /** Get frame size */
   getFrameSize(): Array<number>
   /** Window visibility controls */
@@ -26,6 +148,11 @@ export declare class OverlayWindow {
   maximize(): void
   restore(): void
   isVisible(): boolean
+  /**
+   * Close the window. Later calls that need it fail with the
+   * `WindowDestroyed` error code.
+   */
+  close(): void
   /** Window positioning */
   setPosition(x: number, y: number): void
   getPosition(): WindowPosition
@@ -42,6 +169,13 @@ export declare class OverlayWindow {
   setIgnoreMouseEvents(ignore: boolean): void
   setRenderWhenOccluded(render: boolean): void
   isOccluded(): boolean
+  /**
+   * Limit how failed presents are retried after a lost surface or GPU
+   * device, and resume presenting if retries had run out
+   */
+  setRenderRecovery(options: RenderRecoveryOptions): void
+  /** Failed presents, recoveries and lost devices so far */
+  getRenderStatus(): RenderStatus
   /**
    * Set whether the window should be excluded from screen capture (OBS, etc)
    * When set to false (default), capture tools can capture this window
@@ -52,22 +186,211 @@ export declare class OverlayWindow {
   isExcludedFromCapture(): boolean
   /** Set whether the window should be shown in the taskbar */
   setSkipTaskbar(skip: boolean): void
+  /**
+   * Limit drawing and input to a shape in window pixels, or restore the full
+   * window with null. Returns false where the platform can't shape windows
+   * (only Windows can at the moment).
+   */
+  setShape(shape?: WindowShape | undefined | null): boolean
+  /**
+   * Round the window corners; used while no explicit shape is set. Returns
+   * false where the platform can't shape windows.
+   */
+  setCornerRadius(radius: number): boolean
+  getCornerRadius(): number
+  /**
+   * Set the window and taskbar icons; the image is scaled to the square sizes
+   * the platform uses. Pass null to restore the default icon. Returns false
+   * where the platform doesn't show window icons.
+   */
+  setIcon(image?: DecodedImage | undefined | null): boolean
+  /**
+   * Show progress (0..1) on the taskbar button. Returns false where the
+   * platform has no taskbar progress (only Windows does at the moment).
+   */
+  setProgress(value: number, state?: ProgressState | undefined | null): boolean
+  /** Set the whole-window opacity (0..1), applied when the frame is composited */
+  setOpacity(opacity: number): void
+  getOpacity(): number
+  /** Fade the window in to full opacity; emits `FadeInCompleted` when done */
+  fadeIn(durationMs: number, easing?: Easing | undefined | null): void
+  /** Fade the window out to zero opacity; emits `FadeOutCompleted` when done */
+  fadeOut(durationMs: number, easing?: Easing | undefined | null): void
+  /**
+   * Animate a window or element property natively; returns the animation id.
+   * The callback receives the id once the animation ends, followed by `true`
+   * when it was cancelled instead of completing.
+   */
+  animate(
+    options: TweenOptions,
+    callback?: ((err: Error | null, arg0: number, arg1: boolean) => any) | undefined | null,
+  ): number
+  /** Cancel an animation along with any animations chained after it */
+  cancelAnimation(id: number): boolean
+  cancelAllAnimations(): void
+  isAnimating(): boolean
+  /** Retained elements, drawn over the frame on every composite */
+  addElement(options: ElementOptions): number
+  updateElement(id: number, options: ElementOptions): void
+  removeElement(id: number): boolean
+  clearElements(): void
   /** Frame operations */
   clearFrame(color: Color): void
   drawRectangle(x: number, y: number, width: number, height: number, color: Color): void
-  drawImage(x: number, y: number, image: DecodedImage): void
+  /** Clear the whole frame with a gradient */
+  clearFrameGradient(gradient: Gradient): void
+  drawRectangleGradient(x: number, y: number, width: number, height: number, gradient: Gradient): void
+  /** Copy an image into the frame, running `filters` on it first */
+  drawImage(x: number, y: number, image: DecodedImage, filters?: Array<Filter> | undefined | null): void
+  /** Draw an image registered with `registerImage` */
+  drawSprite(id: string, x: number, y: number, options?: SpriteDrawOptions | undefined | null): void
+  /** Filter a region of the frame in place (the whole frame by default) */
+  applyFilters(filters: Array<Filter>, region?: Rect | undefined | null): void
+  /**
+   * Limit all following frame drawing to a rectangle, intersected with the
+   * current clip
+   */
+  pushClipRect(rect: Rect): void
+  /** Limit all following frame drawing to an anti-aliased rounded rectangle */
+  pushClipRoundedRect(rect: Rect, radius: number): void
+  /** Limit all following frame drawing by an 8-bit coverage mask */
+  pushClipMask(mask: ClipMask): void
+  /** Remove the most recently pushed clip; returns false when none was set */
+  popClip(): boolean
+  /** Remove all clips */
+  resetClip(): void
+  getClipDepth(): number
+  /** Replace the frame with an I420 or NV12 video frame, scaled to fit */
+  updateFrameYuv(frame: YuvFrame): void
+  /** Draw an I420 or NV12 video frame into a region of the frame */
+  drawYuvImage(frame: YuvFrame, options?: YuvDrawOptions | undefined | null): void
+  /** Set the alpha convention of the frame; drawing and compositing follow it */
+  setAlphaMode(mode: AlphaMode): void
+  getAlphaMode(): AlphaMode
+  /**
+   * Choose where elements and opacity are composited. `Auto` uses the GPU
+   * on hardware adapters; the CPU is used whenever the GPU path fails.
+   */
+  setCompositor(backend: CompositorBackend): void
+  /** The backend in use: `Gpu` or `Cpu` */
+  getCompositor(): CompositorBackend
+  /**
+   * Choose how the frame maps onto the window when their sizes differ.
+   * Switching to `MatchWindow` resizes the frame to the window, keeping its
+   * content.
+   */
+  setScaleMode(mode: ScaleMode): void
+  getScaleMode(): ScaleMode
+  /**
+   * Choose what happens to the frame contents when the frame follows a
+   * window resize
+   */
+  setResizePolicy(policy: ResizePolicy): void
+  getResizePolicy(): ResizePolicy
+  /**
+   * Draw a list of commands under a single lock with one redraw. The whole
+   * list is validated first; errors name the offending command index.
+   */
+  drawCommands(commands: Array<DrawCommand>): void
+  /**
+   * Like `drawCommands`, with commands in the binary encoding produced by
+   * `encodeDrawCommands`
+   */
+  drawCommandsEncoded(commands: Buffer): void
+  /** Canvas drawing context that draws into this window's frame */
+  getCanvas(): Canvas
   /** Get the current frame buffer */
   getFrameBuffer(): Buffer
   /** Manually trigger a render */
   render(): void
-  /** Resize the frame buffer and window */
+  /**
+   * Resize the frame buffer and window, carrying the frame contents over
+   * per the resize policy
+   */
   resize(width: number, height: number): void
 }
 
+/** How the color channels of an RGBA buffer relate to its alpha channel */
+export declare const enum AlphaMode {
+  /** Color channels are independent of alpha (the default for frames and images) */
+  Straight = 0,
+  /** Color channels are already multiplied by alpha */
+  Premultiplied = 1,
+}
+
+/** Eased progress for linear progress `t`, which is clamped to 0..1 */
+export declare function applyEasing(easing: Easing, t: number): number
+
+/**
+ * Apply filters in order to the caller's straight RGBA buffer, optionally
+ * limited to a region
+ */
+export declare function applyFilters(
+  buffer: Buffer,
+  layout: BufferLayout,
+  filters: Array<Filter>,
+  region?: Rect | undefined | null,
+): void
+
 export declare function blendColors(foreground: Color, background: Color): Color
 
+/** Blend foreground over background in linear light */
+export declare function blendColorsLinear(foreground: Color, background: Color): Color
+
+/** Blend premultiplied-alpha colors */
+export declare function blendColorsPremultiplied(foreground: Color, background: Color): Color
+
+/** Layout of a caller-owned buffer */
+export interface BufferLayout {
+  width: number
+  height: number
+  /** Bytes per row; defaults to tightly packed rows */
+  stride?: number
+  /** Pixel format of the buffer; defaults to `Rgba8` */
+  format?: PixelFormat
+}
+
 export declare function calculateBufferSizeNapi(width: number, height: number): number
 
+/** Features supported on the current platform and windowing backend */
+export interface Capabilities {
+  /** Operating system as Rust names it: `windows`, `macos`, `linux`, ... */
+  platform: string
+  /** Windowing backend: `win32`, `appkit`, `x11`, `wayland` or `other` */
+  backend: string
+  /** Windows can be created; when false, every feature below is false too */
+  windowCreation: boolean
+  /** `setExcludeFromCapture` hides the window from screen capture */
+  captureExclusion: boolean
+  /** `setSkipTaskbar` hides the taskbar button */
+  skipTaskbar: boolean
+  /** `setIgnoreMouseEvents` passes input through the window */
+  clickThrough: boolean
+  /** Transparent pixels show what is behind the window */
+  transparency: boolean
+  /** `WindowLevel.AlwaysOnBottom` keeps the window below others */
+  alwaysOnBottom: boolean
+  /** `setShape` and `setCornerRadius` limit where the window takes input */
+  perPixelHitTest: boolean
+  /** `setIcon` changes the window icon */
+  windowIcon: boolean
+  /** `setProgress` shows progress on the taskbar button */
+  taskbarProgress: boolean
+}
+
+export declare function clearImages(): void
+
+/** An 8-bit coverage mask, one byte per pixel, placed at `(x, y)` in the frame */
+export interface ClipMask {
+  data: Buffer
+  x: number
+  y: number
+  width: number
+  height: number
+  /** Bytes per row; tightly packed when omitted */
+  stride?: number
+}
+
 export interface Color {
   r: number
   g: number
@@ -83,6 +406,14 @@ export declare function colorCyan(): Color
 
 export declare function colorDarkGray(): Color
 
+export declare function colorFromHsl(hsl: HslColor): Color
+
+export declare function colorFromHsv(hsv: HsvColor): Color
+
+export declare function colorFromLinear(linear: LinearRgbColor): Color
+
+export declare function colorFromOklab(oklab: OklabColor): Color
+
 export declare function colorGray(): Color
 
 export declare function colorGreen(): Color
@@ -97,8 +428,32 @@ export declare function colorPink(): Color
 
 export declare function colorRed(): Color
 
+/** Color spaces available for interpolation */
+export declare const enum ColorSpace {
+  /** Interpolate the gamma-encoded sRGB bytes directly */
+  Srgb = 0,
+  /** Interpolate in linear light (gamma-correct) */
+  LinearRgb = 1,
+  /** Interpolate in the perceptually uniform OKLab space */
+  Oklab = 2,
+}
+
+export interface ColorStop {
+  /** Position along the gradient (0..1) */
+  offset: number
+  color: Color
+}
+
 export declare function colorToHex(color: Color): string
 
+export declare function colorToHsl(color: Color): HslColor
+
+export declare function colorToHsv(color: Color): HsvColor
+
+export declare function colorToLinear(color: Color): LinearRgbColor
+
+export declare function colorToOklab(color: Color): OklabColor
+
 export declare function colorToRgba(color: Color): Array<number>
 
 export declare function colorToRgbHex(color: Color): string
@@ -109,8 +464,59 @@ export declare function colorWhite(): Color
 
 export declare function colorYellow(): Color
 
+/**
+ * Composite layers over a copy of an RGBA frame and return it tightly
+ * packed. Uses the GPU when an adapter is available and falls back to the
+ * CPU otherwise, including for frames too large for the adapter and after
+ * the adapter fails; both produce the same result up to rounding.
+ */
+export declare function compositeLayers(
+  frame: Buffer,
+  layout: BufferLayout,
+  layers: Array<ElementOptions>,
+  options?: CompositeOptions | undefined | null,
+): Buffer
+
+export interface CompositeOptions {
+  /** Opacity applied to the result (0..1, default 1) */
+  opacity?: number
+  alphaMode?: AlphaMode
+  backend?: CompositorBackend
+}
+
+/** Where elements and opacity are composited */
+export declare const enum CompositorBackend {
+  /** GPU on hardware adapters, CPU on software adapters or without one */
+  Auto = 0,
+  Cpu = 1,
+  /** GPU whenever an adapter is available, including software ones */
+  Gpu = 2,
+}
+
+/** The rectangles of a window with rounded corners */
+export declare function computeRoundedRegion(width: number, height: number, radius: number): Array<Rect>
+
+/** The rectangles a shape reduces to, as passed to the platform */
+export declare function computeShapeRegion(shape: WindowShape): Array<Rect>
+
+/** Convert pixels between formats; the result has tightly packed rows */
+export declare function convertPixels(
+  buffer: Buffer,
+  width: number,
+  height: number,
+  from: PixelFormat,
+  to: PixelFormat,
+  stride?: number | undefined | null,
+): Buffer
+
+/** Convert a YUV frame to a tightly packed RGBA buffer at its own size */
+export declare function convertYuvToRgba(frame: YuvFrame): Buffer
+
 export declare function createColor(r: number, g: number, b: number, a: number): Color
 
+/** The square icon sizes generated from an image, as `setIcon` uses them */
+export declare function createIconVariants(image: DecodedImage): Array<IconVariant>
+
 export declare function createPosition(x: number, y: number): WindowPosition
 
 export declare function createRgbaBuffer(width: number, height: number): Buffer
@@ -123,8 +529,18 @@ export interface DecodedImage {
   data: Buffer
   width: number
   height: number
+  /** Layout of `data` (default RGBA8) */
+  format?: PixelFormat
+  /** Bytes per row of `data`; tightly packed when omitted */
+  stride?: number
 }
 
+/** Apply a list of draw commands to the caller's buffer in one call */
+export declare function drawBatch(buffer: Buffer, layout: BufferLayout, commands: Array<DrawCommand>): void
+
+/** Apply a binary-encoded command list to the caller's buffer in one call */
+export declare function drawBatchEncoded(buffer: Buffer, layout: BufferLayout, commands: Buffer): void
+
 export declare function drawCircle(
   buffer: Buffer,
   cx: number,
@@ -135,14 +551,318 @@ export declare function drawCircle(
   color: Color,
 ): Buffer
 
+export declare function drawCircleGradient(
+  buffer: Buffer,
+  cx: number,
+  cy: number,
+  radius: number,
+  bufferWidth: number,
+  bufferHeight: number,
+  gradient: Gradient,
+): Buffer
+
+export declare function drawCircleInPlace(
+  buffer: Buffer,
+  layout: BufferLayout,
+  cx: number,
+  cy: number,
+  radius: number,
+  color: Color,
+): void
+
+export interface DrawCommand {
+  kind: DrawCommandKind
+  /** Pixel position, line start, rectangle corner or circle center */
+  x?: number
+  y?: number
+  /** Line end point */
+  x2?: number
+  y2?: number
+  width?: number
+  height?: number
+  radius?: number
+  color: Color
+}
+
+export declare const enum DrawCommandKind {
+  /** Fill the whole surface */
+  Fill = 0,
+  Pixel = 1,
+  Line = 2,
+  Rectangle = 3,
+  Circle = 4,
+  FillCircle = 5,
+}
+
 export declare function drawLine(buffer: Buffer, params: LineParams): Buffer
 
+export declare function drawLineGradient(buffer: Buffer, params: LineParams, gradient: Gradient): Buffer
+
+export declare function drawLineInPlace(
+  buffer: Buffer,
+  layout: BufferLayout,
+  x1: number,
+  y1: number,
+  x2: number,
+  y2: number,
+  color: Color,
+): void
+
 export declare function drawPixel(buffer: Buffer, x: number, y: number, width: number, color: Color): Buffer
 
-export declare function fillBufferColor(buffer: Buffer, color: Color): Buffer
+export declare function drawPixelInPlace(buffer: Buffer, layout: BufferLayout, x: number, y: number, color: Color): void
+
+export declare function drawRectangleInPlace(
+  buffer: Buffer,
+  layout: BufferLayout,
+  x: number,
+  y: number,
+  width: number,
+  height: number,
+  color: Color,
+): void
+
+/** Draw a registered image into the caller's straight-alpha buffer */
+export declare function drawSpriteInPlace(
+  buffer: Buffer,
+  layout: BufferLayout,
+  id: string,
+  x: number,
+  y: number,
+  options?: SpriteDrawOptions | undefined | null,
+): void
+
+export declare const enum Easing {
+  Linear = 0,
+  /** Cubic ease-in */
+  EaseIn = 1,
+  /** Cubic ease-out */
+  EaseOut = 2,
+  /** Cubic ease-in-out */
+  EaseInOut = 3,
+  /** Damped spring that overshoots slightly before settling */
+  Spring = 4,
+}
+
+export interface ElementOptions {
+  x?: number
+  y?: number
+  width?: number
+  height?: number
+  color?: Color
+  opacity?: number
+  /**
+   * Id of a registered image drawn scaled into the element instead of a
+   * filled rectangle; an empty string switches back to the color
+   */
+  image?: string
+}
+
+/** Encode command objects into the compact binary form */
+export declare function encodeDrawCommands(commands: Array<DrawCommand>): Buffer
+
+/**
+ * Every adapter the system offers, software ones included, as listed on
+ * first use. When this isn't empty but `getGpuAdapter()` is null, the
+ * compositor couldn't be set up.
+ */
+export declare function enumerateGpuAdapters(): Array<GpuAdapterInfo>
+
+/** The `code` of errors thrown by windows and the app */
+export declare enum ErrorCode {
+  /** The window hasn't been created yet */
+  WindowNotInitialized = 'WindowNotInitialized',
+  /** The window was closed */
+  WindowDestroyed = 'WindowDestroyed',
+  /**
+   * A frame buffer doesn't match the frame size; has `expected` and `got`
+   * byte counts
+   */
+  BufferSizeMismatch = 'BufferSizeMismatch',
+  /** The event loop was handed to `run()` or couldn't be created */
+  EventLoopUnavailable = 'EventLoopUnavailable',
+  /** `run()` was called while the event loop is already running */
+  EventLoopRunning = 'EventLoopRunning',
+  /** `run()` was called before any window was created */
+  NoWindows = 'NoWindows',
+  /**
+   * Strict mode rejected a call the platform can't do; has `feature`,
+   * `platform` and `backend`
+   */
+  Unsupported = 'Unsupported',
+  /** The window surface was lost and couldn't be presented to */
+  SurfaceLost = 'SurfaceLost',
+  /** A native platform call failed */
+  PlatformError = 'PlatformError',
+  /**
+   * A panic interrupted an earlier call; the state it left was repaired
+   * and the call can be retried
+   */
+  StatePoisoned = 'StatePoisoned',
+  /** An argument was out of range or malformed */
+  InvalidArg = 'InvalidArg',
+  /** Any other failure */
+  GenericFailure = 'GenericFailure',
+}
+
+/**
+ * A copy of the buffer filled with a color, as pixels in `format`
+ * (default `Rgba8`)
+ */
+export declare function fillBufferColor(buffer: Buffer, color: Color, format?: PixelFormat | undefined | null): Buffer
+
+/** Fill the caller's buffer with a color without copying it */
+export declare function fillBufferColorInPlace(buffer: Buffer, layout: BufferLayout, color: Color): void
+
+export declare function fillBufferGradient(buffer: Buffer, width: number, height: number, gradient: Gradient): Buffer
+
+/** Rule deciding which regions of a self-intersecting path are inside */
+export declare const enum FillRule {
+  NonZero = 0,
+  EvenOdd = 1,
+}
+
+export interface Filter {
+  kind: FilterKind
+  radius?: number
+  amount?: number
+  color?: Color
+  /** Drop shadow offset */
+  offsetX?: number
+  offsetY?: number
+  matrix?: Array<number>
+}
+
+export declare const enum FilterKind {
+  /** Average over a square of `radius` pixels around each pixel */
+  BoxBlur = 0,
+  /** Gaussian blur with standard deviation `radius` */
+  GaussianBlur = 1,
+  /** Blurred copy of the shape in `color`, offset and drawn underneath */
+  DropShadow = 2,
+  /** Shape grown by `radius` pixels in `color`, drawn underneath */
+  Outline = 3,
+  /** Shape blurred by `radius` in `color`, drawn underneath */
+  Glow = 4,
+  /** Desaturate by `amount` (0 to 1, default 1) */
+  Grayscale = 5,
+  /** Multiply color channels by `amount` */
+  Brightness = 6,
+  /** Scale color channels away from mid-gray by `amount` */
+  Contrast = 7,
+  /** Mix color channels toward `color` by its alpha */
+  Tint = 8,
+  /** 4x5 row-major `matrix` applied to `[r, g, b, a, 1]` in 0 to 1 units */
+  ColorMatrix = 9,
+}
+
+/** The adapter `compositeLayers` uses, or null when there is none */
+export declare function getGpuAdapter(): GpuAdapterInfo | null
+
+/**
+ * Capabilities of the platform windows would be created on, without opening
+ * a display; `OverlayApp.getCapabilities()` asks the actual display
+ */
+export declare function getPlatformCapabilities(): Capabilities
+
+/** Number of threads large fills, blits and blends are split across */
+export declare function getRenderThreads(): number
+
+/**
+ * The rectangle of the surface a frame is drawn into under `mode`; it may
+ * extend past the surface, which crops the frame
+ */
+export declare function getScalePlacement(
+  mode: ScaleMode,
+  frameWidth: number,
+  frameHeight: number,
+  surfaceWidth: number,
+  surfaceHeight: number,
+): Rect
+
+/** Instruction set used by the pixel kernels: "avx2", "sse2", "neon" or "scalar" */
+export declare function getSimdSupport(): string
+
+export declare function getSpriteStats(): SpriteStats
+
+export interface GpuAdapterInfo {
+  name: string
+  /** Graphics API, e.g. "Vulkan" or "Gl" */
+  backend: string
+  /** "DiscreteGpu", "IntegratedGpu", "VirtualGpu", "Cpu" or "Other" */
+  deviceType: string
+}
+
+export interface Gradient {
+  kind: GradientKind
+  /** Start point for linear gradients, center for radial and conic ones */
+  x0: number
+  y0: number
+  /** End point, required for linear gradients */
+  x1?: number
+  y1?: number
+  /** Outer radius, required for radial gradients */
+  radius?: number
+  /** Radius at which a radial gradient starts (default 0) */
+  innerRadius?: number
+  /** Start angle in degrees for conic gradients (default 0, pointing right) */
+  angle?: number
+  stops: Array<ColorStop>
+  spread?: SpreadMode
+  /** Apply ordered dithering to reduce banding */
+  dither?: boolean
+  /**
+   * Color space the stops are interpolated in (default sRGB), with
+   * premultiplied alpha
+   */
+  colorSpace?: ColorSpace
+}
+
+export declare const enum GradientKind {
+  Linear = 0,
+  Radial = 1,
+  Conic = 2,
+}
+
+/** Hue in degrees (0..360), saturation, lightness and alpha in 0..1 */
+export interface HslColor {
+  h: number
+  s: number
+  l: number
+  a: number
+}
+
+/** Hue in degrees (0..360), saturation, value and alpha in 0..1 */
+export interface HsvColor {
+  h: number
+  s: number
+  v: number
+  a: number
+}
+
+/** One square size of an icon, as packed RGBA */
+export interface IconVariant {
+  data: Buffer
+  width: number
+  height: number
+}
+
+/** Whether `setProgress` shows anything on this platform */
+export declare function isTaskbarProgressSupported(): boolean
 
 export declare function lerpColors(color1: Color, color2: Color, t: number): Color
 
+/** Interpolate two colors in the given color space */
+export declare function lerpColorsIn(color1: Color, color2: Color, t: number, space: ColorSpace): Color
+
+/** Linear-light sRGB channels and alpha in 0..1 */
+export interface LinearRgbColor {
+  r: number
+  g: number
+  b: number
+  a: number
+}
+
 export interface LineParams {
   x1: number
   y1: number
@@ -153,6 +873,21 @@ export interface LineParams {
   color: Color
 }
 
+export declare const enum MaskFormat {
+  /** One byte per pixel */
+  Alpha8 = 0,
+  /** One bit per pixel, most significant bit first, rows padded to whole bytes */
+  Bit1 = 1,
+}
+
+/** OKLab lightness (0..1), `a`/`b` opponent axes and alpha (0..1) */
+export interface OklabColor {
+  l: number
+  a: number
+  b: number
+  alpha: number
+}
+
 export declare const enum OverlayEvent {
   Resized = 0,
   Moved = 1,
@@ -165,6 +900,248 @@ export declare const enum OverlayEvent {
   Restored = 8,
   MouseEnter = 9,
   MouseLeave = 10,
+  FadeInCompleted = 11,
+  FadeOutCompleted = 12,
+  /**
+   * Presenting failed, or the GPU reported a validation error; failures are
+   * retried with backoff, see `getRenderStatus()`
+   */
+  RenderError = 13,
+  /** The GPU device was lost and is being created again */
+  DeviceLost = 14,
+  /** A fade was cancelled before completing, e.g. by `setOpacity` or another fade */
+  FadeCancelled = 15,
+}
+
+/** Parse a CSS color string */
+export declare function parseColor(input: string): Color
+
+export declare const enum PixelFormat {
+  Rgba8 = 0,
+  Bgra8 = 1,
+  Rgb8 = 2,
+  Bgr8 = 3,
+  /** 8-bit luminance, opaque */
+  Gray8 = 4,
+  /** 8-bit luminance followed by 8-bit alpha */
+  GrayAlpha8 = 5,
+  /** 16-bit little-endian 5:6:5 RGB, opaque */
+  Rgb565 = 6,
+}
+
+export declare function premultiplyBuffer(buffer: Buffer): Buffer
+
+export declare function premultiplyColor(color: Color): Color
+
+export declare const enum ProgressState {
+  /** No progress is shown */
+  None = 0,
+  Normal = 1,
+  /** Progress of unknown length; the value is ignored */
+  Indeterminate = 2,
+  Paused = 3,
+  Error = 4,
+}
+
+/** A rectangle in pixels */
+export interface Rect {
+  x: number
+  y: number
+  width: number
+  height: number
+}
+
+/**
+ * Store an image natively under `id` so it can be drawn with `drawSprite`
+ * without sending its pixels again. Re-registering an id replaces it.
+ */
+export declare function registerImage(id: string, image: DecodedImage): void
+
+export interface RenderRecoveryOptions {
+  /**
+   * Failed presents in a row before presenting stops; 0 retries forever.
+   * Defaults to 5.
+   */
+  maxRetries?: number
+  /**
+   * Wait before the first retry in milliseconds, doubled for each further
+   * failure. Defaults to 100.
+   */
+  initialBackoffMs?: number
+  /** Longest wait between retries in milliseconds. Defaults to 5000. */
+  maxBackoffMs?: number
+}
+
+/** How presenting to a window is doing */
+export interface RenderStatus {
+  /** Presents that failed in a row */
+  consecutiveFailures: number
+  /** Times the surface and device were created again */
+  recoveries: number
+  /** Times the GPU device was lost */
+  deviceLosses: number
+  /** Message of the most recent failure or GPU validation error */
+  lastError?: string
+  /**
+   * Retries ran out; presenting resumes after a resize or
+   * `setRenderRecovery()`
+   */
+  suspended: boolean
+}
+
+/**
+ * A copy of a packed RGBA frame carried over to a new size the way a
+ * window's frame is when it follows a resize under `policy`
+ */
+export declare function resizeFrameBuffer(
+  frame: Buffer,
+  width: number,
+  height: number,
+  newWidth: number,
+  newHeight: number,
+  policy: ResizePolicy,
+): Buffer
+
+/** What happens to the frame contents when the frame follows a window resize */
+export declare const enum ResizePolicy {
+  /** Start from a transparent frame */
+  Clear = 0,
+  /** Keep the overlapping top-left region */
+  PreserveTopLeft = 1,
+  /** Scale the old contents to the new size */
+  Rescale = 2,
+}
+
+/** Sample the color of a gradient at a pixel */
+export declare function sampleGradient(gradient: Gradient, x: number, y: number): Color
+
+export declare const enum ScaleMode {
+  /** The frame follows the window size; content is kept across resizes */
+  MatchWindow = 0,
+  /** Fixed-size frame stretched to fill the window, ignoring aspect ratio */
+  Stretch = 1,
+  /** Fixed-size frame scaled uniformly to fit, centered with letterboxing */
+  Fit = 2,
+  /**
+   * Fixed-size frame scaled by the largest whole factor that fits (at least
+   * 1), centered, so every frame pixel covers the same number of surface pixels
+   */
+  Integer = 3,
+  /** Fixed-size frame shown unscaled and centered */
+  Fixed = 4,
+}
+
+/**
+ * Set the number of threads used for large fills, blits and blends.
+ * 0 uses one thread per CPU; 1 disables parallelism. Counts above four
+ * threads per CPU are lowered to that.
+ */
+export declare function setRenderThreads(count: number): void
+
+/**
+ * Limit the memory held by registered images in bytes, whole atlas pages
+ * included; 0 removes the limit. Least recently drawn images are evicted
+ * first.
+ */
+export declare function setSpriteCacheLimit(bytes: number): void
+
+/** A mask of the window area; pixels at or above the threshold are inside */
+export interface ShapeMask {
+  data: Buffer
+  width: number
+  height: number
+  /** Bytes per row; tightly packed when omitted */
+  stride?: number
+  format?: MaskFormat
+  /** Smallest alpha counted as inside (default 128); ignored for 1-bit masks */
+  threshold?: number
+}
+
+/** How the gradient behaves outside the 0..1 range */
+export declare const enum SpreadMode {
+  /** Extend the end colors */
+  Pad = 0,
+  Repeat = 1,
+  Reflect = 2,
+}
+
+export interface SpriteDrawOptions {
+  /** Destination size; the sprite is scaled (nearest neighbor) when it differs */
+  width?: number
+  height?: number
+  /** Source-over blend instead of replacing the destination pixels */
+  blend?: boolean
+  /** Alpha multiplier from 0 to 1, applied when blending */
+  opacity?: number
+}
+
+export interface SpriteStats {
+  /** Registered images */
+  count: number
+  /** Images packed into atlas pages */
+  atlasSprites: number
+  atlasPages: number
+  /** Memory held by atlas pages, including unused space */
+  atlasBytes: number
+  /** Pixel data of all registered images */
+  spriteBytes: number
+  /**
+   * Memory held by atlas pages and standalone images; this is what the limit
+   * applies to
+   */
+  cachedBytes: number
+  limitBytes: number
+  /** Images evicted to stay under the limit since startup */
+  evictions: number
+}
+
+/** Convert between RGBA and BGRA by swapping the red and blue channels */
+export declare function swizzleBufferRb(buffer: Buffer): Buffer
+
+export interface TweenOptions {
+  property: TweenProperty
+  /** Element to animate; the window itself is animated when omitted */
+  element?: number
+  /** Start value; the current value is used when omitted */
+  from?: number
+  to?: number
+  fromColor?: Color
+  toColor?: Color
+  durationMs: number
+  easing?: Easing
+  delayMs?: number
+  /** Start only once the animation with this id has completed */
+  after?: number
+}
+
+export declare const enum TweenProperty {
+  X = 0,
+  Y = 1,
+  Width = 2,
+  Height = 3,
+  Opacity = 4,
+  /** Element fill color; not available on the window itself */
+  Color = 5,
+}
+
+export declare function unpremultiplyBuffer(buffer: Buffer): Buffer
+
+export declare function unpremultiplyColor(color: Color): Color
+
+/** Remove a registered image; returns false when it wasn't registered */
+export declare function unregisterImage(id: string): boolean
+
+/**
+ * Check a window config without creating a window; throws on invalid values
+ * and impossible combinations such as `minimized` with `maximized`
+ */
+export declare function validateWindowConfig(config: WindowConfig): void
+
+/** Title bar buttons; omitted buttons stay enabled */
+export interface WindowButtons {
+  close?: boolean
+  minimize?: boolean
+  maximize?: boolean
 }
 
 export interface WindowConfig {
@@ -181,7 +1158,46 @@ export interface WindowConfig {
   minimized?: boolean
   maximized?: boolean
   renderWhenOccluded?: boolean
+  /**
+   * Keep presenting while the system runs a modal move/resize loop (Windows),
+   * so content follows the window as it is dragged (default true)
+   */
   handleEventLoopModal?: boolean
+  /** Alpha convention of frame data supplied to this window (default straight) */
+  alphaMode?: AlphaMode
+  /** Compositing backend (default auto) */
+  compositor?: CompositorBackend
+  /**
+   * How the frame maps onto the window when their sizes differ (default
+   * match window)
+   */
+  scaleMode?: ScaleMode
+  /** Frame contents after a window resize (default preserve top-left) */
+  resizePolicy?: ResizePolicy
+  /** Round the window corners by this many pixels (default 0) */
+  cornerRadius?: number
+  /** Smallest and largest inner size the user can resize to */
+  minSize?: WindowSize
+  maxSize?: WindowSize
+  /** Show the window once created (default true) */
+  visible?: boolean
+  /** Focus the window once created (default true) */
+  active?: boolean
+  icon?: DecodedImage
+  /** Light or dark window theme; the system theme when omitted */
+  theme?: WindowTheme
+  /** Keep the window contents out of screenshots and screen capture */
+  contentProtected?: boolean
+  /** Blur what is behind the transparent parts of the window, where supported */
+  blur?: boolean
+  /**
+   * Native handle of the parent window: an HWND on Windows, an X11 window id
+   * on Linux
+   */
+  parentWindow?: number
+  /** Resize in steps of this size */
+  resizeIncrements?: WindowSize
+  enabledButtons?: WindowButtons
 }
 
 export declare const enum WindowLevel {
@@ -195,7 +1211,68 @@ export interface WindowPosition {
   y: number
 }
 
+/** A window shape: the union of a mask and a list of rectangles */
+export interface WindowShape {
+  mask?: ShapeMask
+  rects?: Array<Rect>
+}
+
 export interface WindowSize {
   width: number
   height: number
 }
+
+export declare const enum WindowTheme {
+  Light = 0,
+  Dark = 1,
+}
+
+/** Where to draw a YUV frame; it is scaled (nearest neighbor) to the size */
+export interface YuvDrawOptions {
+  x?: number
+  y?: number
+  /** Destination size, defaulting to the frame's own size */
+  width?: number
+  height?: number
+}
+
+export interface YuvFrame {
+  layout: YuvLayout
+  width: number
+  height: number
+  y: Buffer
+  /** U plane (I420) */
+  u?: Buffer
+  /** V plane (I420) */
+  v?: Buffer
+  /** Interleaved UV plane (NV12) */
+  uv?: Buffer
+  /** Bytes per row of each plane; tightly packed when omitted */
+  yStride?: number
+  uStride?: number
+  vStride?: number
+  uvStride?: number
+  matrix?: YuvMatrix
+  range?: YuvRange
+}
+
+export declare const enum YuvLayout {
+  /** Three planes: Y, then U and V at half resolution */
+  I420 = 0,
+  /** Two planes: Y, then interleaved UV at half resolution */
+  Nv12 = 1,
+}
+
+/** Color matrix used to encode the video */
+export declare const enum YuvMatrix {
+  /** Standard definition video */
+  Bt601 = 0,
+  /** High definition video */
+  Bt709 = 1,
+}
+
+export declare const enum YuvRange {
+  /** Y in 16..235 and chroma in 16..240 (typical for video) */
+  Limited = 0,
+  Full = 1,
+}
@@ -576,14 +576,25 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding
+module.exports.BufferClip = nativeBinding.BufferClip
+module.exports.Canvas = nativeBinding.Canvas
 module.exports.OverlayApp = nativeBinding.OverlayApp
 module.exports.OverlayWindow = nativeBinding.OverlayWindow
+module.exports.applyEasing = nativeBinding.applyEasing
+module.exports.applyFilters = nativeBinding.applyFilters
 module.exports.blendColors = nativeBinding.blendColors
+module.exports.blendColorsLinear = nativeBinding.blendColorsLinear
+module.exports.blendColorsPremultiplied = nativeBinding.blendColorsPremultiplied
 module.exports.calculateBufferSizeNapi = nativeBinding.calculateBufferSizeNapi
+module.exports.clearImages = nativeBinding.clearImages
 module.exports.colorBlack = nativeBinding.colorBlack
 module.exports.colorBlue = nativeBinding.colorBlue
 module.exports.colorCyan = nativeBinding.colorCyan
 module.exports.colorDarkGray = nativeBinding.colorDarkGray
+module.exports.colorFromHsl = nativeBinding.colorFromHsl
+module.exports.colorFromHsv = nativeBinding.colorFromHsv
+module.exports.colorFromLinear = nativeBinding.colorFromLinear
+module.exports.colorFromOklab = nativeBinding.colorFromOklab
 module.exports.colorGray = nativeBinding.colorGray
 module.exports.colorGreen = nativeBinding.colorGreen
 module.exports.colorLightGray = nativeBinding.colorLightGray
@@ -592,20 +603,84 @@ module.exports.colorOrange = nativeBinding.colorOrange
 module.exports.colorPink = nativeBinding.colorPink
 module.exports.colorRed = nativeBinding.colorRed
 module.exports.colorToHex = nativeBinding.colorToHex
+module.exports.colorToHsl = nativeBinding.colorToHsl
+module.exports.colorToHsv = nativeBinding.colorToHsv
+module.exports.colorToLinear = nativeBinding.colorToLinear
+module.exports.colorToOklab = nativeBinding.colorToOklab
 module.exports.colorToRgba = nativeBinding.colorToRgba
 module.exports.colorToRgbHex = nativeBinding.colorToRgbHex
 module.exports.colorTransparent = nativeBinding.colorTransparent
 module.exports.colorWhite = nativeBinding.colorWhite
 module.exports.colorYellow = nativeBinding.colorYellow
+module.exports.compositeLayers = nativeBinding.compositeLayers
+module.exports.computeRoundedRegion = nativeBinding.computeRoundedRegion
+module.exports.computeShapeRegion = nativeBinding.computeShapeRegion
+module.exports.convertPixels = nativeBinding.convertPixels
+module.exports.convertYuvToRgba = nativeBinding.convertYuvToRgba
 module.exports.createColor = nativeBinding.createColor
+module.exports.createIconVariants = nativeBinding.createIconVariants
 module.exports.createPosition = nativeBinding.createPosition
 module.exports.createRgbaBuffer = nativeBinding.createRgbaBuffer
 module.exports.createSize = nativeBinding.createSize
 module.exports.createWindowConfig = nativeBinding.createWindowConfig
+module.exports.drawBatch = nativeBinding.drawBatch
+module.exports.drawBatchEncoded = nativeBinding.drawBatchEncoded
 module.exports.drawCircle = nativeBinding.drawCircle
+module.exports.drawCircleGradient = nativeBinding.drawCircleGradient
+module.exports.drawCircleInPlace = nativeBinding.drawCircleInPlace
 module.exports.drawLine = nativeBinding.drawLine
+module.exports.drawLineGradient = nativeBinding.drawLineGradient
+module.exports.drawLineInPlace = nativeBinding.drawLineInPlace
 module.exports.drawPixel = nativeBinding.drawPixel
+module.exports.drawPixelInPlace = nativeBinding.drawPixelInPlace
+module.exports.drawRectangleInPlace = nativeBinding.drawRectangleInPlace
+module.exports.drawSpriteInPlace = nativeBinding.drawSpriteInPlace
+module.exports.encodeDrawCommands = nativeBinding.encodeDrawCommands
+module.exports.enumerateGpuAdapters = nativeBinding.enumerateGpuAdapters
 module.exports.fillBufferColor = nativeBinding.fillBufferColor
+module.exports.fillBufferColorInPlace = nativeBinding.fillBufferColorInPlace
+module.exports.fillBufferGradient = nativeBinding.fillBufferGradient
+module.exports.getGpuAdapter = nativeBinding.getGpuAdapter
+module.exports.getPlatformCapabilities = nativeBinding.getPlatformCapabilities
+module.exports.getRenderThreads = nativeBinding.getRenderThreads
+module.exports.getScalePlacement = nativeBinding.getScalePlacement
+module.exports.getSimdSupport = nativeBinding.getSimdSupport
+module.exports.getSpriteStats = nativeBinding.getSpriteStats
+module.exports.isTaskbarProgressSupported = nativeBinding.isTaskbarProgressSupported
 module.exports.lerpColors = nativeBinding.lerpColors
+module.exports.lerpColorsIn = nativeBinding.lerpColorsIn
+module.exports.parseColor = nativeBinding.parseColor
+module.exports.premultiplyBuffer = nativeBinding.premultiplyBuffer
+module.exports.premultiplyColor = nativeBinding.premultiplyColor
+module.exports.registerImage = nativeBinding.registerImage
+module.exports.resizeFrameBuffer = nativeBinding.resizeFrameBuffer
+module.exports.sampleGradient = nativeBinding.sampleGradient
+module.exports.setRenderThreads = nativeBinding.setRenderThreads
+module.exports.setSpriteCacheLimit = nativeBinding.setSpriteCacheLimit
+module.exports.swizzleBufferRb = nativeBinding.swizzleBufferRb
+module.exports.unpremultiplyBuffer = nativeBinding.unpremultiplyBuffer
+module.exports.unpremultiplyColor = nativeBinding.unpremultiplyColor
+module.exports.unregisterImage = nativeBinding.unregisterImage
+module.exports.validateWindowConfig = nativeBinding.validateWindowConfig
+module.exports.AlphaMode = nativeBinding.AlphaMode
+module.exports.ColorSpace = nativeBinding.ColorSpace
+module.exports.CompositorBackend = nativeBinding.CompositorBackend
+module.exports.DrawCommandKind = nativeBinding.DrawCommandKind
+module.exports.Easing = nativeBinding.Easing
+module.exports.ErrorCode = nativeBinding.ErrorCode
+module.exports.FillRule = nativeBinding.FillRule
+module.exports.FilterKind = nativeBinding.FilterKind
+module.exports.GradientKind = nativeBinding.GradientKind
+module.exports.MaskFormat = nativeBinding.MaskFormat
 module.exports.OverlayEvent = nativeBinding.OverlayEvent
+module.exports.PixelFormat = nativeBinding.PixelFormat
+module.exports.ProgressState = nativeBinding.ProgressState
+module.exports.ResizePolicy = nativeBinding.ResizePolicy
+module.exports.ScaleMode = nativeBinding.ScaleMode
+module.exports.SpreadMode = nativeBinding.SpreadMode
+module.exports.TweenProperty = nativeBinding.TweenProperty
 module.exports.WindowLevel = nativeBinding.WindowLevel
+module.exports.WindowTheme = nativeBinding.WindowTheme
+module.exports.YuvLayout = nativeBinding.YuvLayout
+module.exports.YuvMatrix = nativeBinding.YuvMatrix
+module.exports.YuvRange = nativeBinding.YuvRange
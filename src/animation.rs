@@ -0,0 +1,279 @@
+//! Easing curves and the native tween engine
+
+use crate::color::Color;
+use crate::types::OverlayEvent;
+use napi::bindgen_prelude::FnArgs;
+use napi::threadsafe_function::ThreadsafeFunction;
+use napi_derive::napi;
+use std::time::{Duration, Instant};
+
+/// Interval between animation ticks while an animation is running (~60 FPS)
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+  #[default]
+  Linear,
+  /// Cubic ease-in
+  EaseIn,
+  /// Cubic ease-out
+  EaseOut,
+  /// Cubic ease-in-out
+  EaseInOut,
+  /// Damped spring that overshoots slightly before settling
+  Spring,
+}
+
+impl Easing {
+  /// Maps linear progress `t` (0..1) onto the easing curve
+  pub fn apply(&self, t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    match self {
+      Easing::Linear => t,
+      Easing::EaseIn => t * t * t,
+      Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+      Easing::EaseInOut => {
+        if t < 0.5 {
+          4.0 * t * t * t
+        } else {
+          1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+        }
+      }
+      Easing::Spring => {
+        if t >= 1.0 {
+          1.0
+        } else {
+          1.0 - (-6.0 * t).exp() * (10.0 * t).cos()
+        }
+      }
+    }
+  }
+}
+
+/// Direction of a fade, used to pick the target opacity and completion event
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FadeDirection {
+  In,
+  Out,
+}
+
+impl FadeDirection {
+  pub fn target(&self) -> f64 {
+    match self {
+      FadeDirection::In => 1.0,
+      FadeDirection::Out => 0.0,
+    }
+  }
+
+  pub fn completion_event(&self) -> OverlayEvent {
+    match self {
+      FadeDirection::In => OverlayEvent::FadeInCompleted,
+      FadeDirection::Out => OverlayEvent::FadeOutCompleted,
+    }
+  }
+}
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TweenProperty {
+  X,
+  Y,
+  Width,
+  Height,
+  Opacity,
+  /// Element fill color; not available on the window itself
+  Color,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct TweenOptions {
+  pub property: TweenProperty,
+  /// Element to animate; the window itself is animated when omitted
+  pub element: Option<u32>,
+  /// Start value; the current value is used when omitted
+  pub from: Option<f64>,
+  pub to: Option<f64>,
+  pub from_color: Option<Color>,
+  pub to_color: Option<Color>,
+  pub duration_ms: u32,
+  pub easing: Option<Easing>,
+  pub delay_ms: Option<u32>,
+  /// Start only once the animation with this id has completed
+  pub after: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TweenTarget {
+  Window,
+  Element(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TweenValue {
+  Number(f64),
+  Color(Color),
+}
+
+impl TweenValue {
+  pub fn interpolate(&self, to: &TweenValue, t: f64) -> TweenValue {
+    match (self, to) {
+      (TweenValue::Number(a), TweenValue::Number(b)) => TweenValue::Number(a + (b - a) * t),
+      // Color::lerp clamps, so springs settle without overshooting colors
+      (TweenValue::Color(a), TweenValue::Color(b)) => TweenValue::Color(a.lerp(b, t)),
+      _ => *to,
+    }
+  }
+}
+
+/// A single property animation
+pub struct Tween {
+  pub id: u32,
+  pub target: TweenTarget,
+  pub property: TweenProperty,
+  /// Resolved from the current value when the tween starts, if not given
+  pub from: Option<TweenValue>,
+  pub to: TweenValue,
+  pub delay: Duration,
+  pub duration: Duration,
+  pub easing: Easing,
+  /// Id of the tween this one is chained after, cleared once it starts counting
+  pub after: Option<u32>,
+  pub start: Option<Instant>,
+  pub completion_event: Option<OverlayEvent>,
+  /// Called with the id and whether the tween was cancelled instead of completing
+  pub callback: Option<ThreadsafeFunction<FnArgs<(u32, bool)>>>,
+}
+
+impl Tween {
+  pub fn new(
+    target: TweenTarget,
+    property: TweenProperty,
+    from: Option<TweenValue>,
+    to: TweenValue,
+    duration_ms: u32,
+    easing: Easing,
+  ) -> Self {
+    Self {
+      id: 0,
+      target,
+      property,
+      from,
+      to,
+      delay: Duration::ZERO,
+      duration: Duration::from_millis(duration_ms as u64),
+      easing,
+      after: None,
+      start: Some(Instant::now()),
+      completion_event: None,
+      callback: None,
+    }
+  }
+
+  /// Eased progress at `now`, or `None` while the tween is waiting to start
+  pub fn progress(&self, now: Instant) -> Option<(f64, bool)> {
+    let begin = self.start? + self.delay;
+    if now < begin {
+      return None;
+    }
+    let elapsed = now - begin;
+    if elapsed >= self.duration {
+      return Some((1.0, true));
+    }
+    let t = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+    Some((self.easing.apply(t), false))
+  }
+}
+
+/// Set of running tweens owned by a window
+#[derive(Default)]
+pub struct Animator {
+  pub tweens: Vec<Tween>,
+  next_id: u32,
+}
+
+impl Animator {
+  pub fn add(&mut self, mut tween: Tween) -> u32 {
+    self.next_id = self.next_id.wrapping_add(1).max(1);
+    tween.id = self.next_id;
+    if tween.after.is_some() {
+      tween.start = None;
+    }
+    self.tweens.push(tween);
+    self.next_id
+  }
+
+  pub fn contains(&self, id: u32) -> bool {
+    self.tweens.iter().any(|t| t.id == id)
+  }
+
+  pub fn is_active(&self) -> bool {
+    !self.tweens.is_empty()
+  }
+
+  /// Cancel a tween and everything chained after it, returning the removed tweens
+  pub fn cancel(&mut self, id: u32) -> Vec<Tween> {
+    let mut removed = Vec::new();
+    let mut pending = vec![id];
+    while let Some(parent) = pending.pop() {
+      let (dropped, kept) = std::mem::take(&mut self.tweens)
+        .into_iter()
+        .partition::<Vec<_>, _>(|t| t.id == parent || t.after == Some(parent));
+      self.tweens = kept;
+      pending.extend(dropped.iter().filter(|t| t.id != parent).map(|t| t.id));
+      removed.extend(dropped);
+    }
+    removed
+  }
+
+  /// Cancel every tween animating the given property of the given target
+  pub fn cancel_property(&mut self, target: TweenTarget, property: TweenProperty) -> Vec<Tween> {
+    self.cancel_matching(|t| t.target == target && t.property == property)
+  }
+
+  /// Cancel every tween targeting an element
+  pub fn cancel_element(&mut self, element: u32) -> Vec<Tween> {
+    self.cancel_matching(|t| t.target == TweenTarget::Element(element))
+  }
+
+  fn cancel_matching(&mut self, matches: impl Fn(&Tween) -> bool) -> Vec<Tween> {
+    let ids: Vec<u32> = self
+      .tweens
+      .iter()
+      .filter(|t| matches(t))
+      .map(|t| t.id)
+      .collect();
+    ids.into_iter().flat_map(|id| self.cancel(id)).collect()
+  }
+
+  pub fn clear(&mut self) -> Vec<Tween> {
+    std::mem::take(&mut self.tweens)
+  }
+
+  /// Start the tweens chained after a completed one
+  pub fn release_dependents(&mut self, completed: u32, now: Instant) {
+    for tween in self.tweens.iter_mut() {
+      if tween.after == Some(completed) {
+        tween.after = None;
+        tween.start = Some(now);
+      }
+    }
+  }
+}
+
+/// Validates and clamps an opacity value coming from JS
+pub fn clamp_opacity(opacity: f64) -> Option<f32> {
+  if opacity.is_nan() {
+    None
+  } else {
+    Some(opacity.clamp(0.0, 1.0) as f32)
+  }
+}
+
+// NAPI exports
+/// Eased progress for linear progress `t`, which is clamped to 0..1
+#[napi(catch_unwind)]
+pub fn apply_easing(easing: Easing, t: f64) -> f64 {
+  easing.apply(t)
+}
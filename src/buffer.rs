@@ -1,6 +1,11 @@
 //! Buffer manipulation and drawing utilities
 
 use crate::color::Color;
+use crate::format::{decode_into, encode_into, PixelFormat};
+use crate::gradient::Paint;
+use crate::parallel::for_each_pixels;
+use crate::simd;
+use crate::surface::Surface;
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
@@ -10,30 +15,35 @@ pub fn calculate_buffer_size(width: u32, height: u32) -> usize {
   (width as usize) * (height as usize) * 4 // RGBA = 4 bytes per pixel
 }
 
-/// Fill buffer with solid color using SIMD-like optimization
+/// Fill buffer with solid color using vectorized stores
 pub fn fill_buffer_rgba_optimized(buffer: &[u8], r: u8, g: u8, b: u8, a: u8) -> Vec<u8> {
-  let len = buffer.len();
-
-  // Initialize with zeros, then fill with color
-  let mut new_data = vec![0u8; len];
-
-  // Fill with optimized chunk processing
-  for chunk in new_data.chunks_exact_mut(4) {
-    chunk[0] = r;
-    chunk[1] = g;
-    chunk[2] = b;
-    chunk[3] = a;
-  }
-
+  let mut new_data = vec![0u8; buffer.len()];
+  clear_buffer_rgba(&mut new_data, [r, g, b, a]);
   new_data
 }
 
+fn clear_buffer_rgba(frame: &mut [u8], rgba: [u8; 4]) {
+  for_each_pixels(frame, |pixels| simd::fill(pixels, rgba));
+}
+
 /// Clear buffer with solid color in-place
 pub fn clear_buffer_optimized(frame: &mut [u8], _width: u32, _height: u32, color: &Color) {
-  let rgba = color.to_rgba();
-  for chunk in frame.chunks_exact_mut(4) {
-    chunk.copy_from_slice(&rgba);
-  }
+  clear_buffer_rgba(frame, color.to_rgba());
+}
+
+/// Convert a straight-alpha RGBA buffer to premultiplied alpha in-place
+pub fn premultiply_in_place(buffer: &mut [u8]) {
+  for_each_pixels(buffer, simd::premultiply);
+}
+
+/// Convert a premultiplied-alpha RGBA buffer to straight alpha in-place
+pub fn unpremultiply_in_place(buffer: &mut [u8]) {
+  for_each_pixels(buffer, simd::unpremultiply);
+}
+
+/// Swap the red and blue channels in-place (RGBA <-> BGRA)
+pub fn swizzle_rb_in_place(buffer: &mut [u8]) {
+  for_each_pixels(buffer, simd::swizzle_rb);
 }
 
 /// Parameters for rectangle drawing
@@ -47,7 +57,11 @@ pub struct RectangleParams {
 }
 
 /// Draw rectangle with bounds checking and optimization
-pub fn draw_rectangle_optimized(frame: &mut [u8], params: RectangleParams, color: &Color) {
+pub fn draw_rectangle_optimized<P: Paint + ?Sized>(
+  frame: &mut [u8],
+  params: RectangleParams,
+  paint: &P,
+) {
   let RectangleParams {
     x,
     y,
@@ -56,23 +70,14 @@ pub fn draw_rectangle_optimized(frame: &mut [u8], params: RectangleParams, color
     frame_width,
     frame_height,
   } = params;
-  let rgba = color.to_rgba();
 
-  // Calculate bounds with clamping
-  let start_x = x.min(frame_width as u32);
-  let start_y = y.min(frame_height as u32);
-  let end_x = x.saturating_add(width).min(frame_width as u32);
-  let end_y = y.saturating_add(height).min(frame_height as u32);
-
-  // Optimized rectangle drawing
-  for py in start_y..end_y {
-    let row_start = (py as usize * frame_width + start_x as usize) * 4;
-    let row_end = (py as usize * frame_width + end_x as usize) * 4;
-
-    for index in (row_start..row_end).step_by(4) {
-      frame[index..index + 4].copy_from_slice(&rgba);
-    }
-  }
+  Surface::packed(frame, frame_width as u32, frame_height as u32).fill_rect(
+    x as i64,
+    y as i64,
+    width as i64,
+    height as i64,
+    paint,
+  );
 }
 
 /// Draw pixel with bounds checking
@@ -96,7 +101,11 @@ pub fn draw_pixel_safe(
 }
 
 /// Bresenham's line algorithm with optimization
-pub fn draw_line_optimized(buffer: &mut [u8], params: crate::types::LineParams, color: &Color) {
+pub fn draw_line_optimized<P: Paint + ?Sized>(
+  buffer: &mut [u8],
+  params: crate::types::LineParams,
+  paint: &P,
+) {
   let crate::types::LineParams {
     x1,
     y1,
@@ -106,118 +115,141 @@ pub fn draw_line_optimized(buffer: &mut [u8], params: crate::types::LineParams,
     buffer_height,
     ..
   } = params;
-  let rgba = color.to_rgba();
-
-  // Bresenham's line algorithm
-  let mut x0 = x1 as i32;
-  let mut y0 = y1 as i32;
-  let x1_i = x2 as i32;
-  let y1_i = y2 as i32;
-
-  let dx = (x1_i - x0).abs();
-  let dy = -(y1_i - y0).abs();
-  let mut error = dx + dy;
-
-  let sx = if x0 < x1_i { 1 } else { -1 };
-  let sy = if y0 < y1_i { 1 } else { -1 };
-
-  loop {
-    // Draw pixel at current position with bounds checking
-    if x0 >= 0 && y0 >= 0 && (x0 as u32) < buffer_width && (y0 as u32) < buffer_height {
-      let index = (y0 as u32 * buffer_width + x0 as u32) as usize * 4;
-      if index + 3 < buffer.len() {
-        buffer[index..index + 4].copy_from_slice(&rgba);
-      }
-    }
 
-    if x0 == x1_i && y0 == y1_i {
-      break;
-    }
-
-    let e2 = 2 * error;
-    if e2 >= dy {
-      error += dy;
-      x0 += sx;
-    }
-    if e2 <= dx {
-      error += dx;
-      y0 += sy;
-    }
-  }
+  Surface::packed(buffer, buffer_width, buffer_height)
+    .draw_line(x1 as i64, y1 as i64, x2 as i64, y2 as i64, paint);
 }
 
 /// Bresenham's circle algorithm with optimization
-pub fn draw_circle_optimized(
+pub fn draw_circle_optimized<P: Paint + ?Sized>(
   buffer: &mut [u8],
   cx: u32,
   cy: u32,
   radius: u32,
   buffer_width: u32,
   buffer_height: u32,
-  color: &Color,
+  paint: &P,
 ) {
-  let rgba = color.to_rgba();
-  let radius_i = radius as i32;
-  let cx_i = cx as i32;
-  let cy_i = cy as i32;
-
-  // Bresenham's circle algorithm
-  let mut x = 0i32;
-  let mut y = radius_i;
-  let mut d = 3 - 2 * radius_i;
-
-  while y >= x {
-    // Draw 8 symmetric points
-    let points = [
-      (cx_i + x, cy_i + y),
-      (cx_i - x, cy_i + y),
-      (cx_i + x, cy_i - y),
-      (cx_i - x, cy_i - y),
-      (cx_i + y, cy_i + x),
-      (cx_i - y, cy_i + x),
-      (cx_i + y, cy_i - x),
-      (cx_i - y, cy_i - x),
-    ];
-
-    for (px, py) in points {
-      if px >= 0 && py >= 0 && (px as u32) < buffer_width && (py as u32) < buffer_height {
-        let index = (py as u32 * buffer_width + px as u32) as usize * 4;
-        if index + 3 < buffer.len() {
-          buffer[index..index + 4].copy_from_slice(&rgba);
-        }
-      }
-    }
+  Surface::packed(buffer, buffer_width, buffer_height).draw_circle(
+    cx as i64,
+    cy as i64,
+    radius as i64,
+    paint,
+  );
+}
 
-    x += 1;
-    if d > 0 {
-      y -= 1;
-      d += 4 * (x - y) + 10;
-    } else {
-      d += 4 * x + 6;
-    }
+/// Layout of a caller-owned buffer
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BufferLayout {
+  pub width: u32,
+  pub height: u32,
+  /// Bytes per row; defaults to tightly packed rows
+  pub stride: Option<u32>,
+  /// Pixel format of the buffer; defaults to `Rgba8`
+  pub format: Option<PixelFormat>,
+}
+
+/// Validate that a caller-owned buffer fits its layout
+pub fn check_layout(data: &[u8], layout: &BufferLayout) -> Result<()> {
+  layout
+    .format
+    .unwrap_or_default()
+    .row_stride(data.len(), layout.width, layout.height, layout.stride)
+    .map(|_| ())
+    .map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Run `draw` on a caller-owned buffer as an RGBA surface, validating its
+/// layout. Buffers in other formats are converted to RGBA for the call and
+/// written back afterwards.
+pub fn with_layout_surface<R>(
+  data: &mut [u8],
+  layout: &BufferLayout,
+  draw: impl FnOnce(&mut Surface) -> R,
+) -> Result<R> {
+  let (width, height) = (layout.width, layout.height);
+  let format = layout.format.unwrap_or_default();
+  if format == PixelFormat::Rgba8 {
+    let mut surface = Surface::new(data, width, height, layout.stride)
+      .map_err(|e| Error::new(Status::InvalidArg, e))?;
+    return Ok(draw(&mut surface));
   }
+
+  let mut rgba = vec![0; calculate_buffer_size(width, height)];
+  decode_into(data, width, height, format, layout.stride, &mut rgba)
+    .map_err(|e| Error::new(Status::InvalidArg, e))?;
+  let result = draw(&mut Surface::packed(&mut rgba, width, height));
+  encode_into(&rgba, width, height, format, layout.stride, data)
+    .map_err(|e| Error::new(Status::InvalidArg, e))?;
+  Ok(result)
 }
 
 // NAPI exports
-#[napi]
+#[napi(catch_unwind)]
 pub fn calculate_buffer_size_napi(width: u32, height: u32) -> u32 {
   calculate_buffer_size(width, height) as u32
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn create_rgba_buffer(width: u32, height: u32) -> Buffer {
   let size = calculate_buffer_size(width, height);
   let data = vec![0u8; size];
   Buffer::from(data)
 }
 
-#[napi]
-pub fn fill_buffer_color(buffer: Buffer, color: Color) -> Result<Buffer> {
-  let new_data = fill_buffer_rgba_optimized(buffer.as_ref(), color.r, color.g, color.b, color.a);
+/// A copy of the buffer filled with a color, as pixels in `format`
+/// (default `Rgba8`)
+#[napi(catch_unwind)]
+pub fn fill_buffer_color(
+  buffer: Buffer,
+  color: Color,
+  format: Option<PixelFormat>,
+) -> Result<Buffer> {
+  let format = format.unwrap_or_default();
+  if format == PixelFormat::Rgba8 {
+    let new_data = fill_buffer_rgba_optimized(buffer.as_ref(), color.r, color.g, color.b, color.a);
+    return Ok(Buffer::from(new_data));
+  }
+  let mut pixel = [0; 4];
+  let pixel = &mut pixel[..format.bytes_per_pixel()];
+  format.encode_row(&color.to_rgba(), pixel);
+  let mut new_data = vec![0u8; buffer.len()];
+  for chunk in new_data.chunks_exact_mut(pixel.len()) {
+    chunk.copy_from_slice(pixel);
+  }
   Ok(Buffer::from(new_data))
 }
 
-#[napi]
+#[napi(catch_unwind)]
+pub fn premultiply_buffer(buffer: Buffer) -> Buffer {
+  let mut new_data = buffer.as_ref().to_vec();
+  premultiply_in_place(&mut new_data);
+  Buffer::from(new_data)
+}
+
+#[napi(catch_unwind)]
+pub fn unpremultiply_buffer(buffer: Buffer) -> Buffer {
+  let mut new_data = buffer.as_ref().to_vec();
+  unpremultiply_in_place(&mut new_data);
+  Buffer::from(new_data)
+}
+
+/// Convert between RGBA and BGRA by swapping the red and blue channels
+#[napi(catch_unwind)]
+pub fn swizzle_buffer_rb(buffer: Buffer) -> Buffer {
+  let mut new_data = buffer.as_ref().to_vec();
+  swizzle_rb_in_place(&mut new_data);
+  Buffer::from(new_data)
+}
+
+/// Instruction set used by the pixel kernels: "avx2", "sse2", "neon" or "scalar"
+#[napi(catch_unwind)]
+pub fn get_simd_support() -> String {
+  simd::level().name().to_string()
+}
+
+#[napi(catch_unwind)]
 pub fn draw_pixel(buffer: Buffer, x: u32, y: u32, width: u32, color: Color) -> Result<Buffer> {
   let buffer_data = buffer.as_ref();
   let mut new_data = buffer_data.to_vec();
@@ -228,7 +260,7 @@ pub fn draw_pixel(buffer: Buffer, x: u32, y: u32, width: u32, color: Color) -> R
   }
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn draw_line(buffer: Buffer, params: crate::types::LineParams) -> Result<Buffer> {
   let buffer_data = buffer.as_ref();
   let mut new_data = buffer_data.to_vec();
@@ -238,7 +270,7 @@ pub fn draw_line(buffer: Buffer, params: crate::types::LineParams) -> Result<Buf
   Ok(Buffer::from(new_data))
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn draw_circle(
   buffer: Buffer,
   cx: u32,
@@ -263,3 +295,77 @@ pub fn draw_circle(
 
   Ok(Buffer::from(new_data))
 }
+
+/// Fill the caller's buffer with a color without copying it
+#[napi(catch_unwind)]
+pub fn fill_buffer_color_in_place(
+  mut buffer: Buffer,
+  layout: BufferLayout,
+  color: Color,
+) -> Result<()> {
+  with_layout_surface(&mut buffer, &layout, |surface| surface.fill(&color))
+}
+
+#[napi(catch_unwind)]
+pub fn draw_pixel_in_place(
+  mut buffer: Buffer,
+  layout: BufferLayout,
+  x: u32,
+  y: u32,
+  color: Color,
+) -> Result<()> {
+  with_layout_surface(&mut buffer, &layout, |surface| {
+    if !surface.contains(x as i64, y as i64) {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "Pixel position out of bounds",
+      ));
+    }
+    surface.put(x as i64, y as i64, &color);
+    Ok(())
+  })?
+}
+
+#[napi(catch_unwind)]
+pub fn draw_line_in_place(
+  mut buffer: Buffer,
+  layout: BufferLayout,
+  x1: i32,
+  y1: i32,
+  x2: i32,
+  y2: i32,
+  color: Color,
+) -> Result<()> {
+  with_layout_surface(&mut buffer, &layout, |surface| {
+    surface.draw_line(x1 as i64, y1 as i64, x2 as i64, y2 as i64, &color)
+  })
+}
+
+#[napi(catch_unwind)]
+pub fn draw_circle_in_place(
+  mut buffer: Buffer,
+  layout: BufferLayout,
+  cx: i32,
+  cy: i32,
+  radius: u32,
+  color: Color,
+) -> Result<()> {
+  with_layout_surface(&mut buffer, &layout, |surface| {
+    surface.draw_circle(cx as i64, cy as i64, radius as i64, &color)
+  })
+}
+
+#[napi(catch_unwind)]
+pub fn draw_rectangle_in_place(
+  mut buffer: Buffer,
+  layout: BufferLayout,
+  x: i32,
+  y: i32,
+  width: u32,
+  height: u32,
+  color: Color,
+) -> Result<()> {
+  with_layout_surface(&mut buffer, &layout, |surface| {
+    surface.fill_rect(x as i64, y as i64, width as i64, height as i64, &color)
+  })
+}
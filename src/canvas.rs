@@ -0,0 +1,831 @@
+//! Canvas-like 2D drawing context over an RGBA surface
+
+use crate::buffer::{calculate_buffer_size, check_layout, with_layout_surface, BufferLayout};
+use crate::color::{AlphaMode, Color};
+use crate::error::IntoJsResult;
+use crate::gradient::{compile_gradient, CompiledGradient, Gradient, Paint, Premultiplied};
+use crate::surface::Surface;
+use crate::window::FrameController;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::f64::consts::TAU;
+use std::sync::Arc;
+
+type Point = (f64, f64);
+
+/// Rule deciding which regions of a self-intersecting path are inside
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FillRule {
+  #[default]
+  NonZero,
+  EvenOdd,
+}
+
+impl FillRule {
+  fn inside(&self, winding: i32) -> bool {
+    match self {
+      FillRule::NonZero => winding != 0,
+      FillRule::EvenOdd => winding % 2 != 0,
+    }
+  }
+}
+
+/// Affine transform `[a c e; b d f]`, laid out like the DOM canvas matrix
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transform {
+  a: f64,
+  b: f64,
+  c: f64,
+  d: f64,
+  e: f64,
+  f: f64,
+}
+
+impl Transform {
+  const IDENTITY: Transform = Transform {
+    a: 1.0,
+    b: 0.0,
+    c: 0.0,
+    d: 1.0,
+    e: 0.0,
+    f: 0.0,
+  };
+
+  fn apply(&self, (x, y): Point) -> Point {
+    (
+      self.a * x + self.c * y + self.e,
+      self.b * x + self.d * y + self.f,
+    )
+  }
+
+  /// `self × m`: `m` is applied to user coordinates first
+  fn then(&self, m: &Transform) -> Transform {
+    Transform {
+      a: self.a * m.a + self.c * m.b,
+      b: self.b * m.a + self.d * m.b,
+      c: self.a * m.c + self.c * m.d,
+      d: self.b * m.c + self.d * m.d,
+      e: self.a * m.e + self.c * m.f + self.e,
+      f: self.b * m.e + self.d * m.f + self.f,
+    }
+  }
+
+  /// Average scale, used to size strokes and curve tessellation in device pixels
+  fn scale(&self) -> f64 {
+    (self.a * self.d - self.b * self.c).abs().sqrt()
+  }
+}
+
+/// Clip rectangle in device coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ClipRect {
+  x0: f64,
+  y0: f64,
+  x1: f64,
+  y1: f64,
+}
+
+#[derive(Debug, Clone)]
+enum Style {
+  Color(Color),
+  Gradient(Arc<CompiledGradient>),
+}
+
+impl Paint for Style {
+  #[inline]
+  fn color_at(&self, x: u32, y: u32) -> [u8; 4] {
+    match self {
+      Style::Color(color) => color.to_rgba(),
+      Style::Gradient(gradient) => gradient.color_at(x, y),
+    }
+  }
+}
+
+/// A style with the global alpha applied
+struct Faded<'a> {
+  style: &'a Style,
+  alpha: f64,
+}
+
+impl Paint for Faded<'_> {
+  #[inline]
+  fn color_at(&self, x: u32, y: u32) -> [u8; 4] {
+    let mut rgba = self.style.color_at(x, y);
+    rgba[3] = (rgba[3] as f64 * self.alpha).round() as u8;
+    rgba
+  }
+}
+
+/// Everything `save()` pushes and `restore()` pops
+#[derive(Debug, Clone)]
+struct CanvasState {
+  transform: Transform,
+  clip: Option<ClipRect>,
+  fill_style: Style,
+  stroke_style: Style,
+  line_width: f64,
+  global_alpha: f64,
+}
+
+impl Default for CanvasState {
+  fn default() -> Self {
+    let black = Style::Color(Color::new(0, 0, 0, 255));
+    Self {
+      transform: Transform::IDENTITY,
+      clip: None,
+      fill_style: black.clone(),
+      stroke_style: black,
+      line_width: 1.0,
+      global_alpha: 1.0,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Subpath {
+  points: Vec<Point>,
+  closed: bool,
+}
+
+/// Path flattened to line segments in device coordinates
+#[derive(Debug, Clone, Default)]
+struct Path {
+  subpaths: Vec<Subpath>,
+}
+
+impl Path {
+  fn current(&mut self) -> Option<&mut Subpath> {
+    self.subpaths.last_mut().filter(|s| !s.points.is_empty())
+  }
+
+  fn last_point(&self) -> Option<Point> {
+    self.subpaths.last().and_then(|s| s.points.last().copied())
+  }
+
+  fn move_to(&mut self, point: Point) {
+    self.subpaths.push(Subpath {
+      points: vec![point],
+      closed: false,
+    });
+  }
+
+  fn line_to(&mut self, point: Point) {
+    match self.current() {
+      Some(subpath) => subpath.points.push(point),
+      None => self.move_to(point),
+    }
+  }
+
+  fn close(&mut self) {
+    if let Some(subpath) = self.current() {
+      subpath.closed = true;
+      let start = subpath.points[0];
+      self.move_to(start);
+    }
+  }
+
+  /// Polygons to fill; open subpaths are closed implicitly
+  fn polygons(&self) -> Vec<Vec<Point>> {
+    self
+      .subpaths
+      .iter()
+      .filter(|s| s.points.len() > 2)
+      .map(|s| s.points.clone())
+      .collect()
+  }
+
+  /// Polygons covering a stroke of the given device-space width, with round
+  /// joins and caps. Pieces are oriented alike so the nonzero rule unions them.
+  fn stroke_polygons(&self, width: f64) -> Vec<Vec<Point>> {
+    let half = width / 2.0;
+    let mut polygons = Vec::new();
+    for subpath in self.subpaths.iter().filter(|s| s.points.len() > 1) {
+      let points = &subpath.points;
+      let mut segments: Vec<(Point, Point)> = points.windows(2).map(|w| (w[0], w[1])).collect();
+      if subpath.closed {
+        segments.push((points[points.len() - 1], points[0]));
+      }
+      for (p, q) in segments {
+        let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+          continue;
+        }
+        let (nx, ny) = (-dy / len * half, dx / len * half);
+        polygons.push(oriented(vec![
+          (p.0 + nx, p.1 + ny),
+          (q.0 + nx, q.1 + ny),
+          (q.0 - nx, q.1 - ny),
+          (p.0 - nx, p.1 - ny),
+        ]));
+      }
+      let sides = ((half * 4.0).ceil() as usize).clamp(8, 64);
+      for &(cx, cy) in points {
+        polygons.push(oriented(
+          (0..sides)
+            .map(|i| {
+              let angle = TAU * i as f64 / sides as f64;
+              (cx + half * angle.cos(), cy + half * angle.sin())
+            })
+            .collect(),
+        ));
+      }
+    }
+    polygons
+  }
+}
+
+/// Reverse a polygon if needed so its signed area is positive
+fn oriented(mut polygon: Vec<Point>) -> Vec<Point> {
+  let n = polygon.len();
+  let area: f64 = (0..n)
+    .map(|i| {
+      let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+      p.0 * q.1 - q.0 * p.1
+    })
+    .sum();
+  if area < 0.0 {
+    polygon.reverse();
+  }
+  polygon
+}
+
+/// Number of line segments used to flatten a curve with the given control polygon
+fn curve_segments(points: &[Point]) -> usize {
+  let length: f64 = points
+    .windows(2)
+    .map(|w| ((w[1].0 - w[0].0).powi(2) + (w[1].1 - w[0].1).powi(2)).sqrt())
+    .sum();
+  ((length / 4.0).ceil() as usize).clamp(1, 256)
+}
+
+/// Pixel rows and columns that may be painted, end exclusive
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+  x0: i64,
+  y0: i64,
+  x1: i64,
+  y1: i64,
+}
+
+/// Scanline rasterizer sampling pixel centers; calls `span(y, x0, x1)` for
+/// every covered run of pixels within `bounds`
+fn rasterize(
+  polygons: &[Vec<Point>],
+  rule: FillRule,
+  bounds: Bounds,
+  mut span: impl FnMut(u32, u32, u32),
+) {
+  // (top, bottom, x at top, dx/dy, winding direction)
+  let mut edges = Vec::new();
+  for polygon in polygons {
+    let n = polygon.len();
+    for i in 0..n {
+      let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+      if p.1 == q.1 || !(p.0.is_finite() && p.1.is_finite() && q.0.is_finite() && q.1.is_finite()) {
+        continue;
+      }
+      let (top, bottom, dir) = if p.1 < q.1 { (p, q, 1) } else { (q, p, -1) };
+      edges.push((
+        top.1,
+        bottom.1,
+        top.0,
+        (bottom.0 - top.0) / (bottom.1 - top.1),
+        dir,
+      ));
+    }
+  }
+  if edges.is_empty() {
+    return;
+  }
+
+  let min_y = edges.iter().map(|e| e.0).fold(f64::INFINITY, f64::min);
+  let max_y = edges.iter().map(|e| e.1).fold(f64::NEG_INFINITY, f64::max);
+  let y_start = ((min_y - 0.5).ceil() as i64).max(bounds.y0);
+  let y_end = ((max_y - 0.5).ceil() as i64).min(bounds.y1);
+
+  let mut crossings: Vec<(f64, i32)> = Vec::new();
+  for y in y_start..y_end {
+    let yc = y as f64 + 0.5;
+    crossings.clear();
+    crossings.extend(
+      edges
+        .iter()
+        .filter(|e| e.0 <= yc && yc < e.1)
+        .map(|e| (e.2 + (yc - e.0) * e.3, e.4)),
+    );
+    crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut winding = 0;
+    let mut start = 0.0;
+    for &(x, dir) in &crossings {
+      let was_inside = rule.inside(winding);
+      winding += dir;
+      match (was_inside, rule.inside(winding)) {
+        (false, true) => start = x,
+        (true, false) => {
+          let x0 = ((start - 0.5).ceil() as i64).max(bounds.x0);
+          let x1 = ((x - 0.5).ceil() as i64).min(bounds.x1);
+          if x0 < x1 {
+            span(y as u32, x0 as u32, x1 as u32);
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+enum Backing {
+  Owned(Vec<u8>, u32, u32),
+  Borrowed(Buffer, BufferLayout),
+  Frame(FrameController),
+}
+
+/// A 2D drawing context in the spirit of the HTML canvas API. Drawing is
+/// source-over blended and not anti-aliased; gradients are sampled in canvas
+/// pixel coordinates.
+#[napi]
+pub struct Canvas {
+  backing: Backing,
+  state: CanvasState,
+  stack: Vec<CanvasState>,
+  path: Path,
+}
+
+impl Canvas {
+  fn with_backing(backing: Backing) -> Self {
+    Self {
+      backing,
+      state: CanvasState::default(),
+      stack: Vec::new(),
+      path: Path::default(),
+    }
+  }
+
+  pub(crate) fn for_frame(frame: FrameController) -> Self {
+    Self::with_backing(Backing::Frame(frame))
+  }
+
+  fn with_surface<R>(
+    &mut self,
+    env: &Env,
+    draw: impl FnOnce(&mut Surface, AlphaMode) -> R,
+  ) -> Result<R> {
+    match &mut self.backing {
+      Backing::Owned(data, width, height) => {
+        #[cfg(feature = "test-hooks")]
+        crate::recovery::check(crate::recovery::PanicSite::Drawing);
+        Ok(draw(
+          &mut Surface::packed(data, *width, *height),
+          AlphaMode::Straight,
+        ))
+      }
+      Backing::Borrowed(buffer, layout) => with_layout_surface(buffer, layout, |surface| {
+        #[cfg(feature = "test-hooks")]
+        crate::recovery::check(crate::recovery::PanicSite::Drawing);
+        draw(surface, AlphaMode::Straight)
+      }),
+      // Checked with the window locked
+      Backing::Frame(frame) => frame.with_surface(draw).into_js(env),
+    }
+  }
+
+  fn point(&self, x: f64, y: f64) -> Point {
+    self.state.transform.apply((x, y))
+  }
+
+  fn rect_polygon(&self, x: f64, y: f64, width: f64, height: f64) -> Vec<Point> {
+    vec![
+      self.point(x, y),
+      self.point(x + width, y),
+      self.point(x + width, y + height),
+      self.point(x, y + height),
+    ]
+  }
+
+  fn paint_polygons(
+    &mut self,
+    env: &Env,
+    polygons: &[Vec<Point>],
+    rule: FillRule,
+    stroke: bool,
+  ) -> Result<()> {
+    let state = self.state.clone();
+    let paint = Faded {
+      style: if stroke {
+        &state.stroke_style
+      } else {
+        &state.fill_style
+      },
+      alpha: state.global_alpha,
+    };
+    self.with_surface(env, |surface, mode| {
+      let bounds = clip_bounds(state.clip, surface);
+      match mode {
+        AlphaMode::Straight => rasterize(polygons, rule, bounds, |y, x0, x1| {
+          surface.blend_span(y, x0, x1, &paint, mode)
+        }),
+        AlphaMode::Premultiplied => {
+          let paint = Premultiplied(&paint);
+          rasterize(polygons, rule, bounds, |y, x0, x1| {
+            surface.blend_span(y, x0, x1, &paint, mode)
+          })
+        }
+      }
+    })
+  }
+}
+
+/// Surface bounds narrowed to the pixels whose centers lie inside the clip
+fn clip_bounds(clip: Option<ClipRect>, surface: &Surface) -> Bounds {
+  let (x0, y0, x1, y1) = surface.bounds();
+  let mut bounds = Bounds {
+    x0: x0 as i64,
+    y0: y0 as i64,
+    x1: x1 as i64,
+    y1: y1 as i64,
+  };
+  if let Some(clip) = clip {
+    let edge = |v: f64| (v - 0.5).ceil() as i64;
+    bounds.x0 = bounds.x0.max(edge(clip.x0));
+    bounds.y0 = bounds.y0.max(edge(clip.y0));
+    bounds.x1 = bounds.x1.min(edge(clip.x1));
+    bounds.y1 = bounds.y1.min(edge(clip.y1));
+  }
+  bounds
+}
+
+fn all_finite(values: &[f64]) -> bool {
+  values.iter().all(|v| v.is_finite())
+}
+
+// Like the DOM canvas, calls with non-finite coordinates are ignored
+#[napi]
+impl Canvas {
+  /// Create a canvas over its own transparent RGBA buffer
+  #[napi(constructor, catch_unwind)]
+  pub fn new(width: u32, height: u32) -> Self {
+    Self::with_backing(Backing::Owned(
+      vec![0; calculate_buffer_size(width, height)],
+      width,
+      height,
+    ))
+  }
+
+  /// Create a canvas that draws directly into the caller's buffer
+  #[napi(factory, catch_unwind)]
+  pub fn from_buffer(buffer: Buffer, layout: BufferLayout) -> Result<Self> {
+    check_layout(&buffer, &layout)?;
+    Ok(Self::with_backing(Backing::Borrowed(buffer, layout)))
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_width(&mut self, env: Env) -> Result<u32> {
+    self.with_surface(&env, |surface, _| surface.width())
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_height(&mut self, env: Env) -> Result<u32> {
+    self.with_surface(&env, |surface, _| surface.height())
+  }
+
+  /// Copy of the pixels this canvas draws into
+  #[napi(catch_unwind)]
+  pub fn to_buffer(&mut self, env: Env) -> Result<Buffer> {
+    self.with_surface(&env, |surface, _| {
+      let mut data = Vec::with_capacity(calculate_buffer_size(surface.width(), surface.height()));
+      for y in 0..surface.height() {
+        data.extend_from_slice(surface.row(y));
+      }
+      Buffer::from(data)
+    })
+  }
+
+  /// Push the transform, clip and styles onto the state stack
+  #[napi(catch_unwind)]
+  pub fn save(&mut self) {
+    self.stack.push(self.state.clone());
+  }
+
+  /// Pop the state stack; does nothing when it is empty
+  #[napi(catch_unwind)]
+  pub fn restore(&mut self) {
+    if let Some(state) = self.stack.pop() {
+      self.state = state;
+    }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn translate(&mut self, x: f64, y: f64) {
+    self.transform(1.0, 0.0, 0.0, 1.0, x, y);
+  }
+
+  #[napi(catch_unwind)]
+  pub fn scale(&mut self, x: f64, y: f64) {
+    self.transform(x, 0.0, 0.0, y, 0.0, 0.0);
+  }
+
+  /// Rotate clockwise by `angle` radians
+  #[napi(catch_unwind)]
+  pub fn rotate(&mut self, angle: f64) {
+    let (sin, cos) = angle.sin_cos();
+    self.transform(cos, sin, -sin, cos, 0.0, 0.0);
+  }
+
+  /// Multiply the current transform by `[a c e; b d f]`
+  #[napi(catch_unwind)]
+  pub fn transform(&mut self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+    if all_finite(&[a, b, c, d, e, f]) {
+      self.state.transform = self.state.transform.then(&Transform { a, b, c, d, e, f });
+    }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn set_transform(&mut self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+    if all_finite(&[a, b, c, d, e, f]) {
+      self.state.transform = Transform { a, b, c, d, e, f };
+    }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn reset_transform(&mut self) {
+    self.state.transform = Transform::IDENTITY;
+  }
+
+  /// Current transform as `[a, b, c, d, e, f]`
+  #[napi(catch_unwind)]
+  pub fn get_transform(&self) -> Vec<f64> {
+    let t = self.state.transform;
+    vec![t.a, t.b, t.c, t.d, t.e, t.f]
+  }
+
+  /// Intersect the clip with a rectangle. Under rotation the rectangle's
+  /// bounding box is used.
+  #[napi(catch_unwind)]
+  pub fn clip_rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+    if !all_finite(&[x, y, width, height]) {
+      return;
+    }
+    let corners = self.rect_polygon(x, y, width, height);
+    let fold = |init: f64, pick: fn(&Point) -> f64, op: fn(f64, f64) -> f64| {
+      corners.iter().map(pick).fold(init, op)
+    };
+    let mut rect = ClipRect {
+      x0: fold(f64::INFINITY, |p| p.0, f64::min),
+      y0: fold(f64::INFINITY, |p| p.1, f64::min),
+      x1: fold(f64::NEG_INFINITY, |p| p.0, f64::max),
+      y1: fold(f64::NEG_INFINITY, |p| p.1, f64::max),
+    };
+    if let Some(clip) = self.state.clip {
+      rect = ClipRect {
+        x0: rect.x0.max(clip.x0),
+        y0: rect.y0.max(clip.y0),
+        x1: rect.x1.min(clip.x1),
+        y1: rect.y1.min(clip.y1),
+      };
+    }
+    self.state.clip = Some(rect);
+  }
+
+  #[napi(catch_unwind)]
+  pub fn set_fill_style(&mut self, color: Color) {
+    self.state.fill_style = Style::Color(color);
+  }
+
+  #[napi(catch_unwind)]
+  pub fn set_fill_gradient(&mut self, gradient: Gradient) -> Result<()> {
+    self.state.fill_style = Style::Gradient(Arc::new(compile_gradient(&gradient)?));
+    Ok(())
+  }
+
+  #[napi(catch_unwind)]
+  pub fn set_stroke_style(&mut self, color: Color) {
+    self.state.stroke_style = Style::Color(color);
+  }
+
+  #[napi(catch_unwind)]
+  pub fn set_stroke_gradient(&mut self, gradient: Gradient) -> Result<()> {
+    self.state.stroke_style = Style::Gradient(Arc::new(compile_gradient(&gradient)?));
+    Ok(())
+  }
+
+  /// Stroke width in user units; non-positive values are ignored
+  #[napi(catch_unwind)]
+  pub fn set_line_width(&mut self, width: f64) {
+    if width.is_finite() && width > 0.0 {
+      self.state.line_width = width;
+    }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_line_width(&self) -> f64 {
+    self.state.line_width
+  }
+
+  /// Opacity multiplied into every fill and stroke (0..1)
+  #[napi(catch_unwind)]
+  pub fn set_global_alpha(&mut self, alpha: f64) {
+    if (0.0..=1.0).contains(&alpha) {
+      self.state.global_alpha = alpha;
+    }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_global_alpha(&self) -> f64 {
+    self.state.global_alpha
+  }
+
+  /// Discard the current path
+  #[napi(catch_unwind)]
+  pub fn begin_path(&mut self) {
+    self.path = Path::default();
+  }
+
+  #[napi(catch_unwind)]
+  pub fn move_to(&mut self, x: f64, y: f64) {
+    if all_finite(&[x, y]) {
+      self.path.move_to(self.point(x, y));
+    }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn line_to(&mut self, x: f64, y: f64) {
+    if all_finite(&[x, y]) {
+      self.path.line_to(self.point(x, y));
+    }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn quadratic_curve_to(&mut self, cpx: f64, cpy: f64, x: f64, y: f64) {
+    if !all_finite(&[cpx, cpy, x, y]) {
+      return;
+    }
+    let (c, end) = (self.point(cpx, cpy), self.point(x, y));
+    let Some(start) = self.path.last_point() else {
+      self.path.move_to(c);
+      self.path.line_to(end);
+      return;
+    };
+    let segments = curve_segments(&[start, c, end]);
+    for i in 1..=segments {
+      let t = i as f64 / segments as f64;
+      let u = 1.0 - t;
+      self.path.line_to((
+        u * u * start.0 + 2.0 * u * t * c.0 + t * t * end.0,
+        u * u * start.1 + 2.0 * u * t * c.1 + t * t * end.1,
+      ));
+    }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn bezier_curve_to(&mut self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+    if !all_finite(&[cp1x, cp1y, cp2x, cp2y, x, y]) {
+      return;
+    }
+    let (c1, c2, end) = (
+      self.point(cp1x, cp1y),
+      self.point(cp2x, cp2y),
+      self.point(x, y),
+    );
+    let Some(start) = self.path.last_point() else {
+      self.path.move_to(c1);
+      self.path.line_to(end);
+      return;
+    };
+    let segments = curve_segments(&[start, c1, c2, end]);
+    for i in 1..=segments {
+      let t = i as f64 / segments as f64;
+      let u = 1.0 - t;
+      let (w0, w1, w2, w3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+      self.path.line_to((
+        w0 * start.0 + w1 * c1.0 + w2 * c2.0 + w3 * end.0,
+        w0 * start.1 + w1 * c1.1 + w2 * c2.1 + w3 * end.1,
+      ));
+    }
+  }
+
+  /// Circular arc around `(x, y)`, connected to the current point by a line.
+  /// Angles are in radians, clockwise unless `counterclockwise` is set.
+  #[napi(catch_unwind)]
+  pub fn arc(
+    &mut self,
+    x: f64,
+    y: f64,
+    radius: f64,
+    start_angle: f64,
+    end_angle: f64,
+    counterclockwise: Option<bool>,
+  ) -> Result<()> {
+    if !all_finite(&[x, y, radius, start_angle, end_angle]) {
+      return Ok(());
+    }
+    if radius < 0.0 {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "Arc radius must not be negative",
+      ));
+    }
+    let sweep = if counterclockwise.unwrap_or(false) {
+      let span = start_angle - end_angle;
+      -if span >= TAU {
+        TAU
+      } else {
+        span.rem_euclid(TAU)
+      }
+    } else {
+      let span = end_angle - start_angle;
+      if span >= TAU {
+        TAU
+      } else {
+        span.rem_euclid(TAU)
+      }
+    };
+    let device_radius = radius * self.state.transform.scale();
+    let segments = ((sweep.abs() * device_radius / 2.0).ceil() as usize).clamp(4, 256);
+    for i in 0..=segments {
+      let angle = start_angle + sweep * i as f64 / segments as f64;
+      self
+        .path
+        .line_to(self.point(x + radius * angle.cos(), y + radius * angle.sin()));
+    }
+    Ok(())
+  }
+
+  /// Add a closed rectangle subpath
+  #[napi(catch_unwind)]
+  pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+    if !all_finite(&[x, y, width, height]) {
+      return;
+    }
+    self.path.subpaths.push(Subpath {
+      points: self.rect_polygon(x, y, width, height),
+      closed: true,
+    });
+    self.path.move_to(self.point(x, y));
+  }
+
+  #[napi(catch_unwind)]
+  pub fn close_path(&mut self) {
+    self.path.close();
+  }
+
+  /// Fill the current path with the fill style
+  #[napi(catch_unwind)]
+  pub fn fill(&mut self, env: Env, rule: Option<FillRule>) -> Result<()> {
+    let polygons = self.path.polygons();
+    self.paint_polygons(&env, &polygons, rule.unwrap_or_default(), false)
+  }
+
+  /// Stroke the current path with the stroke style and line width
+  #[napi(catch_unwind)]
+  pub fn stroke(&mut self, env: Env) -> Result<()> {
+    let width = self.state.line_width * self.state.transform.scale();
+    let polygons = self.path.stroke_polygons(width);
+    self.paint_polygons(&env, &polygons, FillRule::NonZero, true)
+  }
+
+  /// Fill a rectangle without touching the current path
+  #[napi(catch_unwind)]
+  pub fn fill_rect(&mut self, env: Env, x: f64, y: f64, width: f64, height: f64) -> Result<()> {
+    if !all_finite(&[x, y, width, height]) {
+      return Ok(());
+    }
+    let polygon = self.rect_polygon(x, y, width, height);
+    self.paint_polygons(&env, &[polygon], FillRule::NonZero, false)
+  }
+
+  /// Stroke a rectangle without touching the current path
+  #[napi(catch_unwind)]
+  pub fn stroke_rect(&mut self, env: Env, x: f64, y: f64, width: f64, height: f64) -> Result<()> {
+    if !all_finite(&[x, y, width, height]) {
+      return Ok(());
+    }
+    let path = Path {
+      subpaths: vec![Subpath {
+        points: self.rect_polygon(x, y, width, height),
+        closed: true,
+      }],
+    };
+    let width = self.state.line_width * self.state.transform.scale();
+    self.paint_polygons(&env, &path.stroke_polygons(width), FillRule::NonZero, true)
+  }
+
+  /// Make a rectangle fully transparent
+  #[napi(catch_unwind)]
+  pub fn clear_rect(&mut self, env: Env, x: f64, y: f64, width: f64, height: f64) -> Result<()> {
+    if !all_finite(&[x, y, width, height]) {
+      return Ok(());
+    }
+    let polygon = self.rect_polygon(x, y, width, height);
+    let clip = self.state.clip;
+    let transparent = Color::new(0, 0, 0, 0);
+    self.with_surface(&env, |surface, _| {
+      let bounds = clip_bounds(clip, surface);
+      rasterize(&[polygon], FillRule::NonZero, bounds, |y, x0, x1| {
+        surface.fill_rect(x0 as i64, y as i64, (x1 - x0) as i64, 1, &transparent)
+      });
+    })
+  }
+}
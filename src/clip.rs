@@ -0,0 +1,324 @@
+//! Clip stack for frame drawing: rectangles, rounded rectangles and masks
+//!
+//! Each stack entry stores the intersection with everything below it, so the
+//! top entry alone decides what drawing may touch. Rectangles only narrow the
+//! surface bounds; shapes with partial coverage additionally blend the drawn
+//! pixels back over the rows they replaced, saved as the draw first writes
+//! to them.
+
+use crate::buffer::{with_layout_surface, BufferLayout};
+use crate::command::{execute, resolve_commands, DrawCommand};
+use crate::surface::Surface;
+use crate::types::Rect;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// An 8-bit coverage mask, one byte per pixel, placed at `(x, y)` in the frame
+#[napi(object)]
+pub struct ClipMask {
+  pub data: Buffer,
+  pub x: i32,
+  pub y: i32,
+  pub width: u32,
+  pub height: u32,
+  /// Bytes per row; tightly packed when omitted
+  pub stride: Option<u32>,
+}
+
+/// A clip shape in frame coordinates
+pub enum ClipShape<'a> {
+  Rect(Rect),
+  RoundedRect(Rect, f64),
+  Mask(&'a ClipMask),
+}
+
+/// The combined clip of a stack entry
+#[derive(Debug, Clone)]
+pub struct Clip {
+  x0: u32,
+  y0: u32,
+  x1: u32,
+  y1: u32,
+  /// Coverage of every pixel in the bounds, or `None` when fully covered
+  coverage: Option<Vec<u8>>,
+}
+
+impl Clip {
+  fn width(&self) -> usize {
+    (self.x1 - self.x0) as usize
+  }
+
+  fn coverage_at(&self, x: u32, y: u32) -> u8 {
+    match &self.coverage {
+      Some(coverage) => coverage[(y - self.y0) as usize * self.width() + (x - self.x0) as usize],
+      None => 255,
+    }
+  }
+
+  /// Run `draw` with the surface limited to this clip. With partial coverage,
+  /// only the rows the draw wrote to are saved and blended back.
+  pub fn draw<R>(&self, surface: &mut Surface, draw: impl FnOnce(&mut Surface) -> R) -> R {
+    surface.restrict(self.x0, self.y0, self.x1, self.y1);
+    let Some(coverage) = &self.coverage else {
+      return draw(surface);
+    };
+    let (x0, y0, x1, y1) = surface.bounds();
+    if x1 <= x0 || y1 <= y0 {
+      return draw(surface);
+    }
+    surface.save_writes(x0, y0, x1, y1);
+    let result = draw(surface);
+    let Some(saved) = surface.take_saved() else {
+      return result;
+    };
+    let Some(first) = saved.rows.iter().position(Option::is_some) else {
+      return result;
+    };
+    let last = saved
+      .rows
+      .iter()
+      .rposition(Option::is_some)
+      .unwrap_or(first);
+
+    let width = self.width();
+    surface.for_rows(y0 + first as u32, y0 + last as u32 + 1, |y, row| {
+      let Some(before) = &saved.rows[(y - y0) as usize] else {
+        return;
+      };
+      let coverage = &coverage[(y - self.y0) as usize * width + (x0 - self.x0) as usize..];
+      let row = &mut row[x0 as usize * 4..x1 as usize * 4];
+      for ((pixel, old), &c) in row
+        .chunks_exact_mut(4)
+        .zip(before.chunks_exact(4))
+        .zip(coverage)
+      {
+        let c = c as u32;
+        for (new, &old) in pixel.iter_mut().zip(old) {
+          *new = ((*new as u32 * c + old as u32 * (255 - c) + 127) / 255) as u8;
+        }
+      }
+    });
+    result
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClipStack {
+  entries: Vec<Clip>,
+}
+
+impl ClipStack {
+  /// The clip that currently applies, if any
+  pub fn current(&self) -> Option<&Clip> {
+    self.entries.last()
+  }
+
+  pub fn depth(&self) -> u32 {
+    self.entries.len() as u32
+  }
+
+  pub fn pop(&mut self) -> bool {
+    self.entries.pop().is_some()
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+
+  /// Intersect a shape with the current clip and push the result
+  pub fn push(
+    &mut self,
+    shape: ClipShape,
+    frame_width: u32,
+    frame_height: u32,
+  ) -> std::result::Result<(), String> {
+    let rect = match &shape {
+      ClipShape::Rect(rect) | ClipShape::RoundedRect(rect, _) => *rect,
+      ClipShape::Mask(mask) => Rect {
+        x: mask.x,
+        y: mask.y,
+        width: mask.width,
+        height: mask.height,
+      },
+    };
+    let (px0, py0, px1, py1) = match self.current() {
+      Some(clip) => (clip.x0, clip.y0, clip.x1, clip.y1),
+      None => (0, 0, frame_width, frame_height),
+    };
+    let span = |start: i32, len: u32, min: u32, max: u32| {
+      let start = start as i64;
+      let end = start + len as i64;
+      (
+        start.clamp(min as i64, max as i64) as u32,
+        end.clamp(min as i64, max as i64) as u32,
+      )
+    };
+    let (x0, x1) = span(rect.x, rect.width, px0, px1);
+    let (y0, y1) = span(rect.y, rect.height, py0, py1);
+    let (x1, y1) = (x1.max(x0), y1.max(y0));
+
+    let shape_coverage: Option<Box<dyn Fn(u32, u32) -> u8 + '_>> = match shape {
+      ClipShape::Rect(_) => None,
+      ClipShape::RoundedRect(rect, radius) => {
+        if !radius.is_finite() || radius < 0.0 {
+          return Err("Corner radius must be a non-negative number".to_string());
+        }
+        Some(Box::new(move |x, y| {
+          rounded_rect_coverage(&rect, radius, x, y)
+        }))
+      }
+      ClipShape::Mask(mask) => {
+        let stride = mask
+          .stride
+          .map(|s| s as usize)
+          .unwrap_or(mask.width as usize);
+        if stride < mask.width as usize {
+          return Err(format!(
+            "Mask stride {} is smaller than its width {}",
+            stride, mask.width
+          ));
+        }
+        let required = match mask.height {
+          0 => 0,
+          h => (h as usize - 1) * stride + mask.width as usize,
+        };
+        if mask.data.len() < required {
+          return Err(format!(
+            "Mask too small for {}x{} (stride {}): expected at least {} bytes, got {}",
+            mask.width,
+            mask.height,
+            stride,
+            required,
+            mask.data.len()
+          ));
+        }
+        let data: &[u8] = &mask.data;
+        let (mx, my) = (mask.x as i64, mask.y as i64);
+        Some(Box::new(move |x, y| {
+          data[(y as i64 - my) as usize * stride + (x as i64 - mx) as usize]
+        }))
+      }
+    };
+
+    let parent = self.current().filter(|clip| clip.coverage.is_some());
+    let coverage = (shape_coverage.is_some() || parent.is_some()).then(|| {
+      let mut coverage = Vec::with_capacity((x1 - x0) as usize * (y1 - y0) as usize);
+      for y in y0..y1 {
+        for x in x0..x1 {
+          let own = shape_coverage.as_ref().map_or(255, |f| f(x, y));
+          let inherited = parent.map_or(255, |clip| clip.coverage_at(x, y));
+          coverage.push(crate::color::mul_div_255(own as u32, inherited as u32) as u8);
+        }
+      }
+      coverage
+    });
+    self.entries.push(Clip {
+      x0,
+      y0,
+      x1,
+      y1,
+      coverage,
+    });
+    Ok(())
+  }
+}
+
+/// A clip stack for drawing into caller-owned buffers of one size, clipping
+/// the same way as the `pushClip*` methods of a window
+#[napi]
+pub struct BufferClip {
+  width: u32,
+  height: u32,
+  stack: ClipStack,
+}
+
+#[napi]
+impl BufferClip {
+  #[napi(constructor, catch_unwind)]
+  pub fn new(width: u32, height: u32) -> Self {
+    Self {
+      width,
+      height,
+      stack: ClipStack::default(),
+    }
+  }
+
+  fn push(&mut self, shape: ClipShape) -> Result<()> {
+    self
+      .stack
+      .push(shape, self.width, self.height)
+      .map_err(|e| Error::new(Status::InvalidArg, e))
+  }
+
+  /// Limit drawing to a rectangle, intersected with the current clip
+  #[napi(catch_unwind)]
+  pub fn push_rect(&mut self, rect: Rect) -> Result<()> {
+    self.push(ClipShape::Rect(rect))
+  }
+
+  /// Limit drawing to an anti-aliased rounded rectangle
+  #[napi(catch_unwind)]
+  pub fn push_rounded_rect(&mut self, rect: Rect, radius: f64) -> Result<()> {
+    self.push(ClipShape::RoundedRect(rect, radius))
+  }
+
+  /// Limit drawing by an 8-bit coverage mask
+  #[napi(catch_unwind)]
+  pub fn push_mask(&mut self, mask: ClipMask) -> Result<()> {
+    self.push(ClipShape::Mask(&mask))
+  }
+
+  /// Remove the most recently pushed clip; returns false when none was set
+  #[napi(catch_unwind)]
+  pub fn pop(&mut self) -> bool {
+    self.stack.pop()
+  }
+
+  /// Remove all clips
+  #[napi(catch_unwind)]
+  pub fn reset(&mut self) {
+    self.stack.clear();
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_depth(&self) -> u32 {
+    self.stack.depth()
+  }
+
+  /// Apply draw commands to the caller's buffer within the current clip
+  #[napi(catch_unwind)]
+  pub fn draw_batch(
+    &self,
+    mut buffer: Buffer,
+    layout: BufferLayout,
+    commands: Vec<DrawCommand>,
+  ) -> Result<()> {
+    if (layout.width, layout.height) != (self.width, self.height) {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!(
+          "Buffer is {}x{}, but the clip was made for {}x{}",
+          layout.width, layout.height, self.width, self.height
+        ),
+      ));
+    }
+    let ops = resolve_commands(&commands)?;
+    with_layout_surface(&mut buffer, &layout, |surface| match self.stack.current() {
+      Some(clip) => clip.draw(surface, |surface| execute(surface, &ops)),
+      None => execute(surface, &ops),
+    })
+  }
+}
+
+/// Anti-aliased coverage of the pixel centered at `(x + 0.5, y + 0.5)`
+pub(crate) fn rounded_rect_coverage(rect: &Rect, radius: f64, x: u32, y: u32) -> u8 {
+  let half_width = rect.width as f64 / 2.0;
+  let half_height = rect.height as f64 / 2.0;
+  let radius = radius.min(half_width).min(half_height);
+  // Signed distance from the pixel center to the rounded rectangle
+  let px = (x as f64 + 0.5 - (rect.x as f64 + half_width)).abs() - (half_width - radius);
+  let py = (y as f64 + 0.5 - (rect.y as f64 + half_height)).abs() - (half_height - radius);
+  let outside = px.max(0.0).hypot(py.max(0.0));
+  let distance = outside + px.max(py).min(0.0) - radius;
+  ((0.5 - distance).clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -1,5 +1,7 @@
 //! Color management and manipulation utilities
 
+use crate::simd::{self, scalar};
+use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
 #[napi(object)]
@@ -28,27 +30,525 @@ impl Color {
     format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
   }
 
-  /// Blends this color over another using alpha compositing
+  /// Blends this color over another using source-over compositing on straight alpha
   pub fn blend(&self, other: &Color) -> Color {
-    let alpha = self.a as f32 / 255.0;
-    let r = (self.r as f32 * alpha + other.r as f32 * (1.0 - alpha)) as u8;
-    let g = (self.g as f32 * alpha + other.g as f32 * (1.0 - alpha)) as u8;
-    let b = (self.b as f32 * alpha + other.b as f32 * (1.0 - alpha)) as u8;
-    let a = (self.a as f32 * alpha + other.a as f32 * (1.0 - alpha)) as u8;
+    let [r, g, b, a] = scalar::blend_straight_pixel(self.to_rgba(), other.to_rgba());
     Color::new(r, g, b, a)
   }
 
+  /// Blends this color over another when both are premultiplied by their alpha
+  pub fn blend_premultiplied(&self, other: &Color) -> Color {
+    let [r, g, b, a] = scalar::blend_premultiplied_pixel(self.to_rgba(), other.to_rgba());
+    Color::new(r, g, b, a)
+  }
+
+  /// Converts a straight-alpha color to premultiplied alpha
+  pub fn premultiply(&self) -> Color {
+    let [r, g, b, a] = premultiply_pixel(self.to_rgba());
+    Color::new(r, g, b, a)
+  }
+
+  /// Converts a premultiplied-alpha color back to straight alpha
+  pub fn unpremultiply(&self) -> Color {
+    let [r, g, b, a] = unpremultiply_pixel(self.to_rgba());
+    Color::new(r, g, b, a)
+  }
+
+  /// Converts a straight-alpha color into the given alpha convention
+  pub fn in_alpha_mode(&self, mode: AlphaMode) -> Color {
+    match mode {
+      AlphaMode::Straight => *self,
+      AlphaMode::Premultiplied => self.premultiply(),
+    }
+  }
+
   /// Linearly interpolates between two colors
   pub fn lerp(&self, other: &Color, t: f64) -> Color {
     let t = t.clamp(0.0, 1.0);
-    let r = (self.r as f64 + (other.r as f64 - self.r as f64) * t) as u8;
-    let g = (self.g as f64 + (other.g as f64 - self.g as f64) * t) as u8;
-    let b = (self.b as f64 + (other.b as f64 - self.b as f64) * t) as u8;
-    let a = (self.a as f64 + (other.a as f64 - self.a as f64) * t) as u8;
-    Color::new(r, g, b, a)
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Color::new(
+      mix(self.r, other.r),
+      mix(self.g, other.g),
+      mix(self.b, other.b),
+      mix(self.a, other.a),
+    )
+  }
+
+  /// Interpolates in the given color space; alpha is always interpolated linearly
+  pub fn lerp_in(&self, other: &Color, t: f64, space: ColorSpace) -> Color {
+    if space == ColorSpace::Srgb {
+      return self.lerp(other, t);
+    }
+    let [r, g, b, a] = self.mix_in(other, t, space, false);
+    Color::new(to_u8(r), to_u8(g), to_u8(b), to_u8(a))
+  }
+
+  /// Unrounded interpolation in the given color space, as sRGB-encoded channels in 0..1.
+  /// With `premultiplied`, the color components are weighted by alpha while
+  /// mixing, as CSS and Canvas gradients do, so a fade to transparent keeps its hue.
+  pub(crate) fn mix_in(
+    &self,
+    other: &Color,
+    t: f64,
+    space: ColorSpace,
+    premultiplied: bool,
+  ) -> [f64; 4] {
+    let t = t.clamp(0.0, 1.0);
+    let (from_a, to_a) = (unit(self.a), unit(other.a));
+    let alpha = from_a + (to_a - from_a) * t;
+    let (from_w, to_w, out_w) = match (premultiplied, alpha > 0.0) {
+      (false, _) => (1.0, 1.0, 1.0),
+      (true, true) => (from_a, to_a, alpha),
+      (true, false) => (0.0, 0.0, 1.0),
+    };
+    let mix = |a: f64, b: f64| (a * from_w + (b * to_w - a * from_w) * t) / out_w;
+    let linear = match space {
+      ColorSpace::Srgb => {
+        return [
+          mix(unit(self.r), unit(other.r)),
+          mix(unit(self.g), unit(other.g)),
+          mix(unit(self.b), unit(other.b)),
+          alpha,
+        ];
+      }
+      ColorSpace::LinearRgb => {
+        let (a, b) = (self.to_linear(), other.to_linear());
+        LinearRgbColor {
+          r: mix(a.r, b.r),
+          g: mix(a.g, b.g),
+          b: mix(a.b, b.b),
+          a: alpha,
+        }
+      }
+      ColorSpace::Oklab => {
+        let (a, b) = (self.to_oklab(), other.to_oklab());
+        oklab_to_linear(&OklabColor {
+          l: mix(a.l, b.l),
+          a: mix(a.a, b.a),
+          b: mix(a.b, b.b),
+          alpha,
+        })
+      }
+    };
+    [
+      linear_to_srgb(linear.r),
+      linear_to_srgb(linear.g),
+      linear_to_srgb(linear.b),
+      alpha,
+    ]
+  }
+
+  /// Blends this color over another in linear light (gamma-correct source-over)
+  pub fn blend_linear(&self, other: &Color) -> Color {
+    let (src, dst) = (self.to_linear(), other.to_linear());
+    let out_a = src.a + dst.a * (1.0 - src.a);
+    if out_a <= 0.0 {
+      return COLOR_TRANSPARENT;
+    }
+    let mix = |s: f64, d: f64| (s * src.a + d * dst.a * (1.0 - src.a)) / out_a;
+    Color::from_linear(&LinearRgbColor {
+      r: mix(src.r, dst.r),
+      g: mix(src.g, dst.g),
+      b: mix(src.b, dst.b),
+      a: out_a,
+    })
+  }
+
+  /// Parses a CSS color: `#RGB`, `#RGBA`, `#RRGGBB`, `#RRGGBBAA`, `rgb()`/`rgba()`,
+  /// `hsl()`/`hsla()` or a named color
+  pub fn parse(input: &str) -> std::result::Result<Color, String> {
+    let value = input.trim().to_ascii_lowercase();
+    if value.is_empty() {
+      return Err("Invalid color: empty string".to_string());
+    }
+
+    if let Some(hex) = value.strip_prefix('#') {
+      return parse_hex(hex).ok_or_else(|| {
+        format!(
+          "Invalid hex color '{}': expected 3, 4, 6 or 8 hex digits",
+          input.trim()
+        )
+      });
+    }
+
+    if let Some(open) = value.find('(') {
+      let name = value[..open].trim();
+      let args = value[open + 1..].strip_suffix(')').ok_or_else(|| {
+        format!(
+          "Invalid color '{}': missing closing parenthesis",
+          input.trim()
+        )
+      })?;
+      return match name {
+        "rgb" | "rgba" => parse_rgb_function(name, args),
+        "hsl" | "hsla" => parse_hsl_function(name, args),
+        _ => Err(format!(
+          "Unsupported color function '{}()': expected rgb, rgba, hsl or hsla",
+          name
+        )),
+      };
+    }
+
+    if value == "transparent" {
+      return Ok(COLOR_TRANSPARENT);
+    }
+
+    NAMED_COLORS
+      .binary_search_by(|(name, _)| (*name).cmp(value.as_str()))
+      .map(|index| {
+        let rgb = NAMED_COLORS[index].1;
+        Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+      })
+      .map_err(|_| format!("Unknown color name '{}'", input.trim()))
+  }
+
+  pub fn to_hsl(&self) -> HslColor {
+    let (r, g, b) = (unit(self.r), unit(self.g), unit(self.b));
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+
+    let (h, s) = if d == 0.0 {
+      (0.0, 0.0)
+    } else {
+      let s = d / (1.0 - (2.0 * l - 1.0).abs());
+      (hue(r, g, b, max, d), s)
+    };
+
+    HslColor {
+      h,
+      s,
+      l,
+      a: unit(self.a),
+    }
+  }
+
+  pub fn from_hsl(hsl: &HslColor) -> Color {
+    let s = hsl.s.clamp(0.0, 1.0);
+    let l = hsl.l.clamp(0.0, 1.0);
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let (r, g, b) = hue_to_rgb(hsl.h, c);
+    let m = l - c / 2.0;
+    Color::new(to_u8(r + m), to_u8(g + m), to_u8(b + m), to_u8(hsl.a))
+  }
+
+  pub fn to_hsv(&self) -> HsvColor {
+    let (r, g, b) = (unit(self.r), unit(self.g), unit(self.b));
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let d = max - min;
+
+    HsvColor {
+      h: if d == 0.0 { 0.0 } else { hue(r, g, b, max, d) },
+      s: if max == 0.0 { 0.0 } else { d / max },
+      v: max,
+      a: unit(self.a),
+    }
+  }
+
+  pub fn from_hsv(hsv: &HsvColor) -> Color {
+    let s = hsv.s.clamp(0.0, 1.0);
+    let v = hsv.v.clamp(0.0, 1.0);
+    let c = v * s;
+    let (r, g, b) = hue_to_rgb(hsv.h, c);
+    let m = v - c;
+    Color::new(to_u8(r + m), to_u8(g + m), to_u8(b + m), to_u8(hsv.a))
+  }
+
+  pub fn to_linear(&self) -> LinearRgbColor {
+    LinearRgbColor {
+      r: srgb_to_linear(unit(self.r)),
+      g: srgb_to_linear(unit(self.g)),
+      b: srgb_to_linear(unit(self.b)),
+      a: unit(self.a),
+    }
+  }
+
+  pub fn from_linear(linear: &LinearRgbColor) -> Color {
+    Color::new(
+      to_u8(linear_to_srgb(linear.r)),
+      to_u8(linear_to_srgb(linear.g)),
+      to_u8(linear_to_srgb(linear.b)),
+      to_u8(linear.a),
+    )
+  }
+
+  pub fn to_oklab(&self) -> OklabColor {
+    let LinearRgbColor { r, g, b, a } = self.to_linear();
+
+    let l = (0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b).cbrt();
+    let m = (0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b).cbrt();
+    let s = (0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b).cbrt();
+
+    OklabColor {
+      l: 0.210_454_255_3 * l + 0.793_617_785 * m - 0.004_072_046_8 * s,
+      a: 1.977_998_495_1 * l - 2.428_592_205 * m + 0.450_593_709_9 * s,
+      b: 0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766 * s,
+      alpha: a,
+    }
+  }
+
+  pub fn from_oklab(lab: &OklabColor) -> Color {
+    Color::from_linear(&oklab_to_linear(lab))
   }
 }
 
+fn oklab_to_linear(lab: &OklabColor) -> LinearRgbColor {
+  let l = (lab.l + 0.396_337_777_4 * lab.a + 0.215_803_757_3 * lab.b).powi(3);
+  let m = (lab.l - 0.105_561_345_8 * lab.a - 0.063_854_172_8 * lab.b).powi(3);
+  let s = (lab.l - 0.089_484_177_5 * lab.a - 1.291_485_548 * lab.b).powi(3);
+
+  LinearRgbColor {
+    r: 4.076_741_662_1 * l - 3.307_711_591_3 * m + 0.230_969_929_2 * s,
+    g: -1.268_438_004_6 * l + 2.609_757_401_1 * m - 0.341_319_396_5 * s,
+    b: -0.004_196_086_3 * l - 0.703_418_614_7 * m + 1.707_614_701 * s,
+    a: lab.alpha,
+  }
+}
+
+/// How the color channels of an RGBA buffer relate to its alpha channel
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AlphaMode {
+  /// Color channels are independent of alpha (the default for frames and images)
+  #[default]
+  Straight,
+  /// Color channels are already multiplied by alpha
+  Premultiplied,
+}
+
+pub use crate::simd::scalar::{mul_div_255, premultiply_pixel, unpremultiply_pixel};
+
+/// Source-over blend of one pixel onto another in the given alpha convention
+#[inline]
+pub fn blend_pixel(src: [u8; 4], dst: [u8; 4], mode: AlphaMode) -> [u8; 4] {
+  match mode {
+    AlphaMode::Straight => scalar::blend_straight_pixel(src, dst),
+    AlphaMode::Premultiplied => scalar::blend_premultiplied_pixel(src, dst),
+  }
+}
+
+/// Source-over blend a row of pixels onto another in the given alpha convention
+pub fn blend_row(src: &[u8], dst: &mut [u8], mode: AlphaMode) {
+  match mode {
+    AlphaMode::Straight => simd::blend_straight(src, dst),
+    AlphaMode::Premultiplied => simd::blend_premultiplied(src, dst),
+  }
+}
+
+/// Color spaces available for interpolation
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ColorSpace {
+  /// Interpolate the gamma-encoded sRGB bytes directly
+  #[default]
+  Srgb,
+  /// Interpolate in linear light (gamma-correct)
+  LinearRgb,
+  /// Interpolate in the perceptually uniform OKLab space
+  Oklab,
+}
+
+/// Hue in degrees (0..360), saturation, lightness and alpha in 0..1
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HslColor {
+  pub h: f64,
+  pub s: f64,
+  pub l: f64,
+  pub a: f64,
+}
+
+/// Hue in degrees (0..360), saturation, value and alpha in 0..1
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HsvColor {
+  pub h: f64,
+  pub s: f64,
+  pub v: f64,
+  pub a: f64,
+}
+
+/// Linear-light sRGB channels and alpha in 0..1
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearRgbColor {
+  pub r: f64,
+  pub g: f64,
+  pub b: f64,
+  pub a: f64,
+}
+
+/// OKLab lightness (0..1), `a`/`b` opponent axes and alpha (0..1)
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OklabColor {
+  pub l: f64,
+  pub a: f64,
+  pub b: f64,
+  pub alpha: f64,
+}
+
+#[inline]
+fn unit(value: u8) -> f64 {
+  value as f64 / 255.0
+}
+
+#[inline]
+fn to_u8(value: f64) -> u8 {
+  (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[inline]
+fn srgb_to_linear(c: f64) -> f64 {
+  if c <= 0.04045 {
+    c / 12.92
+  } else {
+    ((c + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+#[inline]
+fn linear_to_srgb(c: f64) -> f64 {
+  let c = c.clamp(0.0, 1.0);
+  if c <= 0.003_130_8 {
+    c * 12.92
+  } else {
+    1.055 * c.powf(1.0 / 2.4) - 0.055
+  }
+}
+
+/// Hue in degrees for a non-gray color
+fn hue(r: f64, g: f64, b: f64, max: f64, d: f64) -> f64 {
+  let h = if max == r {
+    ((g - b) / d).rem_euclid(6.0)
+  } else if max == g {
+    (b - r) / d + 2.0
+  } else {
+    (r - g) / d + 4.0
+  };
+  h * 60.0
+}
+
+/// RGB offsets for a hue in degrees and chroma, before adding the lightness match
+fn hue_to_rgb(h: f64, c: f64) -> (f64, f64, f64) {
+  let h = h.rem_euclid(360.0) / 60.0;
+  let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+  match h as u32 {
+    0 => (c, x, 0.0),
+    1 => (x, c, 0.0),
+    2 => (0.0, c, x),
+    3 => (0.0, x, c),
+    4 => (x, 0.0, c),
+    _ => (c, 0.0, x),
+  }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+  if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+    return None;
+  }
+  let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok();
+  let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+  match hex.len() {
+    3 => Some(Color::new(
+      digit(0)? * 17,
+      digit(1)? * 17,
+      digit(2)? * 17,
+      255,
+    )),
+    4 => Some(Color::new(
+      digit(0)? * 17,
+      digit(1)? * 17,
+      digit(2)? * 17,
+      digit(3)? * 17,
+    )),
+    6 => Some(Color::new(byte(0)?, byte(2)?, byte(4)?, 255)),
+    8 => Some(Color::new(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+    _ => None,
+  }
+}
+
+/// Splits function arguments in either `a, b, c[, d]` or `a b c[ / d]` syntax
+fn split_args(name: &str, args: &str) -> std::result::Result<Vec<String>, String> {
+  let parts: Vec<String> = if args.contains(',') {
+    args.split(',').map(|p| p.trim().to_string()).collect()
+  } else {
+    let (main, alpha) = match args.split_once('/') {
+      Some((main, alpha)) => (main, Some(alpha.trim())),
+      None => (args, None),
+    };
+    let mut parts: Vec<String> = main.split_whitespace().map(str::to_string).collect();
+    if let Some(alpha) = alpha {
+      parts.push(alpha.to_string());
+    }
+    parts
+  };
+
+  if parts.len() != 3 && parts.len() != 4 {
+    return Err(format!(
+      "{}() expects 3 or 4 components, got {}",
+      name,
+      parts.len()
+    ));
+  }
+  Ok(parts)
+}
+
+fn parse_number(name: &str, part: &str) -> std::result::Result<f64, String> {
+  part
+    .parse::<f64>()
+    .ok()
+    .filter(|v| v.is_finite())
+    .ok_or_else(|| format!("Invalid number '{}' in {}()", part, name))
+}
+
+/// Parses a number or percentage, scaling percentages to `scale`
+fn parse_scaled(name: &str, part: &str, scale: f64) -> std::result::Result<f64, String> {
+  match part.strip_suffix('%') {
+    Some(percent) => Ok(parse_number(name, percent)? / 100.0 * scale),
+    None => parse_number(name, part),
+  }
+}
+
+fn parse_alpha(name: &str, part: Option<&String>) -> std::result::Result<u8, String> {
+  match part {
+    Some(part) => Ok(to_u8(parse_scaled(name, part, 1.0)?)),
+    None => Ok(255),
+  }
+}
+
+fn parse_rgb_function(name: &str, args: &str) -> std::result::Result<Color, String> {
+  let parts = split_args(name, args)?;
+  let channel = |part: &str| -> std::result::Result<u8, String> {
+    Ok(parse_scaled(name, part, 255.0)?.round().clamp(0.0, 255.0) as u8)
+  };
+  Ok(Color::new(
+    channel(&parts[0])?,
+    channel(&parts[1])?,
+    channel(&parts[2])?,
+    parse_alpha(name, parts.get(3))?,
+  ))
+}
+
+fn parse_hsl_function(name: &str, args: &str) -> std::result::Result<Color, String> {
+  let parts = split_args(name, args)?;
+  let h = parse_number(name, parts[0].trim_end_matches("deg"))?;
+  // Saturation and lightness are percentages; bare numbers are read as percent too
+  let percent = |part: &str| -> std::result::Result<f64, String> {
+    Ok(parse_number(name, part.trim_end_matches('%'))? / 100.0)
+  };
+  let mut color = Color::from_hsl(&HslColor {
+    h,
+    s: percent(&parts[1])?,
+    l: percent(&parts[2])?,
+    a: 1.0,
+  });
+  color.a = parse_alpha(name, parts.get(3))?;
+  Ok(color)
+}
+
 // Common colors as constants
 pub const COLOR_RED: Color = Color {
   r: 255,
@@ -136,89 +636,315 @@ pub const COLOR_TRANSPARENT: Color = Color {
 };
 
 // NAPI exports
-#[napi]
+#[napi(catch_unwind)]
 pub fn create_color(r: u8, g: u8, b: u8, a: u8) -> Color {
   Color::new(r, g, b, a)
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_red() -> Color {
   COLOR_RED
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_green() -> Color {
   COLOR_GREEN
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_blue() -> Color {
   COLOR_BLUE
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_black() -> Color {
   COLOR_BLACK
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_white() -> Color {
   COLOR_WHITE
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_yellow() -> Color {
   COLOR_YELLOW
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_cyan() -> Color {
   COLOR_CYAN
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_magenta() -> Color {
   COLOR_MAGENTA
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_gray() -> Color {
   COLOR_GRAY
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_dark_gray() -> Color {
   COLOR_DARK_GRAY
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_light_gray() -> Color {
   COLOR_LIGHT_GRAY
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_orange() -> Color {
   COLOR_ORANGE
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_pink() -> Color {
   COLOR_PINK
 }
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_transparent() -> Color {
   COLOR_TRANSPARENT
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_to_rgba(color: Color) -> Vec<u8> {
   vec![color.r, color.g, color.b, color.a]
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_to_hex(color: Color) -> String {
   color.to_hex()
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn color_to_rgb_hex(color: Color) -> String {
   color.to_rgb_hex()
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn blend_colors(foreground: Color, background: Color) -> Color {
   foreground.blend(&background)
 }
 
-#[napi]
+/// Blend premultiplied-alpha colors
+#[napi(catch_unwind)]
+pub fn blend_colors_premultiplied(foreground: Color, background: Color) -> Color {
+  foreground.blend_premultiplied(&background)
+}
+
+#[napi(catch_unwind)]
+pub fn premultiply_color(color: Color) -> Color {
+  color.premultiply()
+}
+
+#[napi(catch_unwind)]
+pub fn unpremultiply_color(color: Color) -> Color {
+  color.unpremultiply()
+}
+
+#[napi(catch_unwind)]
 pub fn lerp_colors(color1: Color, color2: Color, t: f64) -> Color {
   color1.lerp(&color2, t)
 }
+
+/// Interpolate two colors in the given color space
+#[napi(catch_unwind)]
+pub fn lerp_colors_in(color1: Color, color2: Color, t: f64, space: ColorSpace) -> Color {
+  color1.lerp_in(&color2, t, space)
+}
+
+/// Blend foreground over background in linear light
+#[napi(catch_unwind)]
+pub fn blend_colors_linear(foreground: Color, background: Color) -> Color {
+  foreground.blend_linear(&background)
+}
+
+/// Parse a CSS color string
+#[napi(catch_unwind)]
+pub fn parse_color(input: String) -> Result<Color> {
+  Color::parse(&input).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+#[napi(catch_unwind)]
+pub fn color_to_hsl(color: Color) -> HslColor {
+  color.to_hsl()
+}
+
+#[napi(catch_unwind)]
+pub fn color_from_hsl(hsl: HslColor) -> Color {
+  Color::from_hsl(&hsl)
+}
+
+#[napi(catch_unwind)]
+pub fn color_to_hsv(color: Color) -> HsvColor {
+  color.to_hsv()
+}
+
+#[napi(catch_unwind)]
+pub fn color_from_hsv(hsv: HsvColor) -> Color {
+  Color::from_hsv(&hsv)
+}
+
+#[napi(catch_unwind)]
+pub fn color_to_linear(color: Color) -> LinearRgbColor {
+  color.to_linear()
+}
+
+#[napi(catch_unwind)]
+pub fn color_from_linear(linear: LinearRgbColor) -> Color {
+  Color::from_linear(&linear)
+}
+
+#[napi(catch_unwind)]
+pub fn color_to_oklab(color: Color) -> OklabColor {
+  color.to_oklab()
+}
+
+#[napi(catch_unwind)]
+pub fn color_from_oklab(oklab: OklabColor) -> Color {
+  Color::from_oklab(&oklab)
+}
+
+/// CSS named colors, sorted by name for binary search
+const NAMED_COLORS: [(&str, u32); 148] = [
+  ("aliceblue", 0xF0F8FF),
+  ("antiquewhite", 0xFAEBD7),
+  ("aqua", 0x00FFFF),
+  ("aquamarine", 0x7FFFD4),
+  ("azure", 0xF0FFFF),
+  ("beige", 0xF5F5DC),
+  ("bisque", 0xFFE4C4),
+  ("black", 0x000000),
+  ("blanchedalmond", 0xFFEBCD),
+  ("blue", 0x0000FF),
+  ("blueviolet", 0x8A2BE2),
+  ("brown", 0xA52A2A),
+  ("burlywood", 0xDEB887),
+  ("cadetblue", 0x5F9EA0),
+  ("chartreuse", 0x7FFF00),
+  ("chocolate", 0xD2691E),
+  ("coral", 0xFF7F50),
+  ("cornflowerblue", 0x6495ED),
+  ("cornsilk", 0xFFF8DC),
+  ("crimson", 0xDC143C),
+  ("cyan", 0x00FFFF),
+  ("darkblue", 0x00008B),
+  ("darkcyan", 0x008B8B),
+  ("darkgoldenrod", 0xB8860B),
+  ("darkgray", 0xA9A9A9),
+  ("darkgreen", 0x006400),
+  ("darkgrey", 0xA9A9A9),
+  ("darkkhaki", 0xBDB76B),
+  ("darkmagenta", 0x8B008B),
+  ("darkolivegreen", 0x556B2F),
+  ("darkorange", 0xFF8C00),
+  ("darkorchid", 0x9932CC),
+  ("darkred", 0x8B0000),
+  ("darksalmon", 0xE9967A),
+  ("darkseagreen", 0x8FBC8F),
+  ("darkslateblue", 0x483D8B),
+  ("darkslategray", 0x2F4F4F),
+  ("darkslategrey", 0x2F4F4F),
+  ("darkturquoise", 0x00CED1),
+  ("darkviolet", 0x9400D3),
+  ("deeppink", 0xFF1493),
+  ("deepskyblue", 0x00BFFF),
+  ("dimgray", 0x696969),
+  ("dimgrey", 0x696969),
+  ("dodgerblue", 0x1E90FF),
+  ("firebrick", 0xB22222),
+  ("floralwhite", 0xFFFAF0),
+  ("forestgreen", 0x228B22),
+  ("fuchsia", 0xFF00FF),
+  ("gainsboro", 0xDCDCDC),
+  ("ghostwhite", 0xF8F8FF),
+  ("gold", 0xFFD700),
+  ("goldenrod", 0xDAA520),
+  ("gray", 0x808080),
+  ("green", 0x008000),
+  ("greenyellow", 0xADFF2F),
+  ("grey", 0x808080),
+  ("honeydew", 0xF0FFF0),
+  ("hotpink", 0xFF69B4),
+  ("indianred", 0xCD5C5C),
+  ("indigo", 0x4B0082),
+  ("ivory", 0xFFFFF0),
+  ("khaki", 0xF0E68C),
+  ("lavender", 0xE6E6FA),
+  ("lavenderblush", 0xFFF0F5),
+  ("lawngreen", 0x7CFC00),
+  ("lemonchiffon", 0xFFFACD),
+  ("lightblue", 0xADD8E6),
+  ("lightcoral", 0xF08080),
+  ("lightcyan", 0xE0FFFF),
+  ("lightgoldenrodyellow", 0xFAFAD2),
+  ("lightgray", 0xD3D3D3),
+  ("lightgreen", 0x90EE90),
+  ("lightgrey", 0xD3D3D3),
+  ("lightpink", 0xFFB6C1),
+  ("lightsalmon", 0xFFA07A),
+  ("lightseagreen", 0x20B2AA),
+  ("lightskyblue", 0x87CEFA),
+  ("lightslategray", 0x778899),
+  ("lightslategrey", 0x778899),
+  ("lightsteelblue", 0xB0C4DE),
+  ("lightyellow", 0xFFFFE0),
+  ("lime", 0x00FF00),
+  ("limegreen", 0x32CD32),
+  ("linen", 0xFAF0E6),
+  ("magenta", 0xFF00FF),
+  ("maroon", 0x800000),
+  ("mediumaquamarine", 0x66CDAA),
+  ("mediumblue", 0x0000CD),
+  ("mediumorchid", 0xBA55D3),
+  ("mediumpurple", 0x9370DB),
+  ("mediumseagreen", 0x3CB371),
+  ("mediumslateblue", 0x7B68EE),
+  ("mediumspringgreen", 0x00FA9A),
+  ("mediumturquoise", 0x48D1CC),
+  ("mediumvioletred", 0xC71585),
+  ("midnightblue", 0x191970),
+  ("mintcream", 0xF5FFFA),
+  ("mistyrose", 0xFFE4E1),
+  ("moccasin", 0xFFE4B5),
+  ("navajowhite", 0xFFDEAD),
+  ("navy", 0x000080),
+  ("oldlace", 0xFDF5E6),
+  ("olive", 0x808000),
+  ("olivedrab", 0x6B8E23),
+  ("orange", 0xFFA500),
+  ("orangered", 0xFF4500),
+  ("orchid", 0xDA70D6),
+  ("palegoldenrod", 0xEEE8AA),
+  ("palegreen", 0x98FB98),
+  ("paleturquoise", 0xAFEEEE),
+  ("palevioletred", 0xDB7093),
+  ("papayawhip", 0xFFEFD5),
+  ("peachpuff", 0xFFDAB9),
+  ("peru", 0xCD853F),
+  ("pink", 0xFFC0CB),
+  ("plum", 0xDDA0DD),
+  ("powderblue", 0xB0E0E6),
+  ("purple", 0x800080),
+  ("rebeccapurple", 0x663399),
+  ("red", 0xFF0000),
+  ("rosybrown", 0xBC8F8F),
+  ("royalblue", 0x4169E1),
+  ("saddlebrown", 0x8B4513),
+  ("salmon", 0xFA8072),
+  ("sandybrown", 0xF4A460),
+  ("seagreen", 0x2E8B57),
+  ("seashell", 0xFFF5EE),
+  ("sienna", 0xA0522D),
+  ("silver", 0xC0C0C0),
+  ("skyblue", 0x87CEEB),
+  ("slateblue", 0x6A5ACD),
+  ("slategray", 0x708090),
+  ("slategrey", 0x708090),
+  ("snow", 0xFFFAFA),
+  ("springgreen", 0x00FF7F),
+  ("steelblue", 0x4682B4),
+  ("tan", 0xD2B48C),
+  ("teal", 0x008080),
+  ("thistle", 0xD8BFD8),
+  ("tomato", 0xFF6347),
+  ("turquoise", 0x40E0D0),
+  ("violet", 0xEE82EE),
+  ("wheat", 0xF5DEB3),
+  ("white", 0xFFFFFF),
+  ("whitesmoke", 0xF5F5F5),
+  ("yellow", 0xFFFF00),
+  ("yellowgreen", 0x9ACD32),
+];
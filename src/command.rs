@@ -0,0 +1,313 @@
+//! Batched draw commands executed against a surface in a single call
+//!
+//! Commands can be passed as objects or in a compact binary encoding: a
+//! sequence of records, each a one-byte opcode (the `DrawCommandKind` index)
+//! followed by its little-endian `i32` arguments and four RGBA bytes.
+//!
+//! | opcode | kind       | arguments            |
+//! |--------|------------|----------------------|
+//! | 0      | Fill       |                      |
+//! | 1      | Pixel      | x, y                 |
+//! | 2      | Line       | x1, y1, x2, y2       |
+//! | 3      | Rectangle  | x, y, width, height  |
+//! | 4      | Circle     | cx, cy, radius       |
+//! | 5      | FillCircle | cx, cy, radius       |
+
+use crate::buffer::{with_layout_surface, BufferLayout};
+use crate::color::{AlphaMode, Color};
+use crate::surface::Surface;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawCommandKind {
+  /// Fill the whole surface
+  Fill,
+  Pixel,
+  Line,
+  Rectangle,
+  Circle,
+  FillCircle,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawCommand {
+  pub kind: DrawCommandKind,
+  /// Pixel position, line start, rectangle corner or circle center
+  pub x: Option<i32>,
+  pub y: Option<i32>,
+  /// Line end point
+  pub x2: Option<i32>,
+  pub y2: Option<i32>,
+  pub width: Option<u32>,
+  pub height: Option<u32>,
+  pub radius: Option<u32>,
+  pub color: Color,
+}
+
+/// A draw command with all of its required fields resolved
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+  Fill(Color),
+  Pixel(i64, i64, Color),
+  Line(i64, i64, i64, i64, Color),
+  Rectangle(i64, i64, i64, i64, Color),
+  Circle(i64, i64, i64, Color),
+  FillCircle(i64, i64, i64, Color),
+}
+
+impl DrawCommand {
+  fn resolve(&self) -> std::result::Result<Op, String> {
+    fn get<T: Into<i64> + Copy>(value: Option<T>, name: &str) -> std::result::Result<i64, String> {
+      value
+        .map(Into::into)
+        .ok_or_else(|| format!("missing `{}`", name))
+    }
+    let color = self.color;
+    Ok(match self.kind {
+      DrawCommandKind::Fill => Op::Fill(color),
+      DrawCommandKind::Pixel => Op::Pixel(get(self.x, "x")?, get(self.y, "y")?, color),
+      DrawCommandKind::Line => Op::Line(
+        get(self.x, "x")?,
+        get(self.y, "y")?,
+        get(self.x2, "x2")?,
+        get(self.y2, "y2")?,
+        color,
+      ),
+      DrawCommandKind::Rectangle => Op::Rectangle(
+        get(self.x, "x")?,
+        get(self.y, "y")?,
+        get(self.width, "width")?,
+        get(self.height, "height")?,
+        color,
+      ),
+      DrawCommandKind::Circle => Op::Circle(
+        get(self.x, "x")?,
+        get(self.y, "y")?,
+        get(self.radius, "radius")?,
+        color,
+      ),
+      DrawCommandKind::FillCircle => Op::FillCircle(
+        get(self.x, "x")?,
+        get(self.y, "y")?,
+        get(self.radius, "radius")?,
+        color,
+      ),
+    })
+  }
+}
+
+/// Validate a whole command list up front so nothing is drawn on error
+pub fn resolve_commands(commands: &[DrawCommand]) -> Result<Vec<Op>> {
+  commands
+    .iter()
+    .enumerate()
+    .map(|(index, command)| {
+      command.resolve().map_err(|e| {
+        Error::new(
+          Status::InvalidArg,
+          format!("Command {} ({:?}): {}", index, command.kind, e),
+        )
+      })
+    })
+    .collect()
+}
+
+impl Op {
+  fn opcode(&self) -> u8 {
+    match self {
+      Op::Fill(..) => 0,
+      Op::Pixel(..) => 1,
+      Op::Line(..) => 2,
+      Op::Rectangle(..) => 3,
+      Op::Circle(..) => 4,
+      Op::FillCircle(..) => 5,
+    }
+  }
+
+  fn kind(&self) -> DrawCommandKind {
+    match self {
+      Op::Fill(..) => DrawCommandKind::Fill,
+      Op::Pixel(..) => DrawCommandKind::Pixel,
+      Op::Line(..) => DrawCommandKind::Line,
+      Op::Rectangle(..) => DrawCommandKind::Rectangle,
+      Op::Circle(..) => DrawCommandKind::Circle,
+      Op::FillCircle(..) => DrawCommandKind::FillCircle,
+    }
+  }
+
+  /// Arguments in encoding order, named like the `DrawCommand` fields
+  fn args(&self) -> (Vec<(&'static str, i64)>, Color) {
+    match *self {
+      Op::Fill(c) => (vec![], c),
+      Op::Pixel(x, y, c) => (vec![("x", x), ("y", y)], c),
+      Op::Line(x1, y1, x2, y2, c) => (vec![("x", x1), ("y", y1), ("x2", x2), ("y2", y2)], c),
+      Op::Rectangle(x, y, w, h, c) => (vec![("x", x), ("y", y), ("width", w), ("height", h)], c),
+      Op::Circle(x, y, r, c) | Op::FillCircle(x, y, r, c) => {
+        (vec![("x", x), ("y", y), ("radius", r)], c)
+      }
+    }
+  }
+
+  fn color_mut(&mut self) -> &mut Color {
+    match self {
+      Op::Fill(c)
+      | Op::Pixel(.., c)
+      | Op::Line(.., c)
+      | Op::Rectangle(.., c)
+      | Op::Circle(.., c)
+      | Op::FillCircle(.., c) => c,
+    }
+  }
+
+  /// Convert the straight-alpha command color to the target convention
+  pub fn in_alpha_mode(mut self, mode: AlphaMode) -> Op {
+    let color = self.color_mut();
+    *color = color.in_alpha_mode(mode);
+    self
+  }
+}
+
+/// Decode a binary command list, validating every record before returning
+pub fn decode_commands(bytes: &[u8]) -> Result<Vec<Op>> {
+  let mut ops = Vec::new();
+  let mut offset = 0;
+  while offset < bytes.len() {
+    let index = ops.len();
+    let opcode = bytes[offset];
+    let argc = match opcode {
+      0 => 0,
+      1 => 2,
+      2 | 3 => 4,
+      4 | 5 => 3,
+      _ => {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!(
+            "Command {} (byte {}): unknown opcode {}",
+            index, offset, opcode
+          ),
+        ))
+      }
+    };
+    let end = offset + 1 + argc * 4 + 4;
+    let Some(record) = bytes.get(offset + 1..end) else {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!(
+          "Command {} (byte {}): truncated, expected {} bytes but only {} remain",
+          index,
+          offset,
+          end - offset,
+          bytes.len() - offset
+        ),
+      ));
+    };
+    let arg = |i: usize| i32::from_le_bytes(record[i * 4..i * 4 + 4].try_into().unwrap()) as i64;
+    let rgba = &record[argc * 4..];
+    let color = Color::new(rgba[0], rgba[1], rgba[2], rgba[3]);
+    let non_negative = |i: usize, name: &str| {
+      let value = arg(i);
+      if value < 0 {
+        Err(Error::new(
+          Status::InvalidArg,
+          format!(
+            "Command {} (byte {}): `{}` must not be negative",
+            index, offset, name
+          ),
+        ))
+      } else {
+        Ok(value)
+      }
+    };
+    ops.push(match opcode {
+      0 => Op::Fill(color),
+      1 => Op::Pixel(arg(0), arg(1), color),
+      2 => Op::Line(arg(0), arg(1), arg(2), arg(3), color),
+      3 => Op::Rectangle(
+        arg(0),
+        arg(1),
+        non_negative(2, "width")?,
+        non_negative(3, "height")?,
+        color,
+      ),
+      4 => Op::Circle(arg(0), arg(1), non_negative(2, "radius")?, color),
+      _ => Op::FillCircle(arg(0), arg(1), non_negative(2, "radius")?, color),
+    });
+    offset = end;
+  }
+  Ok(ops)
+}
+
+/// Encode commands, rejecting sizes too large for the `i32` arguments
+pub fn encode_commands(ops: &[Op]) -> Result<Vec<u8>> {
+  let mut bytes = Vec::new();
+  for (index, op) in ops.iter().enumerate() {
+    let (args, color) = op.args();
+    bytes.push(op.opcode());
+    for (name, arg) in args {
+      let arg = i32::try_from(arg).map_err(|_| {
+        Error::new(
+          Status::InvalidArg,
+          format!(
+            "Command {} ({:?}): `{}` is {}, more than the binary encoding's maximum of {}",
+            index,
+            op.kind(),
+            name,
+            arg,
+            i32::MAX
+          ),
+        )
+      })?;
+      bytes.extend_from_slice(&arg.to_le_bytes());
+    }
+    bytes.extend_from_slice(&color.to_rgba());
+  }
+  Ok(bytes)
+}
+
+pub fn execute(surface: &mut Surface, ops: &[Op]) {
+  for op in ops {
+    match *op {
+      Op::Fill(c) => surface.fill(&c),
+      Op::Pixel(x, y, c) => surface.put(x, y, &c),
+      Op::Line(x1, y1, x2, y2, c) => surface.draw_line(x1, y1, x2, y2, &c),
+      Op::Rectangle(x, y, w, h, c) => surface.fill_rect(x, y, w, h, &c),
+      Op::Circle(cx, cy, r, c) => surface.draw_circle(cx, cy, r, &c),
+      Op::FillCircle(cx, cy, r, c) => surface.fill_circle(cx, cy, r, &c),
+    }
+  }
+}
+
+// NAPI exports
+/// Apply a list of draw commands to the caller's buffer in one call
+#[napi(catch_unwind)]
+pub fn draw_batch(
+  mut buffer: Buffer,
+  layout: BufferLayout,
+  commands: Vec<DrawCommand>,
+) -> Result<()> {
+  let ops = resolve_commands(&commands)?;
+  with_layout_surface(&mut buffer, &layout, |surface| execute(surface, &ops))
+}
+
+/// Apply a binary-encoded command list to the caller's buffer in one call
+#[napi(catch_unwind)]
+pub fn draw_batch_encoded(
+  mut buffer: Buffer,
+  layout: BufferLayout,
+  commands: Buffer,
+) -> Result<()> {
+  let ops = decode_commands(&commands)?;
+  with_layout_surface(&mut buffer, &layout, |surface| execute(surface, &ops))
+}
+
+/// Encode command objects into the compact binary form
+#[napi(catch_unwind)]
+pub fn encode_draw_commands(commands: Vec<DrawCommand>) -> Result<Buffer> {
+  Ok(Buffer::from(encode_commands(&resolve_commands(
+    &commands,
+  )?)?))
+}
@@ -0,0 +1,309 @@
+//! Composites the CPU frame onto the `pixels` surface at present time
+//!
+//! Elements and opacity are applied either on the CPU or, when a hardware
+//! adapter is available, by the GPU compositor in `gpu.rs`. Any failure of
+//! the GPU path falls back to the CPU.
+
+use crate::buffer::{with_layout_surface, BufferLayout};
+use crate::color::AlphaMode;
+use crate::element::{draw_elements, Element, ElementOptions};
+use crate::gpu::{headless, GpuAdapterInfo, GpuCompositor, Scene, Target};
+use crate::parallel::for_each_pixels;
+use crate::window::WindowState;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use pixels::{wgpu, Pixels};
+
+/// Where elements and opacity are composited
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CompositorBackend {
+  /// GPU on hardware adapters, CPU on software adapters or without one
+  #[default]
+  Auto,
+  Cpu,
+  /// GPU whenever an adapter is available, including software ones
+  Gpu,
+}
+
+impl CompositorBackend {
+  /// Whether to composite on an adapter of the given type
+  fn uses_gpu(self, device_type: wgpu::DeviceType) -> bool {
+    match self {
+      CompositorBackend::Auto => device_type != wgpu::DeviceType::Cpu,
+      CompositorBackend::Cpu => false,
+      CompositorBackend::Gpu => true,
+    }
+  }
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompositeOptions {
+  /// Opacity applied to the result (0..1, default 1)
+  pub opacity: Option<f64>,
+  pub alpha_mode: Option<AlphaMode>,
+  pub backend: Option<CompositorBackend>,
+}
+
+/// Scale a composited frame by the window opacity
+pub fn apply_opacity(dst: &mut [u8], opacity: f32, mode: AlphaMode) {
+  if opacity >= 1.0 {
+    return;
+  }
+
+  if opacity <= 0.0 {
+    dst.fill(0);
+    return;
+  }
+
+  let factor = (opacity * 255.0).round() as u32;
+  match mode {
+    // Opacity only scales coverage, so straight color channels are kept as-is
+    AlphaMode::Straight => {
+      for_each_pixels(dst, |pixels| {
+        for pixel in pixels.chunks_exact_mut(4) {
+          pixel[3] = crate::color::mul_div_255(pixel[3] as u32, factor) as u8;
+        }
+      });
+    }
+    AlphaMode::Premultiplied => {
+      for_each_pixels(dst, |pixels| {
+        for value in pixels.iter_mut() {
+          *value = crate::color::mul_div_255(*value as u32, factor) as u8;
+        }
+      });
+    }
+  }
+}
+
+/// Composite elements and opacity over a frame on the CPU
+fn composite_cpu(
+  dst: &mut [u8],
+  width: u32,
+  height: u32,
+  elements: &[Element],
+  opacity: f32,
+  mode: AlphaMode,
+) {
+  draw_elements(dst, width, height, elements, mode);
+  apply_opacity(dst, opacity, mode);
+}
+
+/// The backend the window's next present will use
+pub fn active_backend(state: &WindowState) -> CompositorBackend {
+  match &state.pixels {
+    Some(pixels)
+      if !state.gpu_failed
+        && state
+          .compositor
+          .uses_gpu(pixels.adapter().get_info().device_type) =>
+    {
+      CompositorBackend::Gpu
+    }
+    _ => CompositorBackend::Cpu,
+  }
+}
+
+/// Alpha convention the window's surface is presented in. Compositors
+/// blend transparent windows as premultiplied; opaque ones ignore alpha, so
+/// the frame's own convention is kept.
+pub fn surface_alpha_mode(state: &WindowState) -> AlphaMode {
+  if state.transparent {
+    AlphaMode::Premultiplied
+  } else {
+    state.alpha_mode
+  }
+}
+
+/// Composite on the CPU and convert the result to the surface's convention
+fn composite_for_surface(
+  dst: &mut [u8],
+  width: u32,
+  height: u32,
+  elements: &[Element],
+  opacity: f32,
+  mode: AlphaMode,
+  surface_mode: AlphaMode,
+) {
+  composite_cpu(dst, width, height, elements, opacity, mode);
+  if mode == AlphaMode::Straight && surface_mode == AlphaMode::Premultiplied {
+    crate::buffer::premultiply_in_place(dst);
+  }
+}
+
+/// Resize the `pixels` texture if it isn't `width` x `height`
+fn fit_buffer(
+  pixels: &mut Pixels,
+  width: u32,
+  height: u32,
+) -> std::result::Result<(), pixels::TextureError> {
+  let extent = pixels.context().texture_extent;
+  if (extent.width, extent.height) != (width, height) {
+    pixels.resize_buffer(width, height)?;
+  }
+  Ok(())
+}
+
+/// Composite the window's frame, elements and opacity and render it to the
+/// surface, placed according to the scale mode
+pub fn present(state: &mut WindowState) -> std::result::Result<(), pixels::Error> {
+  let use_gpu = active_backend(state) == CompositorBackend::Gpu;
+  let surface_mode = surface_alpha_mode(state);
+  let WindowState {
+    pixels,
+    frame,
+    width,
+    height,
+    opacity,
+    elements,
+    alpha_mode,
+    gpu,
+    gpu_failed,
+    scale_mode,
+    surface_width,
+    surface_height,
+    ..
+  } = state;
+  let (width, height) = (*width, *height);
+  let (surface_width, surface_height) = (*surface_width, *surface_height);
+
+  let Some(pixels) = pixels else {
+    return Ok(());
+  };
+  let rect = crate::scale::placement(*scale_mode, width, height, surface_width, surface_height);
+
+  if use_gpu && gpu.is_none() {
+    *gpu = GpuCompositor::new(
+      pixels.device(),
+      pixels.queue(),
+      pixels.render_texture_format(),
+    );
+    *gpu_failed = gpu.is_none();
+  }
+  let Some(compositor) = gpu.as_mut().filter(|_| use_gpu) else {
+    let unscaled = (width, height) == (surface_width, surface_height)
+      && (rect.x, rect.y, rect.width, rect.height) == (0, 0, width, height);
+    if unscaled {
+      fit_buffer(pixels, width, height)?;
+      let dst = pixels.frame_mut();
+      let len = frame.len().min(dst.len());
+      dst[..len].copy_from_slice(&frame[..len]);
+      composite_for_surface(
+        dst,
+        width,
+        height,
+        elements,
+        *opacity,
+        *alpha_mode,
+        surface_mode,
+      );
+    } else {
+      // The texture matches the surface, so the frame is scaled here
+      let mut composed = frame.clone();
+      composite_for_surface(
+        &mut composed,
+        width,
+        height,
+        elements,
+        *opacity,
+        *alpha_mode,
+        surface_mode,
+      );
+      fit_buffer(pixels, surface_width, surface_height)?;
+      let dst = pixels.frame_mut();
+      crate::scale::scale_into(&composed, width, height, dst, surface_width, &rect);
+    }
+    return pixels.render();
+  };
+
+  fit_buffer(pixels, width, height)?;
+  let dst = pixels.frame_mut();
+  let len = frame.len().min(dst.len());
+  dst[..len].copy_from_slice(&frame[..len]);
+  pixels.render_with(|encoder, target, context| {
+    let scene = Scene {
+      frame: &context.texture,
+      width,
+      height,
+      mode: *alpha_mode,
+      elements,
+      opacity: *opacity,
+    };
+    let target = Target {
+      view: target,
+      width: surface_width,
+      height: surface_height,
+      placement: rect,
+      mode: surface_mode,
+    };
+    compositor.render(&context.device, &context.queue, encoder, &scene, &target);
+    Ok(())
+  })
+}
+
+// NAPI exports
+/// Composite layers over a copy of an RGBA frame and return it tightly
+/// packed. Uses the GPU when an adapter is available and falls back to the
+/// CPU otherwise, including for frames too large for the adapter and after
+/// the adapter fails; both produce the same result up to rounding.
+#[napi(catch_unwind)]
+pub fn composite_layers(
+  frame: Buffer,
+  layout: BufferLayout,
+  layers: Vec<ElementOptions>,
+  options: Option<CompositeOptions>,
+) -> Result<Buffer> {
+  let options = options.unwrap_or_default();
+  let opacity = options
+    .opacity
+    .map_or(Some(1.0), crate::animation::clamp_opacity)
+    .ok_or_else(|| Error::new(Status::InvalidArg, "Opacity must be a number"))?;
+  let mode = options.alpha_mode.unwrap_or_default();
+  let backend = options.backend.unwrap_or_default();
+
+  let mut data = frame.to_vec();
+  let (width, height) = (layout.width, layout.height);
+  let mut rgba = with_layout_surface(&mut data, &layout, |surface| {
+    surface.read_rect(0, 0, width, height)
+  })?;
+  let elements: Vec<Element> = layers
+    .iter()
+    .enumerate()
+    .map(|(id, layer)| Element::new(id as u32, layer))
+    .collect();
+
+  if backend != CompositorBackend::Cpu && width > 0 && height > 0 {
+    let mut headless = headless();
+    if let Some(gpu) = headless
+      .as_mut()
+      .filter(|gpu| backend.uses_gpu(gpu.device_type()))
+    {
+      let composited = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        gpu.composite(&rgba, width, height, &elements, opacity, mode)
+      }));
+      match composited {
+        Ok(Some(out)) => return Ok(out.into()),
+        Ok(None) => {}
+        // A device that panicked may be lost; stop using it
+        Err(_) => *headless = None,
+      }
+    }
+  }
+  composite_cpu(&mut rgba, width, height, &elements, opacity, mode);
+  Ok(rgba.into())
+}
+
+/// The adapter `compositeLayers` uses, or null when there is none
+#[napi(catch_unwind)]
+pub fn get_gpu_adapter() -> Option<GpuAdapterInfo> {
+  headless().as_ref().map(|headless| headless.info())
+}
+
+/// Every adapter the system offers, software ones included, as listed on
+/// first use. When this isn't empty but `getGpuAdapter()` is null, the
+/// compositor couldn't be set up.
+#[napi(catch_unwind)]
+pub fn enumerate_gpu_adapters() -> Vec<GpuAdapterInfo> {
+  crate::gpu::adapters()
+}
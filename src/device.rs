@@ -0,0 +1,339 @@
+//! Recovery from lost window surfaces and GPU devices
+//!
+//! After a GPU reset or a display change, presenting fails until the surface,
+//! or the whole device, is created again. The CPU frame is the source of
+//! truth, so recreating them loses nothing. Failed presents are retried with
+//! exponential backoff up to a limit and reported to JS as `RenderError` and
+//! `DeviceLost` events.
+
+use crate::error::{OverlayError, OverlayResult};
+use crate::types::OverlayEvent;
+use crate::window::WindowState;
+use napi_derive::napi;
+use pixels::wgpu::{DeviceLostReason, SurfaceError};
+use pixels::Pixels;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_RETRIES: u32 = 5;
+const DEFAULT_INITIAL_BACKOFF_MS: u32 = 100;
+const DEFAULT_MAX_BACKOFF_MS: u32 = 5000;
+
+#[napi(object)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderRecoveryOptions {
+  /// Failed presents in a row before presenting stops; 0 retries forever.
+  /// Defaults to 5.
+  pub max_retries: Option<u32>,
+  /// Wait before the first retry in milliseconds, doubled for each further
+  /// failure. Defaults to 100.
+  pub initial_backoff_ms: Option<u32>,
+  /// Longest wait between retries in milliseconds. Defaults to 5000.
+  pub max_backoff_ms: Option<u32>,
+}
+
+/// How presenting to a window is doing
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct RenderStatus {
+  /// Presents that failed in a row
+  pub consecutive_failures: u32,
+  /// Times the surface and device were created again
+  pub recoveries: u32,
+  /// Times the GPU device was lost
+  pub device_losses: u32,
+  /// Message of the most recent failure or GPU validation error
+  pub last_error: Option<String>,
+  /// Retries ran out; presenting resumes after a resize or
+  /// `setRenderRecovery()`
+  pub suspended: bool,
+}
+
+/// Set by wgpu callbacks, which may run on any thread
+#[derive(Default)]
+struct DeviceWatch {
+  lost: AtomicBool,
+  reason: Mutex<Option<String>>,
+  /// Latest validation error not reported yet
+  validation: Mutex<Option<String>>,
+}
+
+impl DeviceWatch {
+  fn mark_lost(&self, reason: String) {
+    *crate::recovery::lock_or_repair(&self.reason, |_| {}) = Some(reason);
+    self.lost.store(true, Ordering::SeqCst);
+  }
+}
+
+/// Retry state of a window's presents
+pub struct RenderRecovery {
+  max_retries: u32,
+  initial_backoff: Duration,
+  max_backoff: Duration,
+  failures: u32,
+  recoveries: u32,
+  device_losses: u32,
+  last_error: Option<String>,
+  /// Presents wait until then after a failure
+  pub retry_at: Option<Instant>,
+  watch: Arc<DeviceWatch>,
+}
+
+impl Default for RenderRecovery {
+  fn default() -> Self {
+    Self {
+      max_retries: DEFAULT_MAX_RETRIES,
+      initial_backoff: Duration::from_millis(DEFAULT_INITIAL_BACKOFF_MS as u64),
+      max_backoff: Duration::from_millis(DEFAULT_MAX_BACKOFF_MS as u64),
+      failures: 0,
+      recoveries: 0,
+      device_losses: 0,
+      last_error: None,
+      retry_at: None,
+      watch: Arc::default(),
+    }
+  }
+}
+
+impl RenderRecovery {
+  /// Recovery state watching the device of `pixels`
+  pub fn watching(pixels: &Pixels) -> Self {
+    let mut recovery = Self::default();
+    recovery.watch(pixels);
+    recovery
+  }
+
+  /// Apply new limits and start retrying again
+  pub fn configure(&mut self, options: &RenderRecoveryOptions) -> OverlayResult<()> {
+    let initial = options
+      .initial_backoff_ms
+      .unwrap_or(DEFAULT_INITIAL_BACKOFF_MS);
+    let max = options.max_backoff_ms.unwrap_or(DEFAULT_MAX_BACKOFF_MS);
+    if max < initial {
+      return Err(OverlayError::invalid_arg(format!(
+        "maxBackoffMs ({}) must be at least initialBackoffMs ({})",
+        max, initial
+      )));
+    }
+    self.max_retries = options.max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+    self.initial_backoff = Duration::from_millis(initial as u64);
+    self.max_backoff = Duration::from_millis(max as u64);
+    self.resume();
+    Ok(())
+  }
+
+  pub fn status(&self) -> RenderStatus {
+    RenderStatus {
+      consecutive_failures: self.failures,
+      recoveries: self.recoveries,
+      device_losses: self.device_losses,
+      last_error: self.last_error.clone(),
+      suspended: self.suspended(),
+    }
+  }
+
+  /// Whether retries ran out
+  pub fn suspended(&self) -> bool {
+    self.max_retries > 0 && self.failures >= self.max_retries
+  }
+
+  /// Retry right away, with the full number of retries
+  pub fn resume(&mut self) {
+    self.failures = 0;
+    self.retry_at = None;
+  }
+
+  /// Watch the device of newly created `pixels` for loss. Running out of GPU
+  /// memory counts as a loss too, as the device can't be trusted after it.
+  /// Validation errors only affect the call that caused them, so instead of
+  /// panicking like wgpu would, they are kept for the next present to report.
+  fn watch(&mut self, pixels: &Pixels) {
+    let watch = Arc::new(DeviceWatch::default());
+    let device = pixels.device();
+    let lost = watch.clone();
+    device.set_device_lost_callback(move |reason, message| {
+      // Dropped and replaced devices are ours to replace
+      if matches!(
+        reason,
+        DeviceLostReason::Unknown | DeviceLostReason::Destroyed
+      ) {
+        lost.mark_lost(format!("Device lost: {}", message));
+      }
+    });
+    let errored = watch.clone();
+    device.on_uncaptured_error(Box::new(move |error| match error {
+      pixels::wgpu::Error::OutOfMemory { .. } => {
+        errored.mark_lost(format!("GPU error: {}", error));
+      }
+      pixels::wgpu::Error::Validation { description, .. } => {
+        *crate::recovery::lock_or_repair(&errored.validation, |_| {}) =
+          Some(format!("GPU validation error: {}", description));
+      }
+    }));
+    self.watch = watch;
+  }
+
+  /// Why the device was lost, once per loss
+  fn take_device_loss(&self) -> Option<String> {
+    if !self.watch.lost.swap(false, Ordering::SeqCst) {
+      return None;
+    }
+    let reason = crate::recovery::lock_or_repair(&self.watch.reason, |_| {}).take();
+    Some(reason.unwrap_or_else(|| "Device lost".to_string()))
+  }
+
+  /// The latest validation error, once
+  fn take_validation_error(&self) -> Option<String> {
+    crate::recovery::lock_or_repair(&self.watch.validation, |_| {}).take()
+  }
+
+  fn succeed(&mut self) {
+    self.failures = 0;
+    self.retry_at = None;
+  }
+
+  /// Wait before the next retry, doubling with each failure in a row
+  fn backoff(&self) -> Duration {
+    let doublings = self.failures.saturating_sub(1).min(16);
+    self
+      .initial_backoff
+      .saturating_mul(1 << doublings)
+      .min(self.max_backoff)
+  }
+
+  /// Count a failure and schedule the retry
+  fn fail(&mut self, message: String) {
+    self.failures += 1;
+    let backoff = self.backoff();
+    self.retry_at = (!self.suspended()).then(|| Instant::now() + backoff);
+    self.last_error = Some(message);
+  }
+}
+
+/// Whether a present error goes away by creating the surface again
+fn needs_new_surface(error: &pixels::Error) -> bool {
+  matches!(
+    error,
+    pixels::Error::Surface(SurfaceError::Lost | SurfaceError::Outdated | SurfaceError::OutOfMemory)
+  )
+}
+
+/// Create the surface and device of the window again. The old surface is
+/// dropped first, as a window can only have one.
+fn recreate(state: &mut WindowState) -> Result<(), pixels::Error> {
+  let Some(window) = state.window.clone() else {
+    return Ok(());
+  };
+  // The GPU compositor's resources belong to the old device
+  state.gpu = None;
+  state.gpu_failed = false;
+  state.pixels = None;
+  let pixels = crate::window::create_pixels(&window, state.transparent)?;
+  state.render_recovery.watch(&pixels);
+  state.render_recovery.recoveries += 1;
+  state.pixels = Some(pixels);
+  Ok(())
+}
+
+/// Present the window, recovering from a lost surface or device. While a
+/// retry is pending nothing is presented; the event loop wakes up for it.
+pub fn present_recovering(state: &mut WindowState) -> OverlayResult<()> {
+  let Some(window) = state.window.clone() else {
+    return Ok(());
+  };
+  let recovery = &state.render_recovery;
+  if recovery.suspended() {
+    return Err(OverlayError::SurfaceLost(format!(
+      "Presenting stopped after {} failed attempts: {}",
+      recovery.failures,
+      recovery.last_error.as_deref().unwrap_or("unknown error")
+    )));
+  }
+  if recovery.retry_at.is_some_and(|at| at > Instant::now()) {
+    return Ok(());
+  }
+
+  let mut result = Ok(());
+  if let Some(reason) = state.render_recovery.take_device_loss() {
+    state.render_recovery.device_losses += 1;
+    state.render_recovery.last_error = Some(reason);
+    state.emit(OverlayEvent::DeviceLost);
+    result = recreate(state);
+  } else if state.pixels.is_none() {
+    // An earlier recreation failed
+    result = recreate(state);
+  }
+  let result = result
+    .and_then(|()| match crate::compositor::present(state) {
+      // pixels already reconfigured the surface once
+      Err(error) if needs_new_surface(&error) => {
+        recreate(state).and_then(|()| crate::compositor::present(state))
+      }
+      result => result,
+    })
+    .map_err(OverlayError::from);
+
+  match result {
+    Ok(()) => {
+      state.render_recovery.succeed();
+      // Reported like failures, but presenting goes on without retries
+      if let Some(message) = state.render_recovery.take_validation_error() {
+        state.render_recovery.last_error = Some(message);
+        state.emit(OverlayEvent::RenderError);
+      }
+    }
+    Err(ref error) => {
+      state.render_recovery.fail(error.to_string());
+      state.emit(OverlayEvent::RenderError);
+    }
+  }
+  // Losses are often only noticed while submitting
+  if state.render_recovery.watch.lost.load(Ordering::SeqCst) {
+    window.request_redraw();
+  }
+  result
+}
+
+/// Retry schedule of a simulated run of failed presents
+#[cfg(feature = "test-hooks")]
+#[napi(object)]
+pub struct SimulatedRecovery {
+  /// Wait scheduled after each failure, until presenting is suspended
+  pub backoff_ms: Vec<u32>,
+  /// Status after the last failure
+  pub status: RenderStatus,
+  /// Status after `setRenderRecovery` is called again with the same options
+  pub reconfigured: RenderStatus,
+}
+
+// NAPI exports
+/// Fail `failures` presents in a row under `options` without a window or GPU.
+/// For testing the retry schedule; only present in builds with the
+/// `test-hooks` feature.
+#[cfg(feature = "test-hooks")]
+#[napi(catch_unwind)]
+pub fn simulate_render_failures(
+  env: napi::Env,
+  options: RenderRecoveryOptions,
+  failures: u32,
+) -> napi::Result<SimulatedRecovery> {
+  use crate::error::IntoJsResult;
+
+  let mut recovery = RenderRecovery::default();
+  recovery.configure(&options).into_js(&env)?;
+  let mut backoff_ms = Vec::new();
+  for attempt in 1..=failures {
+    recovery.fail(format!("Simulated failure {}", attempt));
+    if recovery.retry_at.is_some() {
+      backoff_ms.push(recovery.backoff().as_millis() as u32);
+    }
+  }
+  let status = recovery.status();
+  recovery.configure(&options).into_js(&env)?;
+  Ok(SimulatedRecovery {
+    backoff_ms,
+    status,
+    reconfigured: recovery.status(),
+  })
+}
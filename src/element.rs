@@ -0,0 +1,151 @@
+//! Retained elements composited over the frame
+
+use crate::color::{blend_row, AlphaMode, Color};
+use crate::parallel::for_each_row;
+use crate::sprite::{registry_or_reset, SpriteDrawOptions};
+use crate::surface::Surface;
+use napi_derive::napi;
+
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct ElementOptions {
+  pub x: Option<f64>,
+  pub y: Option<f64>,
+  pub width: Option<f64>,
+  pub height: Option<f64>,
+  pub color: Option<Color>,
+  pub opacity: Option<f64>,
+  /// Id of a registered image drawn scaled into the element instead of a
+  /// filled rectangle; an empty string switches back to the color
+  pub image: Option<String>,
+}
+
+/// A filled rectangle or image layer kept by the window and drawn on every composite
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+  pub id: u32,
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+  pub color: Color,
+  pub opacity: f32,
+  pub image: Option<String>,
+}
+
+impl Element {
+  pub fn new(id: u32, options: &ElementOptions) -> Self {
+    let mut element = Self {
+      id,
+      x: 0.0,
+      y: 0.0,
+      width: 0.0,
+      height: 0.0,
+      color: crate::color::COLOR_WHITE,
+      opacity: 1.0,
+      image: None,
+    };
+    element.apply(options);
+    element
+  }
+
+  /// Overwrite the properties present in `options`
+  pub fn apply(&mut self, options: &ElementOptions) {
+    if let Some(x) = options.x {
+      self.x = x;
+    }
+    if let Some(y) = options.y {
+      self.y = y;
+    }
+    if let Some(width) = options.width {
+      self.width = width.max(0.0);
+    }
+    if let Some(height) = options.height {
+      self.height = height.max(0.0);
+    }
+    if let Some(color) = options.color {
+      self.color = color;
+    }
+    if let Some(opacity) = options.opacity.and_then(crate::animation::clamp_opacity) {
+      self.opacity = opacity;
+    }
+    if let Some(image) = &options.image {
+      self.image = (!image.is_empty()).then(|| image.clone());
+    }
+  }
+}
+
+/// Blend elements over an RGBA frame in insertion order
+pub fn draw_elements(
+  frame: &mut [u8],
+  frame_width: u32,
+  frame_height: u32,
+  elements: &[Element],
+  mode: AlphaMode,
+) {
+  if frame.len() < crate::buffer::calculate_buffer_size(frame_width, frame_height) {
+    return;
+  }
+
+  for element in elements {
+    if let Some(id) = &element.image {
+      draw_image_element(frame, frame_width, frame_height, element, id, mode);
+      continue;
+    }
+    let alpha = (element.color.a as f32 * element.opacity).round() as u8;
+    if alpha == 0 {
+      continue;
+    }
+    let rgba = Color::new(element.color.r, element.color.g, element.color.b, alpha)
+      .in_alpha_mode(mode)
+      .to_rgba();
+
+    let x0 = element.x.round().clamp(0.0, frame_width as f64) as usize;
+    let y0 = element.y.round().clamp(0.0, frame_height as f64) as usize;
+    let x1 = (element.x + element.width)
+      .round()
+      .clamp(0.0, frame_width as f64) as usize;
+    let y1 = (element.y + element.height)
+      .round()
+      .clamp(0.0, frame_height as f64) as usize;
+
+    let stride = frame_width as usize * 4;
+    if y1 <= y0 {
+      continue;
+    }
+    let src = rgba.repeat(x1.saturating_sub(x0));
+    for_each_row(&mut frame[y0 * stride..y1 * stride], stride, |_, row| {
+      blend_row(&src, &mut row[x0 * 4..x1 * 4], mode)
+    });
+  }
+}
+
+/// Draw an image element; images that are not registered are skipped
+fn draw_image_element(
+  frame: &mut [u8],
+  frame_width: u32,
+  frame_height: u32,
+  element: &Element,
+  id: &str,
+  mode: AlphaMode,
+) {
+  let mut sprites = registry_or_reset();
+  let Ok(sprite) = sprites.get(id) else {
+    return;
+  };
+  let x0 = element.x.round();
+  let y0 = element.y.round();
+  let width = ((element.x + element.width).round() - x0).max(0.0) as u32;
+  let height = ((element.y + element.height).round() - y0).max(0.0) as u32;
+  if width == 0 || height == 0 {
+    return;
+  }
+  let options = SpriteDrawOptions {
+    width: Some(width),
+    height: Some(height),
+    blend: Some(true),
+    opacity: Some(element.opacity as f64),
+  };
+  let mut surface = Surface::packed(frame, frame_width, frame_height);
+  sprite.draw(&mut surface, x0 as i64, y0 as i64, &options, mode);
+}
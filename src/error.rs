@@ -0,0 +1,212 @@
+//! Errors thrown to JS with a stable `code` and structured fields
+//!
+//! napi only turns a `Status` into the `code` of a thrown error, so window
+//! errors are built as JS objects here, with the `Env` of the call.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::fmt;
+
+/// The `code` of errors thrown by windows and the app
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorCode {
+  /// The window hasn't been created yet
+  WindowNotInitialized,
+  /// The window was closed
+  WindowDestroyed,
+  /// A frame buffer doesn't match the frame size; has `expected` and `got`
+  /// byte counts
+  BufferSizeMismatch,
+  /// The event loop was handed to `run()` or couldn't be created
+  EventLoopUnavailable,
+  /// `run()` was called while the event loop is already running
+  EventLoopRunning,
+  /// `run()` was called before any window was created
+  NoWindows,
+  /// Strict mode rejected a call the platform can't do; has `feature`,
+  /// `platform` and `backend`
+  Unsupported,
+  /// The window surface was lost and couldn't be presented to
+  SurfaceLost,
+  /// A native platform call failed
+  PlatformError,
+  /// A panic interrupted an earlier call; the state it left was repaired
+  /// and the call can be retried
+  StatePoisoned,
+  /// An argument was out of range or malformed
+  InvalidArg,
+  /// Any other failure
+  GenericFailure,
+}
+
+#[derive(Debug)]
+pub enum OverlayError {
+  WindowNotInitialized,
+  WindowDestroyed,
+  BufferSizeMismatch {
+    expected: usize,
+    got: usize,
+  },
+  EventLoopUnavailable(String),
+  EventLoopRunning,
+  NoWindows,
+  Unsupported {
+    feature: &'static str,
+    platform: &'static str,
+    backend: &'static str,
+  },
+  SurfaceLost(String),
+  Platform(String),
+  StatePoisoned,
+  /// Errors from napi and the drawing modules, kept as they are
+  Napi(Error),
+}
+
+pub type OverlayResult<T> = std::result::Result<T, OverlayError>;
+
+/// Conversion of controller results for returning from `#[napi]` methods
+pub trait IntoJsResult<T> {
+  fn into_js(self, env: &Env) -> Result<T>;
+}
+
+impl<T> IntoJsResult<T> for OverlayResult<T> {
+  fn into_js(self, env: &Env) -> Result<T> {
+    self.map_err(|error| error.into_js(env))
+  }
+}
+
+impl OverlayError {
+  pub fn invalid_arg(message: impl Into<String>) -> Self {
+    OverlayError::Napi(Error::new(Status::InvalidArg, message.into()))
+  }
+
+  /// The event loop was handed to `run()`
+  pub fn event_loop_taken() -> Self {
+    OverlayError::EventLoopUnavailable("Event loop not available".to_string())
+  }
+
+  pub fn platform(message: impl Into<String>) -> Self {
+    OverlayError::Platform(message.into())
+  }
+
+  pub fn code(&self) -> ErrorCode {
+    match self {
+      OverlayError::WindowNotInitialized => ErrorCode::WindowNotInitialized,
+      OverlayError::WindowDestroyed => ErrorCode::WindowDestroyed,
+      OverlayError::BufferSizeMismatch { .. } => ErrorCode::BufferSizeMismatch,
+      OverlayError::EventLoopUnavailable(_) => ErrorCode::EventLoopUnavailable,
+      OverlayError::EventLoopRunning => ErrorCode::EventLoopRunning,
+      OverlayError::NoWindows => ErrorCode::NoWindows,
+      OverlayError::Unsupported { .. } => ErrorCode::Unsupported,
+      OverlayError::SurfaceLost(_) => ErrorCode::SurfaceLost,
+      OverlayError::Platform(_) => ErrorCode::PlatformError,
+      OverlayError::StatePoisoned => ErrorCode::StatePoisoned,
+      OverlayError::Napi(error) if error.status == Status::InvalidArg => ErrorCode::InvalidArg,
+      OverlayError::Napi(_) => ErrorCode::GenericFailure,
+    }
+  }
+
+  /// The error as thrown to JS: an `Error` with `code` and the fields of the
+  /// variant. Falls back to a plain error if the object can't be built.
+  pub fn into_js(self, env: &Env) -> Error {
+    match self {
+      OverlayError::Napi(error) => error,
+      error => {
+        let status = match error {
+          OverlayError::Unsupported { .. } => Status::InvalidArg,
+          _ => Status::GenericFailure,
+        };
+        error
+          .build(env, status)
+          .unwrap_or_else(|_| Error::new(status, error.to_string()))
+      }
+    }
+  }
+
+  fn build(&self, env: &Env, status: Status) -> Result<Error> {
+    let mut object = env.create_error(Error::new(status, self.to_string()))?;
+    object.set("code", self.code())?;
+    match self {
+      OverlayError::BufferSizeMismatch { expected, got } => {
+        object.set("expected", *expected as f64)?;
+        object.set("got", *got as f64)?;
+      }
+      OverlayError::Unsupported {
+        feature,
+        platform,
+        backend,
+      } => {
+        object.set("feature", *feature)?;
+        object.set("platform", *platform)?;
+        object.set("backend", *backend)?;
+      }
+      _ => {}
+    }
+    Ok(Error::from(object.to_unknown()))
+  }
+}
+
+impl fmt::Display for OverlayError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      OverlayError::WindowNotInitialized => write!(f, "Window not initialized"),
+      OverlayError::WindowDestroyed => write!(f, "Window has been closed"),
+      OverlayError::BufferSizeMismatch { expected, got } => {
+        write!(
+          f,
+          "Buffer size mismatch: expected {}, got {}",
+          expected, got
+        )
+      }
+      OverlayError::EventLoopUnavailable(reason) => write!(f, "{}", reason),
+      OverlayError::EventLoopRunning => write!(f, "Event loop already running"),
+      OverlayError::NoWindows => write!(f, "No windows created"),
+      OverlayError::Unsupported {
+        feature,
+        platform,
+        backend,
+      } => write!(
+        f,
+        "`{}` is not supported on {} ({})",
+        feature, platform, backend
+      ),
+      OverlayError::SurfaceLost(reason) => write!(f, "Surface lost: {}", reason),
+      OverlayError::Platform(reason) => write!(f, "{}", reason),
+      OverlayError::StatePoisoned => write!(
+        f,
+        "State was reset after a panic in an earlier call; retry the call"
+      ),
+      OverlayError::Napi(error) => write!(f, "{}", error.reason),
+    }
+  }
+}
+
+impl From<pixels::Error> for OverlayError {
+  fn from(error: pixels::Error) -> Self {
+    use pixels::wgpu::SurfaceError;
+
+    match error {
+      pixels::Error::Surface(SurfaceError::Lost | SurfaceError::Outdated) => {
+        OverlayError::SurfaceLost(error.to_string())
+      }
+      error => OverlayError::Platform(format!("Render error: {}", error)),
+    }
+  }
+}
+
+/// Without an `Env` only the message and a generic `code` survive
+impl From<OverlayError> for Error {
+  fn from(error: OverlayError) -> Self {
+    match error {
+      OverlayError::Napi(error) => error,
+      error => Error::new(Status::GenericFailure, error.to_string()),
+    }
+  }
+}
+
+impl From<Error> for OverlayError {
+  fn from(error: Error) -> Self {
+    OverlayError::Napi(error)
+  }
+}
@@ -0,0 +1,544 @@
+//! Image filters applied to RGBA buffers, frame regions and images
+//!
+//! Filters run in order on straight RGBA. Blurs, shadows and glows work on
+//! premultiplied copies internally so transparent pixels don't bleed color,
+//! and pixels outside the filtered area count as transparent.
+
+use crate::buffer::{with_layout_surface, BufferLayout};
+use crate::color::{mul_div_255, AlphaMode, Color};
+use crate::parallel::{for_each_pixels, for_each_row};
+use crate::simd;
+use crate::surface::Surface;
+use crate::types::Rect;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::collections::VecDeque;
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterKind {
+  /// Average over a square of `radius` pixels around each pixel
+  BoxBlur,
+  /// Gaussian blur with standard deviation `radius`
+  GaussianBlur,
+  /// Blurred copy of the shape in `color`, offset and drawn underneath
+  DropShadow,
+  /// Shape grown by `radius` pixels in `color`, drawn underneath
+  Outline,
+  /// Shape blurred by `radius` in `color`, drawn underneath
+  Glow,
+  /// Desaturate by `amount` (0 to 1, default 1)
+  Grayscale,
+  /// Multiply color channels by `amount`
+  Brightness,
+  /// Scale color channels away from mid-gray by `amount`
+  Contrast,
+  /// Mix color channels toward `color` by its alpha
+  Tint,
+  /// 4x5 row-major `matrix` applied to `[r, g, b, a, 1]` in 0 to 1 units
+  ColorMatrix,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+  pub kind: FilterKind,
+  pub radius: Option<f64>,
+  pub amount: Option<f64>,
+  pub color: Option<Color>,
+  /// Drop shadow offset
+  pub offset_x: Option<i32>,
+  pub offset_y: Option<i32>,
+  pub matrix: Option<Vec<f64>>,
+}
+
+/// A filter with all of its required fields resolved
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+  BoxBlur(u32),
+  GaussianBlur(f64),
+  DropShadow {
+    dx: i32,
+    dy: i32,
+    sigma: f64,
+    color: Color,
+  },
+  Outline(u32, Color),
+  Glow(f64, Color),
+  Grayscale(f64),
+  Brightness(f64),
+  Contrast(f64),
+  Tint(Color),
+  ColorMatrix([f32; 20]),
+}
+
+/// Largest `radius` a filter accepts
+const MAX_RADIUS: f64 = 256.0;
+/// Largest drop shadow offset in either direction
+const MAX_OFFSET: i32 = 1024;
+
+impl Filter {
+  fn resolve(&self) -> std::result::Result<Effect, String> {
+    let radius = || match self.radius {
+      Some(r) if (0.0..=MAX_RADIUS).contains(&r) => Ok(r),
+      Some(_) => Err(format!(
+        "`radius` must be a number from 0 to {}",
+        MAX_RADIUS
+      )),
+      None => Err("missing `radius`".to_string()),
+    };
+    let offset = |name: &str, value: Option<i32>| match value.unwrap_or(0) {
+      v if (-MAX_OFFSET..=MAX_OFFSET).contains(&v) => Ok(v),
+      _ => Err(format!(
+        "`{}` must be between -{} and {}",
+        name, MAX_OFFSET, MAX_OFFSET
+      )),
+    };
+    let amount = || match self.amount {
+      Some(a) if a.is_finite() && a >= 0.0 => Ok(a),
+      Some(_) => Err("`amount` must be a non-negative number".to_string()),
+      None => Err("missing `amount`".to_string()),
+    };
+    let color = || self.color.ok_or_else(|| "missing `color`".to_string());
+    Ok(match self.kind {
+      FilterKind::BoxBlur => Effect::BoxBlur(radius()?.round() as u32),
+      FilterKind::GaussianBlur => Effect::GaussianBlur(radius()?),
+      FilterKind::DropShadow => Effect::DropShadow {
+        dx: offset("offsetX", self.offset_x)?,
+        dy: offset("offsetY", self.offset_y)?,
+        sigma: self.radius.map_or(Ok(0.0), |_| radius())?,
+        color: color()?,
+      },
+      FilterKind::Outline => Effect::Outline(radius()?.round() as u32, color()?),
+      FilterKind::Glow => Effect::Glow(radius()?, color()?),
+      FilterKind::Grayscale => {
+        Effect::Grayscale(self.amount.map_or(Ok(1.0), |_| amount())?.min(1.0))
+      }
+      FilterKind::Brightness => Effect::Brightness(amount()?),
+      FilterKind::Contrast => Effect::Contrast(amount()?),
+      FilterKind::Tint => Effect::Tint(color()?),
+      FilterKind::ColorMatrix => {
+        let matrix = self.matrix.as_deref().ok_or("missing `matrix`")?;
+        let values: [f64; 20] = matrix
+          .try_into()
+          .map_err(|_| format!("`matrix` must have 20 values, got {}", matrix.len()))?;
+        Effect::ColorMatrix(values.map(|v| v as f32))
+      }
+    })
+  }
+}
+
+/// Validate a whole filter list up front so nothing is changed on error
+pub fn resolve_filters(filters: &[Filter]) -> Result<Vec<Effect>> {
+  filters
+    .iter()
+    .enumerate()
+    .map(|(index, filter)| {
+      filter.resolve().map_err(|e| {
+        Error::new(
+          Status::InvalidArg,
+          format!("Filter {} ({:?}): {}", index, filter.kind, e),
+        )
+      })
+    })
+    .collect()
+}
+
+/// Apply effects in order to a packed straight RGBA image
+pub fn apply(data: &mut [u8], width: u32, height: u32, effects: &[Effect]) {
+  let (w, h) = (width as usize, height as usize);
+  if w == 0 || h == 0 {
+    return;
+  }
+  let data = &mut data[..w * h * 4];
+  for effect in effects {
+    match *effect {
+      Effect::BoxBlur(radius) => {
+        simd::premultiply(data);
+        box_blur(data, w, h, radius as usize);
+        simd::unpremultiply(data);
+      }
+      Effect::GaussianBlur(sigma) => {
+        simd::premultiply(data);
+        gaussian_blur(data, w, h, sigma);
+        simd::unpremultiply(data);
+      }
+      Effect::DropShadow {
+        dx,
+        dy,
+        sigma,
+        color,
+      } => {
+        let mut shadow = silhouette(data, w, h, color, dx as isize, dy as isize);
+        gaussian_blur(&mut shadow, w, h, sigma);
+        underlay(data, &shadow);
+      }
+      Effect::Outline(radius, color) => {
+        let mut shadow = silhouette(data, w, h, color, 0, 0);
+        dilate(&mut shadow, w, h, radius as usize);
+        underlay(data, &shadow);
+      }
+      Effect::Glow(sigma, color) => {
+        let mut shadow = silhouette(data, w, h, color, 0, 0);
+        gaussian_blur(&mut shadow, w, h, sigma);
+        underlay(data, &shadow);
+      }
+      Effect::Grayscale(amount) => {
+        let amount = amount as f32;
+        map_colors(data, |[r, g, b]| {
+          let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+          [r, g, b].map(|c| c + (luma - c) * amount)
+        })
+      }
+      Effect::Brightness(amount) => {
+        let amount = amount as f32;
+        map_colors(data, |rgb| rgb.map(|c| c * amount))
+      }
+      Effect::Contrast(amount) => {
+        let amount = amount as f32;
+        map_colors(data, |rgb| rgb.map(|c| (c - 127.5) * amount + 127.5))
+      }
+      Effect::Tint(color) => {
+        let mix = color.a as f32 / 255.0;
+        let target = [color.r, color.g, color.b].map(|c| c as f32);
+        map_colors(data, |rgb| {
+          [0, 1, 2].map(|i| rgb[i] + (target[i] - rgb[i]) * mix)
+        })
+      }
+      Effect::ColorMatrix(m) => for_each_pixels(data, |pixels| {
+        for p in pixels.chunks_exact_mut(4) {
+          let v = [p[0], p[1], p[2], p[3]].map(|c| c as f32);
+          for (row, out) in p.iter_mut().enumerate() {
+            let m = &m[row * 5..row * 5 + 5];
+            let value = m[0] * v[0] + m[1] * v[1] + m[2] * v[2] + m[3] * v[3] + m[4] * 255.0;
+            *out = value.round().clamp(0.0, 255.0) as u8;
+          }
+        }
+      }),
+    }
+  }
+}
+
+/// How far effects spread past the image: left, top, right and bottom
+fn reach(effects: &[Effect]) -> [usize; 4] {
+  effects.iter().fold([0; 4], |[l, t, r, b], effect| {
+    let spread = |n: usize| [l + n, t + n, r + n, b + n];
+    match *effect {
+      Effect::BoxBlur(radius) | Effect::Outline(radius, _) => spread(radius as usize),
+      Effect::GaussianBlur(sigma) | Effect::Glow(sigma, _) => spread(blur_reach(sigma)),
+      Effect::DropShadow { dx, dy, sigma, .. } => {
+        // The shadow spreads around the shape moved by the offset
+        let n = blur_reach(sigma) as i64;
+        let grow = |v: i64| v.max(0) as usize;
+        [
+          l + grow(n - dx as i64),
+          t + grow(n - dy as i64),
+          r + grow(n + dx as i64),
+          b + grow(n + dy as i64),
+        ]
+      }
+      _ => [l, t, r, b],
+    }
+  })
+}
+
+/// A filtered image on a canvas grown by how far its effects spread
+pub struct Filtered {
+  /// Straight RGBA, tightly packed
+  data: Vec<u8>,
+  width: usize,
+  height: usize,
+  /// Where the image lies in `data`
+  left: usize,
+  top: usize,
+  image_width: usize,
+  image_height: usize,
+}
+
+impl Filtered {
+  /// Filter a packed straight RGBA image, keeping what the effects spread
+  /// past its edges (blur, shadows, outlines, glows)
+  pub fn new(rgba: &[u8], width: u32, height: u32, effects: &[Effect]) -> Self {
+    let [left, top, right, bottom] = reach(effects);
+    let (w, h) = (width as usize, height as usize);
+    let (pw, ph) = (w + left + right, h + top + bottom);
+    let mut data = vec![0; pw * ph * 4];
+    if w > 0 {
+      for (row, src) in rgba.chunks_exact(w * 4).take(h).enumerate() {
+        data[((top + row) * pw + left) * 4..][..w * 4].copy_from_slice(src);
+      }
+    }
+    apply(&mut data, pw as u32, ph as u32, effects);
+    Self {
+      data,
+      width: pw,
+      height: ph,
+      left,
+      top,
+      image_width: w,
+      image_height: h,
+    }
+  }
+
+  /// Draw with the image at `(x, y)`. The image replaces the pixels it
+  /// covers, like a plain blit, and the rest is blended over the surface.
+  pub fn draw(mut self, surface: &mut Surface, x: i64, y: i64, mode: AlphaMode) {
+    if mode == AlphaMode::Premultiplied {
+      simd::premultiply(&mut self.data);
+    }
+    let (px, py) = (x - self.left as i64, y - self.top as i64);
+    let Some((cx, cy, cw, ch)) = surface.clip(px, py, self.width as u32, self.height as u32) else {
+      return;
+    };
+    let mut dst = surface.read_rect(cx, cy, cw, ch);
+    // Columns of the clipped rectangle the image itself covers
+    let image_x0 = (x - cx as i64).clamp(0, cw as i64) as usize;
+    let image_x1 = (x + self.image_width as i64 - cx as i64).clamp(0, cw as i64) as usize;
+    let image_rows = y..y + self.image_height as i64;
+    for_each_row(&mut dst, cw as usize * 4, |row, bytes| {
+      let fy = cy as i64 + row as i64;
+      let start = ((fy - py) as usize * self.width + (cx as i64 - px) as usize) * 4;
+      let src = &self.data[start..][..bytes.len()];
+      if image_rows.contains(&fy) {
+        bytes[image_x0 * 4..image_x1 * 4].fill(0);
+      }
+      match mode {
+        AlphaMode::Straight => simd::blend_straight(src, bytes),
+        AlphaMode::Premultiplied => simd::blend_premultiplied(src, bytes),
+      }
+    });
+    surface.blit(cx as i64, cy as i64, &dst, cw, ch);
+  }
+}
+
+/// Apply effects to a rectangle of a surface (the whole surface when `None`)
+pub fn apply_to_surface(
+  surface: &mut Surface,
+  region: Option<Rect>,
+  mode: AlphaMode,
+  effects: &[Effect],
+) {
+  let region = region.unwrap_or(Rect {
+    x: 0,
+    y: 0,
+    width: surface.width(),
+    height: surface.height(),
+  });
+  let Some((x, y, width, height)) = surface.clip(
+    region.x as i64,
+    region.y as i64,
+    region.width,
+    region.height,
+  ) else {
+    return;
+  };
+  let mut pixels = surface.read_rect(x, y, width, height);
+  if mode == AlphaMode::Premultiplied {
+    simd::unpremultiply(&mut pixels);
+  }
+  apply(&mut pixels, width, height, effects);
+  if mode == AlphaMode::Premultiplied {
+    simd::premultiply(&mut pixels);
+  }
+  surface.blit(x as i64, y as i64, &pixels, width, height);
+}
+
+/// Map the color channels of every pixel, leaving alpha untouched
+fn map_colors(data: &mut [u8], f: impl Fn([f32; 3]) -> [f32; 3] + Sync) {
+  for_each_pixels(data, |pixels| {
+    for p in pixels.chunks_exact_mut(4) {
+      let rgb = f([p[0], p[1], p[2]].map(|c| c as f32));
+      for (out, value) in p.iter_mut().zip(rgb) {
+        *out = value.round().clamp(0.0, 255.0) as u8;
+      }
+    }
+  });
+}
+
+/// Premultiplied `color` covering the shape of `data`, shifted by `(dx, dy)`
+fn silhouette(data: &[u8], w: usize, h: usize, color: Color, dx: isize, dy: isize) -> Vec<u8> {
+  let mut out = vec![0; data.len()];
+  for_each_row(&mut out, w * 4, |y, row| {
+    let sy = y as isize - dy;
+    if sy < 0 || sy >= h as isize {
+      return;
+    }
+    let src = &data[sy as usize * w * 4..][..w * 4];
+    for (x, p) in row.chunks_exact_mut(4).enumerate() {
+      let sx = x as isize - dx;
+      if sx >= 0 && sx < w as isize {
+        let alpha = mul_div_255(src[sx as usize * 4 + 3] as u32, color.a as u32);
+        p.copy_from_slice(&[
+          mul_div_255(color.r as u32, alpha) as u8,
+          mul_div_255(color.g as u32, alpha) as u8,
+          mul_div_255(color.b as u32, alpha) as u8,
+          alpha as u8,
+        ]);
+      }
+    }
+  });
+  out
+}
+
+/// Composite straight `data` over the premultiplied `under` layer
+fn underlay(data: &mut [u8], under: &[u8]) {
+  simd::premultiply(data);
+  let mut out = under.to_vec();
+  simd::blend_premultiplied(data, &mut out);
+  simd::unpremultiply(&mut out);
+  data.copy_from_slice(&out);
+}
+
+/// Three box blurs approximating a gaussian with standard deviation `sigma`
+fn gaussian_blur(data: &mut [u8], w: usize, h: usize, sigma: f64) {
+  for radius in box_radii(sigma) {
+    box_blur(data, w, h, radius);
+  }
+}
+
+/// How far `gaussian_blur` spreads a pixel
+fn blur_reach(sigma: f64) -> usize {
+  box_radii(sigma).iter().sum()
+}
+
+/// Radii of the three box blurs approximating a gaussian
+fn box_radii(sigma: f64) -> [usize; 3] {
+  if sigma <= 0.0 {
+    return [0; 3];
+  }
+  const PASSES: f64 = 3.0;
+  let ideal = (12.0 * sigma * sigma / PASSES + 1.0).sqrt();
+  let mut lower = ideal.floor() as i64;
+  if lower % 2 == 0 {
+    lower -= 1;
+  }
+  let lower_f = lower as f64;
+  let lower_passes =
+    ((12.0 * sigma * sigma - PASSES * lower_f * lower_f - 4.0 * PASSES * lower_f - 3.0 * PASSES)
+      / (-4.0 * lower_f - 4.0))
+      .round() as i64;
+  [0, 1, 2].map(|pass| {
+    let size = if pass < lower_passes {
+      lower
+    } else {
+      lower + 2
+    };
+    (size.max(1) as usize - 1) / 2
+  })
+}
+
+/// Separable box blur of every channel
+fn box_blur(data: &mut [u8], w: usize, h: usize, radius: usize) {
+  if radius == 0 {
+    return;
+  }
+  let window = (2 * radius + 1) as u32;
+  let average = |sum: u32| ((sum + window / 2) / window) as u8;
+
+  // Horizontal pass, one row at a time
+  for_each_row(data, w * 4, |_, row| {
+    let src = row.to_vec();
+    let at = |x: usize, c: usize| src.get(x * 4 + c).map_or(0, |&v| v as u32);
+    let mut sums = [0u32; 4];
+    for (c, sum) in sums.iter_mut().enumerate() {
+      *sum = (0..=radius).map(|x| at(x, c)).sum();
+    }
+    for x in 0..w {
+      for c in 0..4 {
+        row[x * 4 + c] = average(sums[c]);
+        sums[c] += at(x + radius + 1, c);
+        if x >= radius {
+          sums[c] -= at(x - radius, c);
+        }
+      }
+    }
+  });
+
+  // Vertical pass with running sums per column
+  let stride = w * 4;
+  let src = data.to_vec();
+  let row = |y: usize| &src[y * stride..(y + 1) * stride];
+  let mut sums = vec![0u32; stride];
+  for y in 0..=radius.min(h - 1) {
+    for (sum, &v) in sums.iter_mut().zip(row(y)) {
+      *sum += v as u32;
+    }
+  }
+  for y in 0..h {
+    for (out, &sum) in data[y * stride..(y + 1) * stride].iter_mut().zip(&sums) {
+      *out = average(sum);
+    }
+    if y + radius + 1 < h {
+      for (sum, &v) in sums.iter_mut().zip(row(y + radius + 1)) {
+        *sum += v as u32;
+      }
+    }
+    if y >= radius {
+      for (sum, &v) in sums.iter_mut().zip(row(y - radius)) {
+        *sum -= v as u32;
+      }
+    }
+  }
+}
+
+/// Grow a premultiplied layer by taking the strongest pixel within a disc.
+/// The disc is a run of horizontal spans, each scanned with a sliding
+/// maximum, so the cost per pixel grows linearly with the radius.
+fn dilate(data: &mut [u8], w: usize, h: usize, radius: usize) {
+  if radius == 0 {
+    return;
+  }
+  let src = data.to_vec();
+  let r = radius as isize;
+  let spans: Vec<(isize, usize)> = (-r..=r)
+    .map(|dy| (dy, ((r * r - dy * dy) as f64).sqrt() as usize))
+    .collect();
+  for_each_row(data, w * 4, |y, row| {
+    row.fill(0);
+    let mut window = VecDeque::new();
+    for &(dy, half) in &spans {
+      let sy = y as isize + dy;
+      if sy < 0 || sy >= h as isize {
+        continue;
+      }
+      let line = &src[sy as usize * w * 4..][..w * 4];
+      let alpha = |x: usize| line[x * 4 + 3];
+      // Indices of decreasing alpha; the front is the window's strongest
+      window.clear();
+      let mut next = 0;
+      for (x, p) in row.chunks_exact_mut(4).enumerate() {
+        while next < w && next <= x + half {
+          while window.back().is_some_and(|&q| alpha(q) <= alpha(next)) {
+            window.pop_back();
+          }
+          window.push_back(next);
+          next += 1;
+        }
+        while window.front().is_some_and(|&q| q + half < x) {
+          window.pop_front();
+        }
+        if let Some(&q) = window.front() {
+          if alpha(q) > p[3] {
+            p.copy_from_slice(&line[q * 4..q * 4 + 4]);
+          }
+        }
+      }
+    }
+  });
+}
+
+// NAPI exports
+/// Apply filters in order to the caller's straight RGBA buffer, optionally
+/// limited to a region
+#[napi(catch_unwind)]
+pub fn apply_filters(
+  mut buffer: Buffer,
+  layout: BufferLayout,
+  filters: Vec<Filter>,
+  region: Option<Rect>,
+) -> Result<()> {
+  let effects = resolve_filters(&filters)?;
+  with_layout_surface(&mut buffer, &layout, |surface| {
+    apply_to_surface(surface, region, AlphaMode::Straight, &effects)
+  })
+}
@@ -0,0 +1,229 @@
+//! Pixel formats other than RGBA8 and their conversion to and from RGBA8
+
+use crate::parallel::for_each_row;
+use crate::simd;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::borrow::Cow;
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PixelFormat {
+  #[default]
+  Rgba8,
+  Bgra8,
+  Rgb8,
+  Bgr8,
+  /// 8-bit luminance, opaque
+  Gray8,
+  /// 8-bit luminance followed by 8-bit alpha
+  GrayAlpha8,
+  /// 16-bit little-endian 5:6:5 RGB, opaque
+  Rgb565,
+}
+
+impl PixelFormat {
+  pub fn bytes_per_pixel(&self) -> usize {
+    match self {
+      PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+      PixelFormat::Rgb8 | PixelFormat::Bgr8 => 3,
+      PixelFormat::GrayAlpha8 | PixelFormat::Rgb565 => 2,
+      PixelFormat::Gray8 => 1,
+    }
+  }
+
+  /// Validate that `height` rows of `stride` bytes fit in `len` bytes and
+  /// return the stride, defaulting to tightly packed rows
+  pub fn row_stride(
+    &self,
+    len: usize,
+    width: u32,
+    height: u32,
+    stride: Option<u32>,
+  ) -> std::result::Result<usize, String> {
+    let row_bytes = width as usize * self.bytes_per_pixel();
+    let stride = stride.map(|s| s as usize).unwrap_or(row_bytes);
+    if stride < row_bytes {
+      return Err(format!(
+        "Stride {} is smaller than one {:?} row of {} pixels ({} bytes)",
+        stride, self, width, row_bytes
+      ));
+    }
+    let required = match height {
+      0 => 0,
+      h => (h as usize - 1) * stride + row_bytes,
+    };
+    if len < required {
+      return Err(format!(
+        "Buffer too small for {}x{} {:?} (stride {}): expected at least {} bytes, got {}",
+        width, height, self, stride, required, len
+      ));
+    }
+    Ok(stride)
+  }
+
+  /// Convert one row of pixels in this format to RGBA8
+  pub fn decode_row(&self, src: &[u8], dst: &mut [u8]) {
+    match self {
+      PixelFormat::Rgba8 => dst.copy_from_slice(&src[..dst.len()]),
+      PixelFormat::Bgra8 => {
+        dst.copy_from_slice(&src[..dst.len()]);
+        simd::swizzle_rb(dst);
+      }
+      PixelFormat::Rgb8 => {
+        for (p, s) in dst.chunks_exact_mut(4).zip(src.chunks_exact(3)) {
+          p.copy_from_slice(&[s[0], s[1], s[2], 255]);
+        }
+      }
+      PixelFormat::Bgr8 => {
+        for (p, s) in dst.chunks_exact_mut(4).zip(src.chunks_exact(3)) {
+          p.copy_from_slice(&[s[2], s[1], s[0], 255]);
+        }
+      }
+      PixelFormat::Gray8 => {
+        for (p, &l) in dst.chunks_exact_mut(4).zip(src) {
+          p.copy_from_slice(&[l, l, l, 255]);
+        }
+      }
+      PixelFormat::GrayAlpha8 => {
+        for (p, s) in dst.chunks_exact_mut(4).zip(src.chunks_exact(2)) {
+          p.copy_from_slice(&[s[0], s[0], s[0], s[1]]);
+        }
+      }
+      PixelFormat::Rgb565 => {
+        for (p, s) in dst.chunks_exact_mut(4).zip(src.chunks_exact(2)) {
+          let v = u16::from_le_bytes([s[0], s[1]]);
+          let (r, g, b) = ((v >> 11) as u8, (v >> 5) as u8 & 0x3F, v as u8 & 0x1F);
+          p.copy_from_slice(&[
+            (r << 3) | (r >> 2),
+            (g << 2) | (g >> 4),
+            (b << 3) | (b >> 2),
+            255,
+          ]);
+        }
+      }
+    }
+  }
+
+  /// Convert one row of RGBA8 pixels to this format. Alpha is dropped by
+  /// opaque formats, and luminance uses BT.601 weights.
+  pub fn encode_row(&self, src: &[u8], dst: &mut [u8]) {
+    let pixels = src.chunks_exact(4);
+    let luma =
+      |p: &[u8]| ((77 * p[0] as u32 + 150 * p[1] as u32 + 29 * p[2] as u32 + 128) >> 8) as u8;
+    let bits = |c: u8, max: u32| (c as u32 * max + 127) / 255;
+    match self {
+      PixelFormat::Rgba8 => dst.copy_from_slice(&src[..dst.len()]),
+      PixelFormat::Bgra8 => {
+        dst.copy_from_slice(&src[..dst.len()]);
+        simd::swizzle_rb(dst);
+      }
+      PixelFormat::Rgb8 => {
+        for (d, p) in dst.chunks_exact_mut(3).zip(pixels) {
+          d.copy_from_slice(&p[..3]);
+        }
+      }
+      PixelFormat::Bgr8 => {
+        for (d, p) in dst.chunks_exact_mut(3).zip(pixels) {
+          d.copy_from_slice(&[p[2], p[1], p[0]]);
+        }
+      }
+      PixelFormat::Gray8 => {
+        for (d, p) in dst.iter_mut().zip(pixels) {
+          *d = luma(p);
+        }
+      }
+      PixelFormat::GrayAlpha8 => {
+        for (d, p) in dst.chunks_exact_mut(2).zip(pixels) {
+          d.copy_from_slice(&[luma(p), p[3]]);
+        }
+      }
+      PixelFormat::Rgb565 => {
+        for (d, p) in dst.chunks_exact_mut(2).zip(pixels) {
+          let v = (bits(p[0], 31) << 11) | (bits(p[1], 63) << 5) | bits(p[2], 31);
+          d.copy_from_slice(&(v as u16).to_le_bytes());
+        }
+      }
+    }
+  }
+}
+
+/// Convert a whole image into a tightly packed RGBA8 destination
+pub fn decode_into(
+  src: &[u8],
+  width: u32,
+  height: u32,
+  format: PixelFormat,
+  stride: Option<u32>,
+  dst: &mut [u8],
+) -> std::result::Result<(), String> {
+  let src_stride = format.row_stride(src.len(), width, height, stride)?;
+  let row_bytes = width as usize * 4;
+  let len = dst.len().min(row_bytes * height as usize);
+  for_each_row(&mut dst[..len], row_bytes, |y, row| {
+    format.decode_row(&src[y * src_stride..], row)
+  });
+  Ok(())
+}
+
+/// Convert a tightly packed RGBA8 image into a destination in `format`
+pub fn encode_into(
+  src: &[u8],
+  width: u32,
+  height: u32,
+  format: PixelFormat,
+  stride: Option<u32>,
+  dst: &mut [u8],
+) -> std::result::Result<(), String> {
+  let dst_stride = format.row_stride(dst.len(), width, height, stride)?;
+  let row_bytes = width as usize * format.bytes_per_pixel();
+  let len = match height {
+    0 => 0,
+    h => (h as usize - 1) * dst_stride + row_bytes,
+  };
+  for_each_row(&mut dst[..len], dst_stride, |y, row| {
+    format.encode_row(&src[y * width as usize * 4..], &mut row[..row_bytes])
+  });
+  Ok(())
+}
+
+/// View an image as tightly packed RGBA8, converting only when needed
+pub fn to_rgba(
+  src: &[u8],
+  width: u32,
+  height: u32,
+  format: PixelFormat,
+  stride: Option<u32>,
+) -> std::result::Result<Cow<'_, [u8]>, String> {
+  let packed = format.row_stride(src.len(), width, height, stride)? == width as usize * 4;
+  if format == PixelFormat::Rgba8 && packed {
+    return Ok(Cow::Borrowed(src));
+  }
+  let mut rgba = vec![0; crate::buffer::calculate_buffer_size(width, height)];
+  decode_into(src, width, height, format, stride, &mut rgba)?;
+  Ok(Cow::Owned(rgba))
+}
+
+// NAPI exports
+/// Convert pixels between formats; the result has tightly packed rows
+#[napi(catch_unwind)]
+pub fn convert_pixels(
+  buffer: Buffer,
+  width: u32,
+  height: u32,
+  from: PixelFormat,
+  to: PixelFormat,
+  stride: Option<u32>,
+) -> Result<Buffer> {
+  let rgba =
+    to_rgba(&buffer, width, height, from, stride).map_err(|e| Error::new(Status::InvalidArg, e))?;
+  if to == PixelFormat::Rgba8 {
+    return Ok(Buffer::from(rgba.into_owned()));
+  }
+  let row_bytes = width as usize * to.bytes_per_pixel();
+  let mut out = vec![0; row_bytes * height as usize];
+  for_each_row(&mut out, row_bytes, |y, row| {
+    to.encode_row(&rgba[y * width as usize * 4..], row)
+  });
+  Ok(Buffer::from(out))
+}
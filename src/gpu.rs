@@ -0,0 +1,756 @@
+//! Optional GPU compositing with the `wgpu` device behind `pixels`
+//!
+//! The frame texture, retained elements and their images are drawn as
+//! textured quads into an offscreen premultiplied composite, which a second
+//! pass scales onto the surface with the window opacity applied. Output
+//! matches the CPU compositor up to rounding.
+
+use crate::color::AlphaMode;
+use crate::element::Element;
+use crate::sprite::registry_or_reset;
+use crate::types::Rect;
+use napi_derive::napi;
+use pixels::wgpu;
+use pixels::wgpu::util::DeviceExt;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+/// Format of the offscreen composite and of image layer textures
+const LAYER_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+/// Floats per vertex: position, texture coordinates and color
+const VERTEX_FLOATS: usize = 8;
+
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuAdapterInfo {
+  pub name: String,
+  /// Graphics API, e.g. "Vulkan" or "Gl"
+  pub backend: String,
+  /// "DiscreteGpu", "IntegratedGpu", "VirtualGpu", "Cpu" or "Other"
+  pub device_type: String,
+}
+
+impl From<wgpu::AdapterInfo> for GpuAdapterInfo {
+  fn from(info: wgpu::AdapterInfo) -> Self {
+    Self {
+      name: info.name,
+      backend: format!("{:?}", info.backend),
+      device_type: format!("{:?}", info.device_type),
+    }
+  }
+}
+
+/// Uniforms of one texture binding, laid out as in `composite.wgsl`
+#[derive(Debug, Clone, Copy)]
+struct Params {
+  srgb: bool,
+  premultiplied: bool,
+  opacity: f32,
+  /// Fractions of the target: x, y, width, height
+  placement: [f32; 4],
+}
+
+impl Params {
+  fn new(srgb: bool, premultiplied: bool) -> Self {
+    Self {
+      srgb,
+      premultiplied,
+      opacity: 1.0,
+      placement: [0.0, 0.0, 1.0, 1.0],
+    }
+  }
+
+  fn bytes(&self) -> Vec<u8> {
+    let mut bytes = [
+      (self.srgb as u32).to_ne_bytes(),
+      (self.premultiplied as u32).to_ne_bytes(),
+      self.opacity.to_ne_bytes(),
+      0u32.to_ne_bytes(),
+    ]
+    .concat();
+    bytes.extend(self.placement.iter().flat_map(|v| v.to_ne_bytes()));
+    bytes
+  }
+}
+
+/// A texture uploaded from a registered image
+struct ImageLayer {
+  generation: u64,
+  bind_group: wgpu::BindGroup,
+  _texture: wgpu::Texture,
+}
+
+/// The offscreen composite texture
+struct Composite {
+  width: u32,
+  height: u32,
+  view: wgpu::TextureView,
+  _texture: wgpu::Texture,
+}
+
+/// Everything drawn for one presented frame
+pub struct Scene<'a> {
+  /// Texture holding the CPU frame
+  pub frame: &'a wgpu::Texture,
+  pub width: u32,
+  pub height: u32,
+  pub mode: AlphaMode,
+  pub elements: &'a [Element],
+  pub opacity: f32,
+}
+
+/// Where a composited scene is drawn
+pub struct Target<'a> {
+  pub view: &'a wgpu::TextureView,
+  pub width: u32,
+  pub height: u32,
+  /// Where the frame is scaled to; the rest of the target is cleared
+  pub placement: Rect,
+  /// Alpha convention the target stores
+  pub mode: AlphaMode,
+}
+
+pub struct GpuCompositor {
+  layer_pipeline: wgpu::RenderPipeline,
+  present_pipeline: wgpu::RenderPipeline,
+  layout: wgpu::BindGroupLayout,
+  sampler: wgpu::Sampler,
+  /// 1x1 opaque white texture that solid quads sample
+  solid: wgpu::BindGroup,
+  composite: Option<Composite>,
+  /// Whether the present target stores sRGB-encoded values
+  output_srgb: bool,
+  images: HashMap<String, ImageLayer>,
+}
+
+impl GpuCompositor {
+  /// Build the pipelines, returning `None` when the device rejects them
+  pub fn new(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    output_format: wgpu::TextureFormat,
+  ) -> Option<Self> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let compositor = Self::create(device, queue, output_format);
+    match pollster::block_on(device.pop_error_scope()) {
+      Some(_) => None,
+      None => Some(compositor),
+    }
+  }
+
+  fn create(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    output_format: wgpu::TextureFormat,
+  ) -> Self {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("overlay_composite_shader"),
+      source: wgpu::ShaderSource::Wgsl(include_str!("shaders/composite.wgsl").into()),
+    });
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+      label: Some("overlay_composite_bind_group_layout"),
+      entries: &[
+        wgpu::BindGroupLayoutEntry {
+          binding: 0,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+          },
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 1,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+          count: None,
+        },
+        wgpu::BindGroupLayoutEntry {
+          binding: 2,
+          visibility: wgpu::ShaderStages::FRAGMENT,
+          ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: wgpu::BufferSize::new(32),
+          },
+          count: None,
+        },
+      ],
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: Some("overlay_composite_pipeline_layout"),
+      bind_group_layouts: &[&layout],
+      push_constant_ranges: &[],
+    });
+    let pipeline = |label, vertex_entry, fragment_entry, buffers, format, blend| {
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+          module: &module,
+          entry_point: vertex_entry,
+          buffers,
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+          module: &module,
+          entry_point: fragment_entry,
+          targets: &[Some(wgpu::ColorTargetState {
+            format,
+            blend,
+            write_mask: wgpu::ColorWrites::ALL,
+          })],
+        }),
+        multiview: None,
+      })
+    };
+    let attributes = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4];
+    let vertex_layout = [wgpu::VertexBufferLayout {
+      array_stride: (VERTEX_FLOATS * 4) as wgpu::BufferAddress,
+      step_mode: wgpu::VertexStepMode::Vertex,
+      attributes: &attributes,
+    }];
+    let layer_pipeline = pipeline(
+      "overlay_layer_pipeline",
+      "vs_layer",
+      "fs_layer",
+      &vertex_layout,
+      LAYER_FORMAT,
+      Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+    );
+    let present_pipeline = pipeline(
+      "overlay_present_pipeline",
+      "vs_present",
+      "fs_present",
+      &[],
+      output_format,
+      None,
+    );
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("overlay_composite_sampler"),
+      mag_filter: wgpu::FilterMode::Nearest,
+      min_filter: wgpu::FilterMode::Nearest,
+      ..Default::default()
+    });
+
+    // 1x1 opaque white texture for solid quads; the bind group keeps it alive
+    let white = create_texture(device, 1, 1, wgpu::TextureUsages::TEXTURE_BINDING);
+    write_texture(queue, &white, &[255; 4], 1, 1);
+    let view = white.create_view(&wgpu::TextureViewDescriptor::default());
+    let solid = bind_group(device, &layout, &sampler, &view, Params::new(false, false));
+
+    Self {
+      layer_pipeline,
+      present_pipeline,
+      solid,
+      layout,
+      sampler,
+      composite: None,
+      output_srgb: output_format.is_srgb(),
+      images: HashMap::new(),
+    }
+  }
+
+  fn bind(
+    &self,
+    device: &wgpu::Device,
+    view: &wgpu::TextureView,
+    params: Params,
+  ) -> wgpu::BindGroup {
+    bind_group(device, &self.layout, &self.sampler, view, params)
+  }
+
+  /// Upload straight RGBA pixels as a layer texture
+  fn upload(
+    &self,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+  ) -> (wgpu::Texture, wgpu::BindGroup) {
+    let texture = create_texture(device, width, height, wgpu::TextureUsages::TEXTURE_BINDING);
+    write_texture(queue, &texture, rgba, width, height);
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let bind_group = self.bind(device, &view, Params::new(false, false));
+    (texture, bind_group)
+  }
+
+  /// Make sure every image referenced by `elements` has a current texture and
+  /// drop textures no element uses any more
+  fn sync_images(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, elements: &[Element]) {
+    let mut sprites = registry_or_reset();
+    let mut used = HashMap::new();
+    for id in elements.iter().filter_map(|element| element.image.as_ref()) {
+      let Ok(sprite) = sprites.get(id) else {
+        continue;
+      };
+      match self.images.remove(id) {
+        Some(layer) if layer.generation == sprite.generation => {
+          used.insert(id.clone(), layer);
+        }
+        _ if sprite.width > 0 && sprite.height > 0 => {
+          let (texture, bind_group) =
+            self.upload(device, queue, &sprite.packed(), sprite.width, sprite.height);
+          used.insert(
+            id.clone(),
+            ImageLayer {
+              generation: sprite.generation,
+              bind_group,
+              _texture: texture,
+            },
+          );
+        }
+        _ => {}
+      }
+    }
+    self.images = used;
+  }
+
+  /// Composite a scene and scale it onto `target`
+  pub fn render(
+    &mut self,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    encoder: &mut wgpu::CommandEncoder,
+    scene: &Scene,
+    target: &Target,
+  ) {
+    let (width, height) = (scene.width, scene.height);
+    if width == 0 || height == 0 {
+      return;
+    }
+    if !matches!(&self.composite, Some(c) if c.width == width && c.height == height) {
+      let texture = create_texture(
+        device,
+        width,
+        height,
+        wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+      );
+      self.composite = Some(Composite {
+        width,
+        height,
+        view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        _texture: texture,
+      });
+    }
+    self.sync_images(device, queue, scene.elements);
+
+    let frame_view = scene
+      .frame
+      .create_view(&wgpu::TextureViewDescriptor::default());
+    let frame = self.bind(
+      device,
+      &frame_view,
+      Params::new(
+        scene.frame.format().is_srgb(),
+        scene.mode == AlphaMode::Premultiplied,
+      ),
+    );
+
+    // Quads in draw order, each with the bind group it samples
+    let mut vertices: Vec<f32> = Vec::new();
+    let mut draws: Vec<&wgpu::BindGroup> = Vec::new();
+    let mut quad = |rect: (f64, f64, f64, f64), color: [f32; 4]| {
+      let (x0, y0, x1, y1) = rect;
+      let ndc = |x: f64, y: f64| {
+        [
+          (x / width as f64 * 2.0 - 1.0) as f32,
+          (1.0 - y / height as f64 * 2.0) as f32,
+        ]
+      };
+      for (x, y, u, v) in [
+        (x0, y0, 0.0, 0.0),
+        (x1, y0, 1.0, 0.0),
+        (x0, y1, 0.0, 1.0),
+        (x0, y1, 0.0, 1.0),
+        (x1, y0, 1.0, 0.0),
+        (x1, y1, 1.0, 1.0),
+      ] {
+        vertices.extend_from_slice(&ndc(x, y));
+        vertices.extend_from_slice(&[u, v]);
+        vertices.extend_from_slice(&color);
+      }
+    };
+    quad((0.0, 0.0, width as f64, height as f64), [1.0; 4]);
+    draws.push(&frame);
+    for element in scene.elements {
+      // Same pixel snapping as the CPU compositor
+      let rect = (
+        element.x.round(),
+        element.y.round(),
+        (element.x + element.width).round(),
+        (element.y + element.height).round(),
+      );
+      match &element.image {
+        Some(id) => {
+          if let Some(layer) = self.images.get(id) {
+            quad(rect, [element.opacity; 4]);
+            draws.push(&layer.bind_group);
+          }
+        }
+        None => {
+          let alpha = (element.color.a as f32 * element.opacity).round() / 255.0;
+          let channel = |c: u8| c as f32 / 255.0 * alpha;
+          quad(
+            rect,
+            [
+              channel(element.color.r),
+              channel(element.color.g),
+              channel(element.color.b),
+              alpha,
+            ],
+          );
+          draws.push(&self.solid);
+        }
+      }
+    }
+    let vertex_bytes: Vec<u8> = vertices.iter().flat_map(|v| v.to_ne_bytes()).collect();
+    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("overlay_layer_vertices"),
+      contents: &vertex_bytes,
+      usage: wgpu::BufferUsages::VERTEX,
+    });
+
+    let composite = self.composite.as_ref().expect("composite texture exists");
+    {
+      let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("overlay_layer_pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: &composite.view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+      });
+      pass.set_pipeline(&self.layer_pipeline);
+      pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+      for (index, bind_group) in draws.iter().enumerate() {
+        pass.set_bind_group(0, bind_group, &[]);
+        let first = index as u32 * 6;
+        pass.draw(first..first + 6, 0..1);
+      }
+    }
+
+    let present = self.bind(
+      device,
+      &composite.view,
+      Params {
+        opacity: scene.opacity.clamp(0.0, 1.0),
+        placement: fractions(&target.placement, target.width, target.height),
+        ..Params::new(self.output_srgb, target.mode == AlphaMode::Premultiplied)
+      },
+    );
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("overlay_present_pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view: target.view,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      depth_stencil_attachment: None,
+      timestamp_writes: None,
+      occlusion_query_set: None,
+    });
+    pass.set_pipeline(&self.present_pipeline);
+    pass.set_bind_group(0, &present, &[]);
+    pass.draw(0..3, 0..1);
+  }
+}
+
+/// A placement as fractions of the target size; empty placements map outside
+/// the target
+fn fractions(rect: &Rect, width: u32, height: u32) -> [f32; 4] {
+  if rect.width == 0 || rect.height == 0 || width == 0 || height == 0 {
+    return [2.0, 2.0, 1.0, 1.0];
+  }
+  [
+    rect.x as f32 / width as f32,
+    rect.y as f32 / height as f32,
+    rect.width as f32 / width as f32,
+    rect.height as f32 / height as f32,
+  ]
+}
+
+/// Bind a texture with its sampling parameters to the composite layout
+fn bind_group(
+  device: &wgpu::Device,
+  layout: &wgpu::BindGroupLayout,
+  sampler: &wgpu::Sampler,
+  view: &wgpu::TextureView,
+  params: Params,
+) -> wgpu::BindGroup {
+  let uniforms = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+    label: Some("overlay_composite_params"),
+    contents: &params.bytes(),
+    usage: wgpu::BufferUsages::UNIFORM,
+  });
+  device.create_bind_group(&wgpu::BindGroupDescriptor {
+    label: None,
+    layout,
+    entries: &[
+      wgpu::BindGroupEntry {
+        binding: 0,
+        resource: wgpu::BindingResource::TextureView(view),
+      },
+      wgpu::BindGroupEntry {
+        binding: 1,
+        resource: wgpu::BindingResource::Sampler(sampler),
+      },
+      wgpu::BindGroupEntry {
+        binding: 2,
+        resource: uniforms.as_entire_binding(),
+      },
+    ],
+  })
+}
+
+fn create_texture(
+  device: &wgpu::Device,
+  width: u32,
+  height: u32,
+  usage: wgpu::TextureUsages,
+) -> wgpu::Texture {
+  device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("overlay_layer_texture"),
+    size: wgpu::Extent3d {
+      width,
+      height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: LAYER_FORMAT,
+    usage: usage | wgpu::TextureUsages::COPY_DST,
+    view_formats: &[],
+  })
+}
+
+fn write_texture(
+  queue: &wgpu::Queue,
+  texture: &wgpu::Texture,
+  rgba: &[u8],
+  width: u32,
+  height: u32,
+) {
+  queue.write_texture(
+    wgpu::ImageCopyTexture {
+      texture,
+      mip_level: 0,
+      origin: wgpu::Origin3d::ZERO,
+      aspect: wgpu::TextureAspect::All,
+    },
+    rgba,
+    wgpu::ImageDataLayout {
+      offset: 0,
+      bytes_per_row: Some(width * 4),
+      rows_per_image: Some(height),
+    },
+    wgpu::Extent3d {
+      width,
+      height,
+      depth_or_array_layers: 1,
+    },
+  );
+}
+
+/// A device without a window, used by `compositeLayers`
+pub struct Headless {
+  device: wgpu::Device,
+  queue: wgpu::Queue,
+  info: wgpu::AdapterInfo,
+  compositor: GpuCompositor,
+}
+
+/// Shared by the headless device and adapter enumeration for the life of the
+/// process: on the GL backend, dropping an instance invalidates the display
+/// that devices created through it still use
+static INSTANCE: LazyLock<wgpu::Instance> =
+  LazyLock::new(|| wgpu::Instance::new(wgpu::InstanceDescriptor::default()));
+
+/// Created on first use; `None` when there is no usable adapter
+pub static HEADLESS: LazyLock<Mutex<Option<Headless>>> =
+  LazyLock::new(|| Mutex::new(Headless::new()));
+
+/// Listed once, before the headless device is created: on the GL backend,
+/// enumerating again invalidates the display that device uses
+static ADAPTERS: LazyLock<Vec<GpuAdapterInfo>> = LazyLock::new(|| {
+  INSTANCE
+    .enumerate_adapters(wgpu::Backends::all())
+    .into_iter()
+    .map(|adapter| adapter.get_info().into())
+    .collect()
+});
+
+/// Every adapter on any backend, as found on first use
+pub fn adapters() -> Vec<GpuAdapterInfo> {
+  ADAPTERS.clone()
+}
+
+/// Lock the headless GPU. After a panic the device is dropped, as it may be
+/// unusable, and compositing stays on the CPU.
+pub fn headless() -> MutexGuard<'static, Option<Headless>> {
+  crate::recovery::lock_or_repair(&HEADLESS, |headless| *headless = None)
+}
+
+impl Headless {
+  fn new() -> Option<Self> {
+    LazyLock::force(&ADAPTERS);
+    let adapter = pollster::block_on(INSTANCE.request_adapter(&wgpu::RequestAdapterOptions {
+      power_preference: wgpu::PowerPreference::LowPower,
+      force_fallback_adapter: false,
+      compatible_surface: None,
+    }))?;
+    let (device, queue) = pollster::block_on(adapter.request_device(
+      &wgpu::DeviceDescriptor {
+        label: Some("overlay_headless_device"),
+        required_features: wgpu::Features::empty(),
+        required_limits:
+          wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+      },
+      None,
+    ))
+    .ok()?;
+    let compositor = GpuCompositor::new(&device, &queue, LAYER_FORMAT)?;
+    Some(Self {
+      device,
+      queue,
+      info: adapter.get_info(),
+      compositor,
+    })
+  }
+
+  pub fn info(&self) -> GpuAdapterInfo {
+    self.info.clone().into()
+  }
+
+  pub fn device_type(&self) -> wgpu::DeviceType {
+    self.info.device_type
+  }
+
+  /// Composite a packed RGBA frame and read the result back. `None` when
+  /// the frame or a layer is too large for the device; the caller composites
+  /// on the CPU instead.
+  pub fn composite(
+    &mut self,
+    frame: &[u8],
+    width: u32,
+    height: u32,
+    elements: &[Element],
+    opacity: f32,
+    mode: AlphaMode,
+  ) -> Option<Vec<u8>> {
+    // Rows of a texture copy must be aligned
+    let row_bytes = width * 4;
+    let padded =
+      row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let limits = self.device.limits();
+    if width.max(height) > limits.max_texture_dimension_2d
+      || padded as u64 * height as u64 > limits.max_buffer_size
+    {
+      return None;
+    }
+
+    let device = &self.device;
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let texture = create_texture(device, width, height, wgpu::TextureUsages::TEXTURE_BINDING);
+    write_texture(&self.queue, &texture, frame, width, height);
+    let target = create_texture(
+      device,
+      width,
+      height,
+      wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+    );
+    let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: Some("overlay_headless_encoder"),
+    });
+    let scene = Scene {
+      frame: &texture,
+      width,
+      height,
+      mode,
+      elements,
+      opacity,
+    };
+    self.compositor.render(
+      device,
+      &self.queue,
+      &mut encoder,
+      &scene,
+      &Target {
+        view: &target_view,
+        width,
+        height,
+        placement: Rect {
+          x: 0,
+          y: 0,
+          width,
+          height,
+        },
+        mode,
+      },
+    );
+
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("overlay_headless_readback"),
+      size: padded as u64 * height as u64,
+      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+    encoder.copy_texture_to_buffer(
+      wgpu::ImageCopyTexture {
+        texture: &target,
+        mip_level: 0,
+        origin: wgpu::Origin3d::ZERO,
+        aspect: wgpu::TextureAspect::All,
+      },
+      wgpu::ImageCopyBuffer {
+        buffer: &readback,
+        layout: wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(padded),
+          rows_per_image: Some(height),
+        },
+      },
+      wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+      },
+    );
+    let commands = encoder.finish();
+    if pollster::block_on(device.pop_error_scope()).is_some() {
+      // Image layers too large for the device leave invalid textures in the
+      // compositor's cache, so start over with a fresh one
+      self.compositor = GpuCompositor::new(device, &self.queue, LAYER_FORMAT)?;
+      return None;
+    }
+    self.queue.submit(Some(commands));
+
+    let slice = readback.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |_| {});
+    device.poll(wgpu::Maintain::Wait);
+    let mapped = slice.get_mapped_range();
+    let mut out = Vec::with_capacity((row_bytes * height) as usize);
+    for row in mapped.chunks_exact(padded as usize) {
+      out.extend_from_slice(&row[..row_bytes as usize]);
+    }
+    drop(mapped);
+    readback.unmap();
+    Some(out)
+  }
+}
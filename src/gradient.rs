@@ -0,0 +1,356 @@
+//! Gradient paints: linear, radial and conic fills with multiple color stops
+//!
+//! They paint frames, rectangles, lines, circles and canvas paths. There is no
+//! text drawing API, so text is not a paint target.
+
+use crate::color::{premultiply_pixel, Color, ColorSpace};
+use crate::parallel::for_each_row;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::f64::consts::TAU;
+
+/// Number of precomputed colors per gradient
+const LUT_SIZE: usize = 1024;
+
+/// 4x4 ordered dither thresholds, centered around zero
+const BAYER_4X4: [[f32; 4]; 4] = [
+  [-0.46875, 0.03125, -0.34375, 0.15625],
+  [0.28125, -0.21875, 0.40625, -0.09375],
+  [-0.28125, 0.21875, -0.40625, 0.09375],
+  [0.46875, -0.03125, 0.34375, -0.15625],
+];
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+  Linear,
+  Radial,
+  Conic,
+}
+
+/// How the gradient behaves outside the 0..1 range
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SpreadMode {
+  /// Extend the end colors
+  #[default]
+  Pad,
+  Repeat,
+  Reflect,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+  /// Position along the gradient (0..1)
+  pub offset: f64,
+  pub color: Color,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+  pub kind: GradientKind,
+  /// Start point for linear gradients, center for radial and conic ones
+  pub x0: f64,
+  pub y0: f64,
+  /// End point, required for linear gradients
+  pub x1: Option<f64>,
+  pub y1: Option<f64>,
+  /// Outer radius, required for radial gradients
+  pub radius: Option<f64>,
+  /// Radius at which a radial gradient starts (default 0)
+  pub inner_radius: Option<f64>,
+  /// Start angle in degrees for conic gradients (default 0, pointing right)
+  pub angle: Option<f64>,
+  pub stops: Vec<ColorStop>,
+  pub spread: Option<SpreadMode>,
+  /// Apply ordered dithering to reduce banding
+  pub dither: Option<bool>,
+  /// Color space the stops are interpolated in (default sRGB), with
+  /// premultiplied alpha
+  pub color_space: Option<ColorSpace>,
+}
+
+/// Something that can provide a color for each pixel it covers
+pub trait Paint: Sync {
+  fn color_at(&self, x: u32, y: u32) -> [u8; 4];
+
+  /// The single color of a uniform paint, enabling vectorized fills
+  fn solid(&self) -> Option<[u8; 4]> {
+    None
+  }
+}
+
+impl Paint for Color {
+  #[inline]
+  fn color_at(&self, _x: u32, _y: u32) -> [u8; 4] {
+    self.to_rgba()
+  }
+
+  fn solid(&self) -> Option<[u8; 4]> {
+    Some(self.to_rgba())
+  }
+}
+
+/// Adapts a straight-alpha paint for drawing into a premultiplied buffer
+pub struct Premultiplied<'a, P: Paint + ?Sized>(pub &'a P);
+
+impl<P: Paint + ?Sized> Paint for Premultiplied<'_, P> {
+  #[inline]
+  fn color_at(&self, x: u32, y: u32) -> [u8; 4] {
+    premultiply_pixel(self.0.color_at(x, y))
+  }
+
+  fn solid(&self) -> Option<[u8; 4]> {
+    self.0.solid().map(premultiply_pixel)
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Geometry {
+  Linear { dx: f64, dy: f64, inv_len_sq: f64 },
+  Radial { inner: f64, inv_span: f64 },
+  Conic { start: f64 },
+}
+
+/// A validated gradient with its colors precomputed, ready for sampling
+#[derive(Debug, Clone)]
+pub struct CompiledGradient {
+  x0: f64,
+  y0: f64,
+  geometry: Geometry,
+  spread: SpreadMode,
+  dither: bool,
+  lut: Vec<[f32; 4]>,
+}
+
+impl Gradient {
+  pub fn compile(&self) -> std::result::Result<CompiledGradient, String> {
+    let finite = |name: &str, v: f64| {
+      if v.is_finite() {
+        Ok(v)
+      } else {
+        Err(format!("Gradient {} must be a finite number", name))
+      }
+    };
+    let x0 = finite("x0", self.x0)?;
+    let y0 = finite("y0", self.y0)?;
+
+    let geometry = match self.kind {
+      GradientKind::Linear => {
+        let (Some(x1), Some(y1)) = (self.x1, self.y1) else {
+          return Err("Linear gradients require x1 and y1".to_string());
+        };
+        let dx = finite("x1", x1)? - x0;
+        let dy = finite("y1", y1)? - y0;
+        let len_sq = dx * dx + dy * dy;
+        if len_sq == 0.0 {
+          return Err("Linear gradient start and end points must differ".to_string());
+        }
+        Geometry::Linear {
+          dx,
+          dy,
+          inv_len_sq: 1.0 / len_sq,
+        }
+      }
+      GradientKind::Radial => {
+        let radius = finite(
+          "radius",
+          self
+            .radius
+            .ok_or_else(|| "Radial gradients require a radius".to_string())?,
+        )?;
+        let inner = finite("innerRadius", self.inner_radius.unwrap_or(0.0))?;
+        if inner < 0.0 || radius <= inner {
+          return Err("Radial gradient radius must be greater than innerRadius >= 0".to_string());
+        }
+        Geometry::Radial {
+          inner,
+          inv_span: 1.0 / (radius - inner),
+        }
+      }
+      GradientKind::Conic => Geometry::Conic {
+        start: finite("angle", self.angle.unwrap_or(0.0))?.to_radians(),
+      },
+    };
+
+    if self.stops.is_empty() {
+      return Err("Gradients require at least one color stop".to_string());
+    }
+    let mut stops = Vec::with_capacity(self.stops.len());
+    for (index, stop) in self.stops.iter().enumerate() {
+      if !stop.offset.is_finite() {
+        return Err(format!("Color stop {} has a non-finite offset", index));
+      }
+      stops.push(ColorStop {
+        offset: stop.offset.clamp(0.0, 1.0),
+        color: stop.color,
+      });
+    }
+    // Stable sort keeps the order of stops sharing an offset (hard color edges)
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+    Ok(CompiledGradient {
+      x0,
+      y0,
+      geometry,
+      spread: self.spread.unwrap_or_default(),
+      dither: self.dither.unwrap_or(false),
+      lut: build_lut(&stops, self.color_space.unwrap_or_default()),
+    })
+  }
+}
+
+/// Precompute the gradient colors as unrounded straight 0..255 channels,
+/// interpolated with premultiplied alpha
+fn build_lut(stops: &[ColorStop], space: ColorSpace) -> Vec<[f32; 4]> {
+  (0..LUT_SIZE)
+    .map(|i| {
+      let t = i as f64 / (LUT_SIZE - 1) as f64;
+      let next = stops.iter().position(|s| s.offset > t);
+      let (a, b, local) = match next {
+        None => (stops[stops.len() - 1], stops[stops.len() - 1], 0.0),
+        Some(0) => (stops[0], stops[0], 0.0),
+        Some(n) => {
+          let (a, b) = (stops[n - 1], stops[n]);
+          (a, b, (t - a.offset) / (b.offset - a.offset))
+        }
+      };
+      let [r, g, b, alpha] = a.color.mix_in(&b.color, local, space, true);
+      [
+        (r * 255.0) as f32,
+        (g * 255.0) as f32,
+        (b * 255.0) as f32,
+        (alpha * 255.0) as f32,
+      ]
+    })
+    .collect()
+}
+
+impl CompiledGradient {
+  /// Gradient parameter (before spreading) at a pixel center
+  fn parameter(&self, x: u32, y: u32) -> f64 {
+    let px = x as f64 + 0.5 - self.x0;
+    let py = y as f64 + 0.5 - self.y0;
+    match self.geometry {
+      Geometry::Linear { dx, dy, inv_len_sq } => (px * dx + py * dy) * inv_len_sq,
+      Geometry::Radial { inner, inv_span } => ((px * px + py * py).sqrt() - inner) * inv_span,
+      Geometry::Conic { start } => (py.atan2(px) - start).rem_euclid(TAU) / TAU,
+    }
+  }
+
+  fn spread(&self, t: f64) -> f64 {
+    match self.spread {
+      SpreadMode::Pad => t.clamp(0.0, 1.0),
+      SpreadMode::Repeat => t.rem_euclid(1.0),
+      SpreadMode::Reflect => {
+        let t = t.rem_euclid(2.0);
+        if t > 1.0 {
+          2.0 - t
+        } else {
+          t
+        }
+      }
+    }
+  }
+}
+
+impl Paint for CompiledGradient {
+  fn color_at(&self, x: u32, y: u32) -> [u8; 4] {
+    let t = self.spread(self.parameter(x, y));
+    let c = self.lut[(t * (LUT_SIZE - 1) as f64).round() as usize];
+    let offset = if self.dither {
+      BAYER_4X4[(y & 3) as usize][(x & 3) as usize]
+    } else {
+      0.0
+    };
+    let quantize = |v: f32| (v + offset).round().clamp(0.0, 255.0) as u8;
+    [
+      quantize(c[0]),
+      quantize(c[1]),
+      quantize(c[2]),
+      c[3].round() as u8,
+    ]
+  }
+}
+
+/// Fill a whole RGBA buffer with a paint
+pub fn fill_buffer_paint<P: Paint + ?Sized>(frame: &mut [u8], width: u32, height: u32, paint: &P) {
+  let stride = width as usize * 4;
+  let len = frame.len().min(stride * height as usize);
+  for_each_row(&mut frame[..len], stride, |y, row| {
+    for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+      pixel.copy_from_slice(&paint.color_at(x as u32, y as u32));
+    }
+  });
+}
+
+pub(crate) fn compile_gradient(gradient: &Gradient) -> Result<CompiledGradient> {
+  gradient
+    .compile()
+    .map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+// NAPI exports
+/// Sample the color of a gradient at a pixel
+#[napi(catch_unwind)]
+pub fn sample_gradient(gradient: Gradient, x: u32, y: u32) -> Result<Color> {
+  let [r, g, b, a] = compile_gradient(&gradient)?.color_at(x, y);
+  Ok(Color::new(r, g, b, a))
+}
+
+#[napi(catch_unwind)]
+pub fn fill_buffer_gradient(
+  buffer: Buffer,
+  width: u32,
+  height: u32,
+  gradient: Gradient,
+) -> Result<Buffer> {
+  let gradient = compile_gradient(&gradient)?;
+  let mut new_data = buffer.as_ref().to_vec();
+  if new_data.len() < crate::buffer::calculate_buffer_size(width, height) {
+    return Err(Error::new(
+      Status::InvalidArg,
+      "Buffer is smaller than width * height * 4",
+    ));
+  }
+  fill_buffer_paint(&mut new_data, width, height, &gradient);
+  Ok(Buffer::from(new_data))
+}
+
+#[napi(catch_unwind)]
+pub fn draw_line_gradient(
+  buffer: Buffer,
+  params: crate::types::LineParams,
+  gradient: Gradient,
+) -> Result<Buffer> {
+  let gradient = compile_gradient(&gradient)?;
+  let mut new_data = buffer.as_ref().to_vec();
+  crate::buffer::draw_line_optimized(&mut new_data, params, &gradient);
+  Ok(Buffer::from(new_data))
+}
+
+#[napi(catch_unwind)]
+pub fn draw_circle_gradient(
+  buffer: Buffer,
+  cx: u32,
+  cy: u32,
+  radius: u32,
+  buffer_width: u32,
+  buffer_height: u32,
+  gradient: Gradient,
+) -> Result<Buffer> {
+  let gradient = compile_gradient(&gradient)?;
+  let mut new_data = buffer.as_ref().to_vec();
+  crate::buffer::draw_circle_optimized(
+    &mut new_data,
+    cx,
+    cy,
+    radius,
+    buffer_width,
+    buffer_height,
+    &gradient,
+  );
+  Ok(Buffer::from(new_data))
+}
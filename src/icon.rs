@@ -0,0 +1,237 @@
+//! Window icons and taskbar progress
+//!
+//! Icons are converted natively into the square sizes platforms ask for; the
+//! platform calls themselves report whether they did anything, so callers can
+//! tell a no-op apart from success.
+
+use crate::platform::{Backend, Feature};
+use crate::types::DecodedImage;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use winit::window::{Icon, Window};
+
+/// Square sizes generated for an icon, smallest first
+pub const ICON_SIZES: [u32; 6] = [16, 24, 32, 48, 64, 256];
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ProgressState {
+  /// No progress is shown
+  None,
+  #[default]
+  Normal,
+  /// Progress of unknown length; the value is ignored
+  Indeterminate,
+  Paused,
+  Error,
+}
+
+/// One square size of an icon, as packed RGBA
+#[napi(object)]
+pub struct IconVariant {
+  pub data: Buffer,
+  pub width: u32,
+  pub height: u32,
+}
+
+/// The sizes generated for an image: every standard size below its larger
+/// side, and the first one at or above it
+pub fn icon_sizes(width: u32, height: u32) -> &'static [u32] {
+  let largest = width.max(height);
+  let count = ICON_SIZES
+    .iter()
+    .position(|&size| size >= largest)
+    .map_or(ICON_SIZES.len(), |index| index + 1);
+  &ICON_SIZES[..count]
+}
+
+/// Scale a packed RGBA image to fit a `size` x `size` square, centered on a
+/// transparent background. Every pixel averages the source area it covers,
+/// weighted by alpha so transparent pixels don't darken the edges.
+pub fn fit_square(rgba: &[u8], width: u32, height: u32, size: u32) -> Vec<u8> {
+  let mut out = vec![0; size as usize * size as usize * 4];
+  if width == 0 || height == 0 || rgba.len() < width as usize * height as usize * 4 {
+    return out;
+  }
+  let scale = size as f64 / width.max(height) as f64;
+  let fit_width = ((width as f64 * scale).round() as u32).clamp(1, size);
+  let fit_height = ((height as f64 * scale).round() as u32).clamp(1, size);
+  let (left, top) = ((size - fit_width) / 2, (size - fit_height) / 2);
+  // Source interval covered by destination pixel `d` of `count`
+  let span = |d: u32, count: u32, source: u32| {
+    let step = source as f64 / count as f64;
+    (d as f64 * step, (d + 1) as f64 * step)
+  };
+
+  for y in 0..fit_height {
+    let (y0, y1) = span(y, fit_height, height);
+    for x in 0..fit_width {
+      let (x0, x1) = span(x, fit_width, width);
+      let mut sum = [0.0f64; 4];
+      let mut area = 0.0;
+      for sy in y0 as u32..(y1.ceil() as u32).min(height) {
+        let weight_y = y1.min(sy as f64 + 1.0) - y0.max(sy as f64);
+        for sx in x0 as u32..(x1.ceil() as u32).min(width) {
+          let weight = weight_y * (x1.min(sx as f64 + 1.0) - x0.max(sx as f64));
+          let pixel = &rgba[(sy as usize * width as usize + sx as usize) * 4..][..4];
+          let alpha = pixel[3] as f64 * weight;
+          for channel in 0..3 {
+            sum[channel] += pixel[channel] as f64 * alpha;
+          }
+          sum[3] += alpha;
+          area += weight;
+        }
+      }
+      if sum[3] <= 0.0 {
+        continue;
+      }
+      let offset = (((top + y) * size + left + x) * 4) as usize;
+      for channel in 0..3 {
+        out[offset + channel] = (sum[channel] / sum[3]).round() as u8;
+      }
+      out[offset + 3] = (sum[3] / area).round() as u8;
+    }
+  }
+  out
+}
+
+/// Convert an image into the square variants of [`icon_sizes`]
+pub fn icon_variants(image: &DecodedImage) -> std::result::Result<Vec<(u32, Vec<u8>)>, String> {
+  if image.width == 0 || image.height == 0 {
+    return Err("Icon must be at least 1x1".to_string());
+  }
+  let rgba = crate::format::to_rgba(
+    &image.data,
+    image.width,
+    image.height,
+    image.format.unwrap_or_default(),
+    image.stride,
+  )?;
+  Ok(
+    icon_sizes(image.width, image.height)
+      .iter()
+      .map(|&size| (size, fit_square(&rgba, image.width, image.height, size)))
+      .collect(),
+  )
+}
+
+/// The smallest variant of at least `size`, or the largest there is
+fn pick(variants: &[(u32, Vec<u8>)], size: u32) -> Option<Icon> {
+  let (size, rgba) = variants
+    .iter()
+    .find(|(variant, _)| *variant >= size)
+    .or(variants.last())?;
+  Icon::from_rgba(rgba.clone(), *size, *size).ok()
+}
+
+/// Set (or with `None`, reset) the window and taskbar icons
+pub fn apply_icon(window: &Window, variants: Option<&[(u32, Vec<u8>)]>) {
+  let scale = window.scale_factor();
+  let at = |size: f64| (size * scale).round() as u32;
+  #[cfg(target_os = "windows")]
+  {
+    use winit::platform::windows::WindowExtWindows;
+
+    window.set_window_icon(variants.and_then(|variants| pick(variants, at(16.0))));
+    window.set_taskbar_icon(variants.and_then(|variants| pick(variants, at(32.0))));
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    // X11 takes a single icon and lets the window manager scale it
+    let _ = at;
+    window.set_window_icon(variants.and_then(|variants| pick(variants, u32::MAX)));
+  }
+}
+
+/// Check a progress value, which must be within 0..1
+pub fn validate_progress(value: f64) -> Result<f64> {
+  if !value.is_finite() || !(0.0..=1.0).contains(&value) {
+    return Err(Error::new(
+      Status::InvalidArg,
+      format!("Progress must be between 0 and 1, got {}", value),
+    ));
+  }
+  Ok(value)
+}
+
+#[cfg(target_os = "windows")]
+pub fn apply_progress(window: &Window, value: f64, state: ProgressState) -> bool {
+  use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+  use windows::Win32::Foundation::HWND;
+  use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+  };
+  use windows::Win32::UI::Shell::{
+    ITaskbarList3, TaskbarList, TBPF_ERROR, TBPF_INDETERMINATE, TBPF_NOPROGRESS, TBPF_NORMAL,
+    TBPF_PAUSED,
+  };
+
+  const STEPS: u64 = 10_000;
+  let Ok(handle) = window.window_handle() else {
+    return false;
+  };
+  let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+    return false;
+  };
+  let hwnd = HWND(handle.hwnd.get());
+  let flag = match state {
+    ProgressState::None => TBPF_NOPROGRESS,
+    ProgressState::Normal => TBPF_NORMAL,
+    ProgressState::Indeterminate => TBPF_INDETERMINATE,
+    ProgressState::Paused => TBPF_PAUSED,
+    ProgressState::Error => TBPF_ERROR,
+  };
+  unsafe {
+    // The event loop thread is usually initialized already, which is fine
+    let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+    let Ok(taskbar) =
+      CoCreateInstance::<_, ITaskbarList3>(&TaskbarList, None, CLSCTX_INPROC_SERVER)
+    else {
+      return false;
+    };
+    if taskbar.HrInit().is_err() {
+      return false;
+    }
+    let has_value = matches!(
+      state,
+      ProgressState::Normal | ProgressState::Paused | ProgressState::Error
+    );
+    if has_value
+      && taskbar
+        .SetProgressValue(hwnd, (value * STEPS as f64).round() as u64, STEPS)
+        .is_err()
+    {
+      return false;
+    }
+    taskbar.SetProgressState(hwnd, flag).is_ok()
+  }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn apply_progress(_window: &Window, _value: f64, _state: ProgressState) -> bool {
+  // On non-Windows platforms, this is a no-op
+  false
+}
+
+// NAPI exports
+/// The square icon sizes generated from an image, as `setIcon` uses them
+#[napi(catch_unwind)]
+pub fn create_icon_variants(image: DecodedImage) -> Result<Vec<IconVariant>> {
+  let variants = icon_variants(&image).map_err(|e| Error::new(Status::InvalidArg, e))?;
+  Ok(
+    variants
+      .into_iter()
+      .map(|(size, data)| IconVariant {
+        data: data.into(),
+        width: size,
+        height: size,
+      })
+      .collect(),
+  )
+}
+
+/// Whether `setProgress` shows anything on this platform
+#[napi(catch_unwind)]
+pub fn is_taskbar_progress_supported() -> bool {
+  Feature::TaskbarProgress.supported(Backend::native())
+}
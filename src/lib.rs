@@ -7,23 +7,90 @@ use std::sync::{Arc, Mutex};
 use winit::event_loop::EventLoop;
 
 // Module declarations
+mod animation;
 mod buffer;
+mod canvas;
+mod clip;
 mod color;
+mod command;
+mod compositor;
+mod device;
+mod element;
+mod error;
+mod filter;
+mod format;
+mod gpu;
+mod gradient;
+mod icon;
+mod parallel;
+mod platform;
+mod recovery;
+mod scale;
+mod shape;
+mod simd;
+mod sprite;
+mod surface;
 mod types;
 mod window;
+mod yuv;
 
 // Re-export main types for NAPI compatibility
+pub use animation::*;
 pub use buffer::*;
+pub use canvas::*;
+pub use clip::*;
 pub use color::*;
+pub use command::*;
+pub use compositor::*;
+pub use device::*;
+pub use element::*;
+pub use error::*;
+pub use filter::*;
+pub use format::*;
+pub use gpu::*;
+pub use gradient::*;
+pub use icon::*;
+pub use parallel::*;
+pub use platform::*;
+pub use recovery::*;
+pub use scale::*;
+pub use shape::*;
+pub use sprite::*;
 pub use types::*;
+pub use yuv::*;
 
-use window::{poll_event_loop, run_event_loop, FrameController, WindowController, WindowState};
+use window::{
+  poll_event_loop, recover, run_event_loop, FrameController, WindowController, WindowState,
+};
 
 /// Application manager for the overlay system
 #[napi]
 pub struct OverlayApp {
   event_loop: Option<winit::event_loop::EventLoop<()>>,
   windows: Vec<Arc<Mutex<WindowState>>>,
+  backend: Backend,
+  strict: bool,
+}
+
+impl OverlayApp {
+  /// Create the app, failing when there is no display or an event loop was
+  /// already created in this process
+  pub fn try_new() -> OverlayResult<Self> {
+    let event_loop = EventLoop::new().map_err(|e| {
+      OverlayError::EventLoopUnavailable(format!("Failed to create event loop: {}", e))
+    })?;
+    Ok(Self {
+      backend: Backend::of(&event_loop),
+      event_loop: Some(event_loop),
+      windows: Vec::new(),
+      strict: false,
+    })
+  }
+
+  /// Panics where `try_new` fails
+  pub fn new() -> Self {
+    Self::try_new().expect("Failed to create event loop")
+  }
 }
 
 impl Default for OverlayApp {
@@ -34,29 +101,117 @@ impl Default for OverlayApp {
 
 #[napi]
 impl OverlayApp {
-  #[napi(constructor)]
-  pub fn new() -> Self {
-    let event_loop = EventLoop::new().expect("Failed to create event loop");
-    Self {
-      event_loop: Some(event_loop),
-      windows: Vec::new(),
+  /// Fails with the `EventLoopUnavailable` error code when there is no
+  /// display, or an event loop was already created in this process
+  #[napi(constructor, catch_unwind)]
+  pub fn create(env: Env) -> Result<Self> {
+    Self::try_new().into_js(&env)
+  }
+
+  /// Which platform features work on the current platform and windowing
+  /// backend
+  #[napi(catch_unwind)]
+  pub fn get_capabilities(&self) -> Capabilities {
+    capabilities(self.backend)
+  }
+
+  /// In strict mode, calls to features the platform doesn't support fail with
+  /// an `InvalidArg` error instead of doing nothing. Applies to existing and
+  /// new windows.
+  #[napi(catch_unwind)]
+  pub fn set_strict_mode(&mut self, strict: bool) {
+    self.strict = strict;
+    for state in &self.windows {
+      recover(state).strict = strict;
     }
   }
 
+  #[napi(catch_unwind)]
+  pub fn is_strict_mode(&self) -> bool {
+    self.strict
+  }
+
   /// Create a new window with the given configuration
-  #[napi]
-  pub fn create_window(&mut self, config: WindowConfig) -> Result<OverlayWindow> {
+  #[napi(catch_unwind)]
+  pub fn create_window(&mut self, env: Env, config: WindowConfig) -> Result<OverlayWindow> {
+    self.open_window(config).into_js(&env)
+  }
+
+  /// Poll events once and return (non-blocking)
+  #[napi(catch_unwind)]
+  pub fn poll_events(&mut self, env: Env) -> Result<bool> {
+    let event_loop = self
+      .event_loop
+      .as_mut()
+      .ok_or_else(OverlayError::event_loop_taken)
+      .into_js(&env)?;
+
+    Ok(poll_event_loop(event_loop, &self.windows))
+  }
+
+  /// Start the event loop (blocks the current thread)
+  #[napi(catch_unwind)]
+  pub fn run(&mut self, env: Env) -> Result<()> {
+    if self.windows.is_empty() {
+      return Err(OverlayError::NoWindows.into_js(&env));
+    }
+    let event_loop = self
+      .event_loop
+      .take()
+      .ok_or(OverlayError::EventLoopRunning)
+      .into_js(&env)?;
+
+    run_event_loop(event_loop, self.windows.clone());
+
+    #[allow(unreachable_code)]
+    Ok(())
+  }
+}
+
+impl OverlayApp {
+  fn open_window(&mut self, config: WindowConfig) -> OverlayResult<OverlayWindow> {
     let event_loop = self
       .event_loop
       .as_mut()
-      .ok_or_else(|| Error::new(Status::GenericFailure, "Event loop not available"))?;
+      .ok_or_else(OverlayError::event_loop_taken)?;
 
+    validate_window_config(config.clone())?;
+    let corner_radius = config.corner_radius.unwrap_or(0.0);
+    if config.transparent.unwrap_or(true) {
+      Feature::Transparency.check(self.backend, self.strict)?;
+    }
+    if corner_radius > 0.0 {
+      Feature::PerPixelHitTest.check(self.backend, self.strict)?;
+    }
+    if config.content_protected.unwrap_or(false) {
+      Feature::CaptureExclusion.check(self.backend, self.strict)?;
+    }
     let (window, pixels) = window::create_overlay_window_from_loop(event_loop, &config)?;
     let window_size = window.inner_size();
     let width = window_size.width;
     let height = window_size.height;
-
-    let state = Arc::new(Mutex::new(WindowState {
+    let render_recovery = RenderRecovery::watching(&pixels);
+
+    let state = WindowState {
+      frame: vec![0; calculate_buffer_size(width, height)],
+      opacity: 1.0,
+      animator: Default::default(),
+      elements: Vec::new(),
+      next_element_id: 0,
+      alpha_mode: config.alpha_mode.unwrap_or_default(),
+      clip: Default::default(),
+      compositor: config.compositor.unwrap_or_default(),
+      gpu: None,
+      gpu_failed: false,
+      scale_mode: config.scale_mode.unwrap_or_default(),
+      surface_width: width,
+      surface_height: height,
+      resize_policy: config.resize_policy.unwrap_or_default(),
+      resize_callback: None,
+      repaint_deadline: None,
+      shape: None,
+      corner_radius,
+      handle_event_loop_modal: config.handle_event_loop_modal.unwrap_or(true),
       pixels: Some(pixels),
       window: Some(window),
       width,
@@ -64,10 +219,19 @@ impl OverlayApp {
       event_callback: None,
       render_when_occluded: config.render_when_occluded.unwrap_or(true),
       occluded: false,
-      exclude_from_capture: false,
+      exclude_from_capture: config.content_protected.unwrap_or(false),
       pending_resize: false,
-    }));
-
+      backend: self.backend,
+      strict: self.strict,
+      destroyed: false,
+      transparent: config.transparent.unwrap_or(true),
+      render_recovery,
+    };
+
+    if corner_radius > 0.0 {
+      state.apply_shape();
+    }
+    let state = Arc::new(Mutex::new(state));
     self.windows.push(state.clone());
 
     let window_controller = WindowController::new(state.clone());
@@ -79,35 +243,6 @@ impl OverlayApp {
       frame_controller,
     })
   }
-
-  /// Poll events once and return (non-blocking)
-  #[napi]
-  pub fn poll_events(&mut self) -> Result<bool> {
-    let event_loop = self
-      .event_loop
-      .as_mut()
-      .ok_or_else(|| Error::new(Status::GenericFailure, "Event loop not available"))?;
-
-    Ok(poll_event_loop(event_loop, &self.windows))
-  }
-
-  /// Start the event loop (blocks the current thread)
-  #[napi]
-  pub fn run(&mut self) -> Result<()> {
-    let event_loop = self
-      .event_loop
-      .take()
-      .ok_or_else(|| Error::new(Status::GenericFailure, "Event loop already running"))?;
-
-    if self.windows.is_empty() {
-      return Err(Error::new(Status::GenericFailure, "No windows created"));
-    }
-
-    run_event_loop(event_loop, self.windows.clone());
-
-    #[allow(unreachable_code)]
-    Ok(())
-  }
 }
 
 /// A wrapper for an overlay window
@@ -122,153 +257,343 @@ pub struct OverlayWindow {
 #[napi]
 impl OverlayWindow {
   /// Register an event callback
-  #[napi]
-  pub fn on_event(&self, callback: ThreadsafeFunction<OverlayEvent>) -> Result<()> {
-    self.window_controller.set_event_callback(callback);
-    Ok(())
+  #[napi(catch_unwind)]
+  pub fn on_event(&self, env: Env, callback: ThreadsafeFunction<OverlayEvent>) -> Result<()> {
+    self
+      .window_controller
+      .set_event_callback(callback)
+      .into_js(&env)
   }
 
-  /// Update frame with optimized buffer handling
-  #[napi]
-  pub fn update_frame(&self, buffer: Buffer) -> Result<()> {
-    self.frame_controller.update_frame(buffer.as_ref())
+  /// Register a callback called with the new frame size after the window is
+  /// resized. The next present waits until it returns (up to 100 ms), so a
+  /// synchronous repaint shows without a stale or blank frame.
+  #[napi(catch_unwind)]
+  pub fn on_resize(&self, env: Env, callback: ThreadsafeFunction<WindowSize>) -> Result<()> {
+    self
+      .window_controller
+      .set_resize_callback(callback)
+      .into_js(&env)
+  }
+
+  /// Update frame with optimized buffer handling. Pixels in other formats or
+  /// with padded rows are converted into the frame natively.
+  #[napi(catch_unwind)]
+  pub fn update_frame(
+    &self,
+    env: Env,
+    buffer: Buffer,
+    format: Option<PixelFormat>,
+    stride: Option<u32>,
+  ) -> Result<()> {
+    self
+      .frame_controller
+      .update_frame(buffer.as_ref(), format.unwrap_or_default(), stride)
+      .into_js(&env)
   }
 
   /// Get frame size
-  #[napi]
-  pub fn get_frame_size(&self) -> Result<Vec<u32>> {
-    self.frame_controller.get_frame_size()
+  #[napi(catch_unwind)]
+  pub fn get_frame_size(&self, env: Env) -> Result<Vec<u32>> {
+    self.frame_controller.get_frame_size().into_js(&env)
   }
 
   /// Window visibility controls
-  #[napi]
-  pub fn show(&self) -> Result<()> {
-    self.window_controller.show()
+  #[napi(catch_unwind)]
+  pub fn show(&self, env: Env) -> Result<()> {
+    self.window_controller.show().into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn hide(&self, env: Env) -> Result<()> {
+    self.window_controller.hide().into_js(&env)
   }
 
-  #[napi]
-  pub fn hide(&self) -> Result<()> {
-    self.window_controller.hide()
+  #[napi(catch_unwind)]
+  pub fn minimize(&self, env: Env) -> Result<()> {
+    self.window_controller.minimize().into_js(&env)
   }
 
-  #[napi]
-  pub fn minimize(&self) -> Result<()> {
-    self.window_controller.minimize()
+  #[napi(catch_unwind)]
+  pub fn maximize(&self, env: Env) -> Result<()> {
+    self.window_controller.maximize().into_js(&env)
   }
 
-  #[napi]
-  pub fn maximize(&self) -> Result<()> {
-    self.window_controller.maximize()
+  #[napi(catch_unwind)]
+  pub fn restore(&self, env: Env) -> Result<()> {
+    self.window_controller.restore().into_js(&env)
   }
 
-  #[napi]
-  pub fn restore(&self) -> Result<()> {
-    self.window_controller.restore()
+  #[napi(catch_unwind)]
+  pub fn is_visible(&self, env: Env) -> Result<bool> {
+    self.window_controller.is_visible().into_js(&env)
   }
 
-  #[napi]
-  pub fn is_visible(&self) -> Result<bool> {
-    self.window_controller.is_visible()
+  /// Close the window. Later calls that need it fail with the
+  /// `WindowDestroyed` error code.
+  #[napi(catch_unwind)]
+  pub fn close(&self) {
+    self.window_controller.close()
   }
 
   /// Window positioning
-  #[napi]
-  pub fn set_position(&self, x: i32, y: i32) -> Result<()> {
-    self.window_controller.set_position(x, y)
+  #[napi(catch_unwind)]
+  pub fn set_position(&self, env: Env, x: i32, y: i32) -> Result<()> {
+    self.window_controller.set_position(x, y).into_js(&env)
   }
 
-  #[napi]
-  pub fn get_position(&self) -> Result<WindowPosition> {
-    self.window_controller.get_position()
+  #[napi(catch_unwind)]
+  pub fn get_position(&self, env: Env) -> Result<WindowPosition> {
+    self.window_controller.get_position().into_js(&env)
   }
 
   /// Window sizing
-  #[napi]
-  pub fn set_size(&self, width: u32, height: u32) -> Result<()> {
-    self.window_controller.set_size(width, height)
+  #[napi(catch_unwind)]
+  pub fn set_size(&self, env: Env, width: u32, height: u32) -> Result<()> {
+    self.window_controller.set_size(width, height).into_js(&env)
   }
 
-  #[napi]
-  pub fn get_size(&self) -> Result<WindowSize> {
-    self.window_controller.get_size()
+  #[napi(catch_unwind)]
+  pub fn get_size(&self, env: Env) -> Result<WindowSize> {
+    self.window_controller.get_size().into_js(&env)
   }
 
-  #[napi]
-  pub fn set_fullscreen(&self, fullscreen: bool) -> Result<()> {
-    self.window_controller.set_fullscreen(fullscreen)
+  #[napi(catch_unwind)]
+  pub fn set_fullscreen(&self, env: Env, fullscreen: bool) -> Result<()> {
+    self
+      .window_controller
+      .set_fullscreen(fullscreen)
+      .into_js(&env)
   }
 
-  #[napi]
-  pub fn is_fullscreen(&self) -> Result<bool> {
-    self.window_controller.is_fullscreen()
+  #[napi(catch_unwind)]
+  pub fn is_fullscreen(&self, env: Env) -> Result<bool> {
+    self.window_controller.is_fullscreen().into_js(&env)
   }
 
   /// Window properties
-  #[napi]
-  pub fn set_title(&self, title: String) -> Result<()> {
-    self.window_controller.set_title(&title)
+  #[napi(catch_unwind)]
+  pub fn set_title(&self, env: Env, title: String) -> Result<()> {
+    self.window_controller.set_title(&title).into_js(&env)
   }
 
-  #[napi]
-  pub fn set_window_level(&self, level: WindowLevel) -> Result<()> {
-    self.window_controller.set_window_level(level)
+  #[napi(catch_unwind)]
+  pub fn set_window_level(&self, env: Env, level: WindowLevel) -> Result<()> {
+    self.window_controller.set_window_level(level).into_js(&env)
   }
 
-  #[napi]
-  pub fn request_redraw(&self) -> Result<()> {
-    self.window_controller.request_redraw()
+  #[napi(catch_unwind)]
+  pub fn request_redraw(&self, env: Env) -> Result<()> {
+    self.window_controller.request_redraw().into_js(&env)
   }
 
-  #[napi]
-  pub fn set_cursor_visible(&self, visible: bool) -> Result<()> {
-    self.window_controller.set_cursor_visible(visible)
+  #[napi(catch_unwind)]
+  pub fn set_cursor_visible(&self, env: Env, visible: bool) -> Result<()> {
+    self
+      .window_controller
+      .set_cursor_visible(visible)
+      .into_js(&env)
   }
 
-  #[napi]
-  pub fn set_ignore_mouse_events(&self, ignore: bool) -> Result<()> {
-    self.window_controller.set_ignore_mouse_events(ignore)
+  #[napi(catch_unwind)]
+  pub fn set_ignore_mouse_events(&self, env: Env, ignore: bool) -> Result<()> {
+    self
+      .window_controller
+      .set_ignore_mouse_events(ignore)
+      .into_js(&env)
   }
 
-  #[napi]
-  pub fn set_render_when_occluded(&self, render: bool) -> Result<()> {
-    self.window_controller.set_render_when_occluded(render);
-    Ok(())
+  #[napi(catch_unwind)]
+  pub fn set_render_when_occluded(&self, env: Env, render: bool) -> Result<()> {
+    self
+      .window_controller
+      .set_render_when_occluded(render)
+      .into_js(&env)
   }
 
-  #[napi]
-  pub fn is_occluded(&self) -> Result<bool> {
-    Ok(self.window_controller.is_occluded())
+  #[napi(catch_unwind)]
+  pub fn is_occluded(&self, env: Env) -> Result<bool> {
+    self.window_controller.is_occluded().into_js(&env)
+  }
+
+  /// Limit how failed presents are retried after a lost surface or GPU
+  /// device, and resume presenting if retries had run out
+  #[napi(catch_unwind)]
+  pub fn set_render_recovery(&self, env: Env, options: RenderRecoveryOptions) -> Result<()> {
+    self
+      .window_controller
+      .set_render_recovery(&options)
+      .into_js(&env)
+  }
+
+  /// Failed presents, recoveries and lost devices so far
+  #[napi(catch_unwind)]
+  pub fn get_render_status(&self, env: Env) -> Result<RenderStatus> {
+    self.window_controller.get_render_status().into_js(&env)
   }
 
   /// Set whether the window should be excluded from screen capture (OBS, etc)
   /// When set to false (default), capture tools can capture this window
   /// When set to true, the window will appear black/invisible in captures
-  #[napi]
-  pub fn set_exclude_from_capture(&self, exclude: bool) -> Result<()> {
-    self.window_controller.set_exclude_from_capture(exclude)
+  #[napi(catch_unwind)]
+  pub fn set_exclude_from_capture(&self, env: Env, exclude: bool) -> Result<()> {
+    self
+      .window_controller
+      .set_exclude_from_capture(exclude)
+      .into_js(&env)
   }
 
   /// Check if the window is excluded from screen capture
-  #[napi]
-  pub fn is_excluded_from_capture(&self) -> Result<bool> {
-    Ok(self.window_controller.is_excluded_from_capture())
+  #[napi(catch_unwind)]
+  pub fn is_excluded_from_capture(&self, env: Env) -> Result<bool> {
+    self
+      .window_controller
+      .is_excluded_from_capture()
+      .into_js(&env)
   }
 
   /// Set whether the window should be shown in the taskbar
-  #[napi]
-  pub fn set_skip_taskbar(&self, skip: bool) -> Result<()> {
-    self.window_controller.set_skip_taskbar(skip)
+  #[napi(catch_unwind)]
+  pub fn set_skip_taskbar(&self, env: Env, skip: bool) -> Result<()> {
+    self.window_controller.set_skip_taskbar(skip).into_js(&env)
+  }
+
+  /// Limit drawing and input to a shape in window pixels, or restore the full
+  /// window with null. Returns false where the platform can't shape windows
+  /// (only Windows can at the moment).
+  #[napi(catch_unwind)]
+  pub fn set_shape(&self, env: Env, shape: Option<WindowShape>) -> Result<bool> {
+    self.window_controller.set_shape(shape).into_js(&env)
+  }
+
+  /// Round the window corners; used while no explicit shape is set. Returns
+  /// false where the platform can't shape windows.
+  #[napi(catch_unwind)]
+  pub fn set_corner_radius(&self, env: Env, radius: f64) -> Result<bool> {
+    self
+      .window_controller
+      .set_corner_radius(radius)
+      .into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_corner_radius(&self, env: Env) -> Result<f64> {
+    self.window_controller.get_corner_radius().into_js(&env)
+  }
+
+  /// Set the window and taskbar icons; the image is scaled to the square sizes
+  /// the platform uses. Pass null to restore the default icon. Returns false
+  /// where the platform doesn't show window icons.
+  #[napi(catch_unwind)]
+  pub fn set_icon(&self, env: Env, image: Option<DecodedImage>) -> Result<bool> {
+    self.window_controller.set_icon(image).into_js(&env)
+  }
+
+  /// Show progress (0..1) on the taskbar button. Returns false where the
+  /// platform has no taskbar progress (only Windows does at the moment).
+  #[napi(catch_unwind)]
+  pub fn set_progress(&self, env: Env, value: f64, state: Option<ProgressState>) -> Result<bool> {
+    self
+      .window_controller
+      .set_progress(value, state.unwrap_or_default())
+      .into_js(&env)
+  }
+
+  /// Set the whole-window opacity (0..1), applied when the frame is composited
+  #[napi(catch_unwind)]
+  pub fn set_opacity(&self, env: Env, opacity: f64) -> Result<()> {
+    self.window_controller.set_opacity(opacity).into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_opacity(&self, env: Env) -> Result<f64> {
+    self.window_controller.get_opacity().into_js(&env)
+  }
+
+  /// Fade the window in to full opacity; emits `FadeInCompleted` when done
+  #[napi(catch_unwind)]
+  pub fn fade_in(&self, env: Env, duration_ms: u32, easing: Option<Easing>) -> Result<()> {
+    self
+      .window_controller
+      .fade(FadeDirection::In, duration_ms, easing.unwrap_or_default())
+      .into_js(&env)
+  }
+
+  /// Fade the window out to zero opacity; emits `FadeOutCompleted` when done
+  #[napi(catch_unwind)]
+  pub fn fade_out(&self, env: Env, duration_ms: u32, easing: Option<Easing>) -> Result<()> {
+    self
+      .window_controller
+      .fade(FadeDirection::Out, duration_ms, easing.unwrap_or_default())
+      .into_js(&env)
+  }
+
+  /// Animate a window or element property natively; returns the animation id.
+  /// The callback receives the id once the animation ends, followed by `true`
+  /// when it was cancelled instead of completing.
+  #[napi(catch_unwind)]
+  pub fn animate(
+    &self,
+    env: Env,
+    options: TweenOptions,
+    callback: Option<ThreadsafeFunction<FnArgs<(u32, bool)>>>,
+  ) -> Result<u32> {
+    self
+      .window_controller
+      .animate(options, callback)
+      .into_js(&env)
+  }
+
+  /// Cancel an animation along with any animations chained after it
+  #[napi(catch_unwind)]
+  pub fn cancel_animation(&self, env: Env, id: u32) -> Result<bool> {
+    self.window_controller.cancel_animation(id).into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn cancel_all_animations(&self, env: Env) -> Result<()> {
+    self.window_controller.cancel_all_animations().into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn is_animating(&self, env: Env) -> Result<bool> {
+    self.window_controller.is_animating().into_js(&env)
+  }
+
+  /// Retained elements, drawn over the frame on every composite
+  #[napi(catch_unwind)]
+  pub fn add_element(&self, env: Env, options: ElementOptions) -> Result<u32> {
+    self.frame_controller.add_element(&options).into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn update_element(&self, env: Env, id: u32, options: ElementOptions) -> Result<()> {
+    self
+      .frame_controller
+      .update_element(id, &options)
+      .into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn remove_element(&self, env: Env, id: u32) -> Result<bool> {
+    self.frame_controller.remove_element(id).into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn clear_elements(&self, env: Env) -> Result<()> {
+    self.frame_controller.clear_elements().into_js(&env)
   }
 
   /// Frame operations
-  #[napi]
-  pub fn clear_frame(&self, color: Color) -> Result<()> {
-    self.frame_controller.clear_frame(&color)
+  #[napi(catch_unwind)]
+  pub fn clear_frame(&self, env: Env, color: Color) -> Result<()> {
+    self.frame_controller.clear_frame(&color).into_js(&env)
   }
 
-  #[napi]
+  #[napi(catch_unwind)]
   pub fn draw_rectangle(
     &self,
+    env: Env,
     x: u32,
     y: u32,
     width: u32,
@@ -278,28 +603,232 @@ impl OverlayWindow {
     self
       .frame_controller
       .draw_rectangle(x, y, width, height, &color)
+      .into_js(&env)
+  }
+
+  /// Clear the whole frame with a gradient
+  #[napi(catch_unwind)]
+  pub fn clear_frame_gradient(&self, env: Env, gradient: Gradient) -> Result<()> {
+    self
+      .frame_controller
+      .clear_frame_gradient(&gradient)
+      .into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn draw_rectangle_gradient(
+    &self,
+    env: Env,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    gradient: Gradient,
+  ) -> Result<()> {
+    self
+      .frame_controller
+      .draw_rectangle_gradient(x, y, width, height, &gradient)
+      .into_js(&env)
+  }
+
+  /// Copy an image into the frame, running `filters` on it first
+  #[napi(catch_unwind)]
+  pub fn draw_image(
+    &self,
+    env: Env,
+    x: u32,
+    y: u32,
+    image: DecodedImage,
+    filters: Option<Vec<Filter>>,
+  ) -> Result<()> {
+    let effects = filter::resolve_filters(filters.as_deref().unwrap_or_default())?;
+    self
+      .frame_controller
+      .draw_image(x, y, &image, &effects)
+      .into_js(&env)
+  }
+
+  /// Draw an image registered with `registerImage`
+  #[napi(catch_unwind)]
+  pub fn draw_sprite(
+    &self,
+    env: Env,
+    id: String,
+    x: i32,
+    y: i32,
+    options: Option<SpriteDrawOptions>,
+  ) -> Result<()> {
+    self
+      .frame_controller
+      .draw_sprite(&id, x, y, &options.unwrap_or_default())
+      .into_js(&env)
+  }
+
+  /// Filter a region of the frame in place (the whole frame by default)
+  #[napi(catch_unwind)]
+  pub fn apply_filters(&self, env: Env, filters: Vec<Filter>, region: Option<Rect>) -> Result<()> {
+    let effects = filter::resolve_filters(&filters)?;
+    self
+      .frame_controller
+      .apply_filters(region, &effects)
+      .into_js(&env)
+  }
+
+  /// Limit all following frame drawing to a rectangle, intersected with the
+  /// current clip
+  #[napi(catch_unwind)]
+  pub fn push_clip_rect(&self, env: Env, rect: Rect) -> Result<()> {
+    self
+      .frame_controller
+      .push_clip(ClipShape::Rect(rect))
+      .into_js(&env)
+  }
+
+  /// Limit all following frame drawing to an anti-aliased rounded rectangle
+  #[napi(catch_unwind)]
+  pub fn push_clip_rounded_rect(&self, env: Env, rect: Rect, radius: f64) -> Result<()> {
+    self
+      .frame_controller
+      .push_clip(ClipShape::RoundedRect(rect, radius))
+      .into_js(&env)
+  }
+
+  /// Limit all following frame drawing by an 8-bit coverage mask
+  #[napi(catch_unwind)]
+  pub fn push_clip_mask(&self, env: Env, mask: ClipMask) -> Result<()> {
+    self
+      .frame_controller
+      .push_clip(ClipShape::Mask(&mask))
+      .into_js(&env)
+  }
+
+  /// Remove the most recently pushed clip; returns false when none was set
+  #[napi(catch_unwind)]
+  pub fn pop_clip(&self, env: Env) -> Result<bool> {
+    self.frame_controller.pop_clip().into_js(&env)
+  }
+
+  /// Remove all clips
+  #[napi(catch_unwind)]
+  pub fn reset_clip(&self, env: Env) -> Result<()> {
+    self.frame_controller.reset_clip().into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_clip_depth(&self, env: Env) -> Result<u32> {
+    self.frame_controller.get_clip_depth().into_js(&env)
+  }
+
+  /// Replace the frame with an I420 or NV12 video frame, scaled to fit
+  #[napi(catch_unwind)]
+  pub fn update_frame_yuv(&self, env: Env, frame: YuvFrame) -> Result<()> {
+    self.frame_controller.update_frame_yuv(&frame).into_js(&env)
+  }
+
+  /// Draw an I420 or NV12 video frame into a region of the frame
+  #[napi(catch_unwind)]
+  pub fn draw_yuv_image(
+    &self,
+    env: Env,
+    frame: YuvFrame,
+    options: Option<YuvDrawOptions>,
+  ) -> Result<()> {
+    self
+      .frame_controller
+      .draw_yuv(&frame, options)
+      .into_js(&env)
+  }
+
+  /// Set the alpha convention of the frame; drawing and compositing follow it
+  #[napi(catch_unwind)]
+  pub fn set_alpha_mode(&self, env: Env, mode: AlphaMode) -> Result<()> {
+    self.frame_controller.set_alpha_mode(mode).into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_alpha_mode(&self, env: Env) -> Result<AlphaMode> {
+    self.frame_controller.get_alpha_mode().into_js(&env)
+  }
+
+  /// Choose where elements and opacity are composited. `Auto` uses the GPU
+  /// on hardware adapters; the CPU is used whenever the GPU path fails.
+  #[napi(catch_unwind)]
+  pub fn set_compositor(&self, env: Env, backend: CompositorBackend) -> Result<()> {
+    self.frame_controller.set_compositor(backend).into_js(&env)
+  }
+
+  /// The backend in use: `Gpu` or `Cpu`
+  #[napi(catch_unwind)]
+  pub fn get_compositor(&self, env: Env) -> Result<CompositorBackend> {
+    self.frame_controller.get_compositor().into_js(&env)
+  }
+
+  /// Choose how the frame maps onto the window when their sizes differ.
+  /// Switching to `MatchWindow` resizes the frame to the window, keeping its
+  /// content.
+  #[napi(catch_unwind)]
+  pub fn set_scale_mode(&self, env: Env, mode: ScaleMode) -> Result<()> {
+    self.frame_controller.set_scale_mode(mode).into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_scale_mode(&self, env: Env) -> Result<ScaleMode> {
+    self.frame_controller.get_scale_mode().into_js(&env)
+  }
+
+  /// Choose what happens to the frame contents when the frame follows a
+  /// window resize
+  #[napi(catch_unwind)]
+  pub fn set_resize_policy(&self, env: Env, policy: ResizePolicy) -> Result<()> {
+    self
+      .frame_controller
+      .set_resize_policy(policy)
+      .into_js(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get_resize_policy(&self, env: Env) -> Result<ResizePolicy> {
+    self.frame_controller.get_resize_policy().into_js(&env)
+  }
+
+  /// Draw a list of commands under a single lock with one redraw. The whole
+  /// list is validated first; errors name the offending command index.
+  #[napi(catch_unwind)]
+  pub fn draw_commands(&self, env: Env, commands: Vec<DrawCommand>) -> Result<()> {
+    let ops = command::resolve_commands(&commands)?;
+    self.frame_controller.draw_commands(ops).into_js(&env)
+  }
+
+  /// Like `drawCommands`, with commands in the binary encoding produced by
+  /// `encodeDrawCommands`
+  #[napi(catch_unwind)]
+  pub fn draw_commands_encoded(&self, env: Env, commands: Buffer) -> Result<()> {
+    let ops = command::decode_commands(&commands)?;
+    self.frame_controller.draw_commands(ops).into_js(&env)
   }
 
-  #[napi]
-  pub fn draw_image(&self, x: u32, y: u32, image: DecodedImage) -> Result<()> {
-    self.frame_controller.draw_image(x, y, &image)
+  /// Canvas drawing context that draws into this window's frame
+  #[napi(catch_unwind)]
+  pub fn get_canvas(&self) -> Canvas {
+    Canvas::for_frame(self.frame_controller.clone())
   }
 
   /// Get the current frame buffer
-  #[napi]
-  pub fn get_frame_buffer(&self) -> Result<Buffer> {
-    self.frame_controller.get_frame_buffer()
+  #[napi(catch_unwind)]
+  pub fn get_frame_buffer(&self, env: Env) -> Result<Buffer> {
+    self.frame_controller.get_frame_buffer().into_js(&env)
   }
 
   /// Manually trigger a render
-  #[napi]
-  pub fn render(&self) -> Result<()> {
-    self.frame_controller.render()
+  #[napi(catch_unwind)]
+  pub fn render(&self, env: Env) -> Result<()> {
+    self.frame_controller.render().into_js(&env)
   }
 
-  /// Resize the frame buffer and window
-  #[napi]
-  pub fn resize(&self, width: u32, height: u32) -> Result<()> {
-    self.frame_controller.resize(width, height)
+  /// Resize the frame buffer and window, carrying the frame contents over
+  /// per the resize policy
+  #[napi(catch_unwind)]
+  pub fn resize(&self, env: Env, width: u32, height: u32) -> Result<()> {
+    self.frame_controller.resize(width, height).into_js(&env)
   }
 }
@@ -0,0 +1,221 @@
+//! Row-parallel execution of pixel loops on a persistent worker pool
+//!
+//! Work is split into bands of whole rows and every pixel is computed the same
+//! way regardless of which band it lands in, so output is identical for any
+//! thread count. Workers are started on first use and kept for later calls.
+
+use napi_derive::napi;
+use std::any::Any;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::sync::{Arc, Condvar, Mutex, OnceLock, PoisonError};
+use std::thread;
+
+/// Configured worker count; 0 means one per available CPU
+static THREAD_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Most threads per available CPU; workers are never retired, so a huge
+/// count would keep that many OS threads for the life of the process
+const MAX_THREADS_PER_CPU: usize = 4;
+
+/// Below this many bytes the work stays on the calling thread
+const MIN_PARALLEL_BYTES: usize = 256 * 1024;
+
+fn cpus() -> usize {
+  thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+pub fn thread_count() -> usize {
+  match THREAD_COUNT.load(Ordering::Relaxed) {
+    0 => cpus(),
+    n => n as usize,
+  }
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Workers that take bands from a shared queue
+struct Pool {
+  sender: Sender<Job>,
+  receiver: Arc<Mutex<Receiver<Job>>>,
+  workers: Mutex<usize>,
+}
+
+static POOL: OnceLock<Pool> = OnceLock::new();
+
+thread_local! {
+  /// Set on pool workers, whose own parallel calls run inline so a worker
+  /// never waits on bands queued behind it
+  static IS_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+impl Pool {
+  /// The pool, with up to `count` workers started; `None` when no worker
+  /// could be started
+  fn with_workers(count: usize) -> Option<&'static Pool> {
+    let pool = POOL.get_or_init(|| {
+      let (sender, receiver) = mpsc::channel();
+      Pool {
+        sender,
+        receiver: Arc::new(Mutex::new(receiver)),
+        workers: Mutex::new(0),
+      }
+    });
+    let mut workers = pool.workers.lock().unwrap_or_else(PoisonError::into_inner);
+    while *workers < count {
+      let receiver = pool.receiver.clone();
+      let spawned = thread::Builder::new()
+        .name(format!("overlay-render-{}", *workers))
+        .spawn(move || Pool::work(&receiver));
+      if spawned.is_err() {
+        break;
+      }
+      *workers += 1;
+    }
+    (*workers > 0).then_some(pool)
+  }
+
+  fn work(receiver: &Mutex<Receiver<Job>>) {
+    IS_WORKER.with(|worker| worker.set(true));
+    loop {
+      let job = receiver
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .recv();
+      match job {
+        Ok(job) => job(),
+        Err(_) => return,
+      }
+    }
+  }
+}
+
+/// Counts down the bands handed to workers and keeps the first panic
+struct Latch {
+  state: Mutex<(usize, Option<Box<dyn Any + Send>>)>,
+  done: Condvar,
+}
+
+impl Latch {
+  fn finish(&self, panic: Option<Box<dyn Any + Send>>) {
+    let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+    state.0 -= 1;
+    if state.1.is_none() {
+      state.1 = panic;
+    }
+    self.done.notify_all();
+  }
+
+  /// Block until every band finished, returning a worker's panic if any
+  fn wait(&self) -> Option<Box<dyn Any + Send>> {
+    let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+    while state.0 > 0 {
+      state = self
+        .done
+        .wait(state)
+        .unwrap_or_else(PoisonError::into_inner);
+    }
+    state.1.take()
+  }
+}
+
+/// Waits for the workers even when the caller's own band panics, so no
+/// worker outlives the data it borrows
+struct WaitGuard<'a>(&'a Latch);
+
+impl Drop for WaitGuard<'_> {
+  fn drop(&mut self) {
+    if let Some(panic) = self.0.wait() {
+      if !thread::panicking() {
+        panic::resume_unwind(panic);
+      }
+    }
+  }
+}
+
+/// Run `work(first_row, band)` over contiguous bands of rows of `stride`
+/// bytes. The last row may be shorter than `stride`.
+pub fn for_each_row_band<F>(data: &mut [u8], stride: usize, work: F)
+where
+  F: Fn(usize, &mut [u8]) + Sync,
+{
+  if stride == 0 || data.is_empty() {
+    return;
+  }
+  let rows = data.len().div_ceil(stride);
+  let threads = thread_count().min(rows);
+  if threads <= 1 || data.len() < MIN_PARALLEL_BYTES || IS_WORKER.with(Cell::get) {
+    work(0, data);
+    return;
+  }
+
+  let Some(pool) = Pool::with_workers(threads - 1) else {
+    work(0, data);
+    return;
+  };
+
+  let band_rows = rows.div_ceil(threads);
+  let work = &work;
+  let mut bands = data.chunks_mut(band_rows * stride).enumerate();
+  let first = bands.next();
+  let latch = Latch {
+    state: Mutex::new((bands.len(), None)),
+    done: Condvar::new(),
+  };
+  let _guard = WaitGuard(&latch);
+  for (index, band) in bands {
+    let latch = &latch;
+    let job: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+      let result = panic::catch_unwind(AssertUnwindSafe(|| work(index * band_rows, band)));
+      latch.finish(result.err());
+    });
+    // SAFETY: the guard blocks until every job has run, so the data and
+    // closure the job borrows outlive it
+    let job = unsafe { std::mem::transmute::<Box<dyn FnOnce() + Send + '_>, Job>(job) };
+    if let Err(SendError(job)) = pool.sender.send(job) {
+      job();
+    }
+  }
+  // The calling thread takes the first band itself
+  if let Some((_, band)) = first {
+    work(0, band);
+  }
+}
+
+/// Run `work(y, row)` for every row of `stride` bytes, in parallel
+pub fn for_each_row<F>(data: &mut [u8], stride: usize, work: F)
+where
+  F: Fn(usize, &mut [u8]) + Sync,
+{
+  for_each_row_band(data, stride, |first, band| {
+    for (index, row) in band.chunks_mut(stride).enumerate() {
+      work(first + index, row);
+    }
+  });
+}
+
+/// Run `work` over pixel-aligned chunks of a flat RGBA buffer, in parallel
+pub fn for_each_pixels<F>(data: &mut [u8], work: F)
+where
+  F: Fn(&mut [u8]) + Sync,
+{
+  for_each_row_band(data, 4, |_, band| work(band));
+}
+
+// NAPI exports
+/// Set the number of threads used for large fills, blits and blends.
+/// 0 uses one thread per CPU; 1 disables parallelism. Counts above four
+/// threads per CPU are lowered to that.
+#[napi(catch_unwind)]
+pub fn set_render_threads(count: u32) {
+  let max = (cpus() * MAX_THREADS_PER_CPU).min(u32::MAX as usize) as u32;
+  THREAD_COUNT.store(count.min(max), Ordering::Relaxed);
+}
+
+/// Number of threads large fills, blits and blends are split across
+#[napi(catch_unwind)]
+pub fn get_render_threads() -> u32 {
+  thread_count() as u32
+}
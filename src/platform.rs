@@ -0,0 +1,175 @@
+//! What the current platform and windowing backend can do
+//!
+//! Several window features only exist on some platforms. Calls to them
+//! quietly do nothing elsewhere, unless strict mode turns that into an error.
+
+use crate::error::{OverlayError, OverlayResult};
+use napi_derive::napi;
+use raw_window_handle::{HasDisplayHandle, RawDisplayHandle};
+
+/// Windowing system windows are created on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+  Win32,
+  AppKit,
+  X11,
+  Wayland,
+  Other,
+}
+
+impl Backend {
+  /// The backend behind a display handle
+  pub fn of(display: &impl HasDisplayHandle) -> Self {
+    match display.display_handle().map(|handle| handle.as_raw()) {
+      Ok(RawDisplayHandle::Windows(_)) => Backend::Win32,
+      Ok(RawDisplayHandle::AppKit(_)) => Backend::AppKit,
+      Ok(RawDisplayHandle::Xlib(_) | RawDisplayHandle::Xcb(_)) => Backend::X11,
+      Ok(RawDisplayHandle::Wayland(_)) => Backend::Wayland,
+      _ => Backend::Other,
+    }
+  }
+
+  /// The backend windows would be created on, without opening a display.
+  /// Like winit, Wayland is preferred when a compositor is advertised.
+  pub fn native() -> Self {
+    if cfg!(target_os = "windows") {
+      Backend::Win32
+    } else if cfg!(target_os = "macos") {
+      Backend::AppKit
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some_and(|display| !display.is_empty()) {
+      Backend::Wayland
+    } else {
+      Backend::X11
+    }
+  }
+
+  /// Whether this build can create windows on the backend. Window creation
+  /// is only implemented for Win32, so nothing else supports any feature.
+  pub fn creates_windows(self) -> bool {
+    self == Backend::Win32
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Backend::Win32 => "win32",
+      Backend::AppKit => "appkit",
+      Backend::X11 => "x11",
+      Backend::Wayland => "wayland",
+      Backend::Other => "other",
+    }
+  }
+}
+
+/// A window feature that isn't available everywhere
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Feature {
+  CaptureExclusion,
+  SkipTaskbar,
+  ClickThrough,
+  Transparency,
+  AlwaysOnBottom,
+  PerPixelHitTest,
+  WindowIcon,
+  TaskbarProgress,
+}
+
+impl Feature {
+  pub fn supported(self, backend: Backend) -> bool {
+    if !backend.creates_windows() {
+      return false;
+    }
+    let desktop = backend != Backend::Other;
+    match self {
+      Feature::CaptureExclusion | Feature::SkipTaskbar | Feature::TaskbarProgress => {
+        backend == Backend::Win32
+      }
+      // Shapes are applied as window regions
+      Feature::PerPixelHitTest => backend == Backend::Win32,
+      Feature::ClickThrough | Feature::Transparency => desktop,
+      Feature::AlwaysOnBottom => desktop && backend != Backend::Wayland,
+      Feature::WindowIcon => matches!(backend, Backend::Win32 | Backend::X11),
+    }
+  }
+
+  /// The JS method that needs this feature, for error messages
+  fn method(self) -> &'static str {
+    match self {
+      Feature::CaptureExclusion => "setExcludeFromCapture",
+      Feature::SkipTaskbar => "setSkipTaskbar",
+      Feature::ClickThrough => "setIgnoreMouseEvents",
+      Feature::Transparency => "transparent",
+      Feature::AlwaysOnBottom => "setWindowLevel(AlwaysOnBottom)",
+      Feature::PerPixelHitTest => "setShape",
+      Feature::WindowIcon => "setIcon",
+      Feature::TaskbarProgress => "setProgress",
+    }
+  }
+
+  /// Whether the feature works on `backend`; in strict mode an unsupported
+  /// feature is an error instead
+  pub fn check(self, backend: Backend, strict: bool) -> OverlayResult<bool> {
+    if self.supported(backend) {
+      return Ok(true);
+    }
+    if strict {
+      return Err(OverlayError::Unsupported {
+        feature: self.method(),
+        platform: std::env::consts::OS,
+        backend: backend.name(),
+      });
+    }
+    Ok(false)
+  }
+}
+
+/// Features supported on the current platform and windowing backend
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+  /// Operating system as Rust names it: `windows`, `macos`, `linux`, ...
+  pub platform: String,
+  /// Windowing backend: `win32`, `appkit`, `x11`, `wayland` or `other`
+  pub backend: String,
+  /// Windows can be created; when false, every feature below is false too
+  pub window_creation: bool,
+  /// `setExcludeFromCapture` hides the window from screen capture
+  pub capture_exclusion: bool,
+  /// `setSkipTaskbar` hides the taskbar button
+  pub skip_taskbar: bool,
+  /// `setIgnoreMouseEvents` passes input through the window
+  pub click_through: bool,
+  /// Transparent pixels show what is behind the window
+  pub transparency: bool,
+  /// `WindowLevel.AlwaysOnBottom` keeps the window below others
+  pub always_on_bottom: bool,
+  /// `setShape` and `setCornerRadius` limit where the window takes input
+  pub per_pixel_hit_test: bool,
+  /// `setIcon` changes the window icon
+  pub window_icon: bool,
+  /// `setProgress` shows progress on the taskbar button
+  pub taskbar_progress: bool,
+}
+
+pub fn capabilities(backend: Backend) -> Capabilities {
+  Capabilities {
+    platform: std::env::consts::OS.to_string(),
+    backend: backend.name().to_string(),
+    window_creation: backend.creates_windows(),
+    capture_exclusion: Feature::CaptureExclusion.supported(backend),
+    skip_taskbar: Feature::SkipTaskbar.supported(backend),
+    click_through: Feature::ClickThrough.supported(backend),
+    transparency: Feature::Transparency.supported(backend),
+    always_on_bottom: Feature::AlwaysOnBottom.supported(backend),
+    per_pixel_hit_test: Feature::PerPixelHitTest.supported(backend),
+    window_icon: Feature::WindowIcon.supported(backend),
+    taskbar_progress: Feature::TaskbarProgress.supported(backend),
+  }
+}
+
+// NAPI exports
+/// Capabilities of the platform windows would be created on, without opening
+/// a display; `OverlayApp.getCapabilities()` asks the actual display
+#[napi(catch_unwind)]
+pub fn get_platform_capabilities() -> Capabilities {
+  capabilities(Backend::native())
+}
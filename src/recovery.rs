@@ -0,0 +1,80 @@
+//! Surviving panics in native code
+//!
+//! Exports catch panics and throw them as JS errors instead of aborting Node.
+//! A panic while a lock is held poisons it; the helpers here repair the data
+//! behind the lock and clear the poison, so one failed call doesn't fail
+//! every call after it. With the `test-hooks` feature, `injectPanic` makes a
+//! chosen code path panic, so tests can check all of this.
+
+use crate::error::{OverlayError, OverlayResult};
+#[cfg(feature = "test-hooks")]
+use napi_derive::napi;
+#[cfg(feature = "test-hooks")]
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Code path `injectPanic` makes panic
+#[cfg(feature = "test-hooks")]
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PanicSite {
+  /// Registering an image, halfway through updating the sprite registry
+  SpriteRegistry,
+  /// Drawing into a canvas or window frame, with the window locked
+  Drawing,
+  /// Redrawing a window in the event loop, with the window locked
+  EventLoop,
+}
+
+/// The armed site plus one; 0 when nothing is armed
+#[cfg(feature = "test-hooks")]
+static ARMED: AtomicU8 = AtomicU8::new(0);
+
+/// Panic if a panic was injected at `site`. Each injection fires once.
+#[cfg(feature = "test-hooks")]
+pub fn check(site: PanicSite) {
+  if ARMED
+    .compare_exchange(site as u8 + 1, 0, Ordering::SeqCst, Ordering::SeqCst)
+    .is_ok()
+  {
+    panic!("Injected panic at {:?}", site);
+  }
+}
+
+/// Lock `mutex` for a call from JS. If a panic poisoned it, the data is
+/// restored with `repair`, the poison cleared, and this call fails with
+/// `StatePoisoned` so the caller knows earlier changes may be lost.
+pub fn lock<'a, T>(
+  mutex: &'a Mutex<T>,
+  repair: impl FnOnce(&mut T),
+) -> OverlayResult<MutexGuard<'a, T>> {
+  mutex.lock().map_err(|poisoned| {
+    repair(&mut poisoned.into_inner());
+    mutex.clear_poison();
+    OverlayError::StatePoisoned
+  })
+}
+
+/// Lock `mutex` where a poisoned lock can't be reported, like in the event
+/// loop: the data is restored with `repair` and used
+pub fn lock_or_repair<'a, T>(
+  mutex: &'a Mutex<T>,
+  repair: impl FnOnce(&mut T),
+) -> MutexGuard<'a, T> {
+  mutex.lock().unwrap_or_else(|poisoned| {
+    mutex.clear_poison();
+    let mut guard = poisoned.into_inner();
+    repair(&mut guard);
+    guard
+  })
+}
+
+// NAPI exports
+/// Make the next call that reaches `site` panic, or with `null` disarm it.
+/// For testing that panics surface as errors; the process keeps running.
+/// Only present in builds with the `test-hooks` feature.
+#[cfg(feature = "test-hooks")]
+#[napi(catch_unwind)]
+pub fn inject_panic(site: Option<PanicSite>) {
+  ARMED.store(site.map_or(0, |site| site as u8 + 1), Ordering::SeqCst);
+}
@@ -0,0 +1,200 @@
+//! How the frame maps onto the window surface when their sizes differ
+
+use crate::buffer::{check_layout, BufferLayout};
+use crate::parallel::for_each_row;
+use crate::types::Rect;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScaleMode {
+  /// The frame follows the window size; content is kept across resizes
+  #[default]
+  MatchWindow,
+  /// Fixed-size frame stretched to fill the window, ignoring aspect ratio
+  Stretch,
+  /// Fixed-size frame scaled uniformly to fit, centered with letterboxing
+  Fit,
+  /// Fixed-size frame scaled by the largest whole factor that fits (at least
+  /// 1), centered, so every frame pixel covers the same number of surface pixels
+  Integer,
+  /// Fixed-size frame shown unscaled and centered
+  Fixed,
+}
+
+/// What happens to the frame contents when the frame follows a window resize
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ResizePolicy {
+  /// Start from a transparent frame
+  Clear,
+  /// Keep the overlapping top-left region
+  #[default]
+  PreserveTopLeft,
+  /// Scale the old contents to the new size
+  Rescale,
+}
+
+/// Where a `frame_width` x `frame_height` frame lands on the surface. The
+/// rectangle may extend past the surface, in which case it is cropped.
+pub fn placement(
+  mode: ScaleMode,
+  frame_width: u32,
+  frame_height: u32,
+  surface_width: u32,
+  surface_height: u32,
+) -> Rect {
+  let centered = |width: u32, height: u32| Rect {
+    x: ((surface_width as i64 - width as i64) / 2) as i32,
+    y: ((surface_height as i64 - height as i64) / 2) as i32,
+    width,
+    height,
+  };
+  match mode {
+    ScaleMode::MatchWindow => Rect {
+      x: 0,
+      y: 0,
+      width: frame_width,
+      height: frame_height,
+    },
+    ScaleMode::Stretch => Rect {
+      x: 0,
+      y: 0,
+      width: surface_width,
+      height: surface_height,
+    },
+    _ if frame_width == 0 || frame_height == 0 => centered(0, 0),
+    ScaleMode::Fit => {
+      let scale = (surface_width as f64 / frame_width as f64)
+        .min(surface_height as f64 / frame_height as f64);
+      centered(
+        (frame_width as f64 * scale).round() as u32,
+        (frame_height as f64 * scale).round() as u32,
+      )
+    }
+    ScaleMode::Integer => {
+      let scale = (surface_width / frame_width)
+        .min(surface_height / frame_height)
+        .max(1);
+      centered(frame_width * scale, frame_height * scale)
+    }
+    ScaleMode::Fixed => centered(frame_width, frame_height),
+  }
+}
+
+/// Draw a packed RGBA frame into a packed surface buffer at `rect`, scaled
+/// with nearest-neighbor sampling. Surface pixels outside `rect` are cleared.
+pub fn scale_into(
+  src: &[u8],
+  src_width: u32,
+  src_height: u32,
+  dst: &mut [u8],
+  dst_width: u32,
+  rect: &Rect,
+) {
+  let stride = dst_width as usize * 4;
+  let src_stride = src_width as usize * 4;
+  if src.len() < src_stride * src_height as usize || src_width == 0 || src_height == 0 {
+    dst.fill(0);
+    return;
+  }
+  // Source index of the destination pixel centered at offset `d` into the rect
+  let source = |d: i64, dst_size: u32, src_size: u32| {
+    ((d * 2 + 1) * src_size as i64 / (dst_size as i64 * 2)) as usize
+  };
+  let x0 = (rect.x as i64).clamp(0, dst_width as i64) as usize;
+  let x1 = (rect.x as i64 + rect.width as i64).clamp(0, dst_width as i64) as usize;
+  let columns: Vec<usize> = (x0..x1)
+    .map(|x| source(x as i64 - rect.x as i64, rect.width, src_width) * 4)
+    .collect();
+  for_each_row(dst, stride, |y, row| {
+    let dy = y as i64 - rect.y as i64;
+    if dy < 0 || dy >= rect.height as i64 || x1 <= x0 {
+      row.fill(0);
+      return;
+    }
+    let src_row = &src[source(dy, rect.height, src_height) * src_stride..][..src_stride];
+    row[..x0 * 4].fill(0);
+    row[x1 * 4..].fill(0);
+    for (pixel, &sx) in row[x0 * 4..x1 * 4].chunks_exact_mut(4).zip(&columns) {
+      pixel.copy_from_slice(&src_row[sx..sx + 4]);
+    }
+  });
+}
+
+/// Copy a packed frame into a new size according to `policy`
+pub fn resize_frame(
+  frame: &[u8],
+  width: u32,
+  height: u32,
+  new_width: u32,
+  new_height: u32,
+  policy: ResizePolicy,
+) -> Vec<u8> {
+  let mut out = vec![0; crate::buffer::calculate_buffer_size(new_width, new_height)];
+  match policy {
+    ResizePolicy::Clear => {}
+    ResizePolicy::PreserveTopLeft => {
+      let span = width.min(new_width) as usize * 4;
+      let (stride, new_stride) = (width as usize * 4, new_width as usize * 4);
+      for y in 0..height.min(new_height) as usize {
+        let Some(src) = frame.get(y * stride..y * stride + span) else {
+          break;
+        };
+        out[y * new_stride..y * new_stride + span].copy_from_slice(src);
+      }
+    }
+    ResizePolicy::Rescale => {
+      let rect = Rect {
+        x: 0,
+        y: 0,
+        width: new_width,
+        height: new_height,
+      };
+      scale_into(frame, width, height, &mut out, new_width, &rect);
+    }
+  }
+  out
+}
+
+// NAPI exports
+/// The rectangle of the surface a frame is drawn into under `mode`; it may
+/// extend past the surface, which crops the frame
+#[napi(catch_unwind)]
+pub fn get_scale_placement(
+  mode: ScaleMode,
+  frame_width: u32,
+  frame_height: u32,
+  surface_width: u32,
+  surface_height: u32,
+) -> Rect {
+  placement(
+    mode,
+    frame_width,
+    frame_height,
+    surface_width,
+    surface_height,
+  )
+}
+
+/// A copy of a packed RGBA frame carried over to a new size the way a
+/// window's frame is when it follows a resize under `policy`
+#[napi(catch_unwind)]
+pub fn resize_frame_buffer(
+  frame: Buffer,
+  width: u32,
+  height: u32,
+  new_width: u32,
+  new_height: u32,
+  policy: ResizePolicy,
+) -> Result<Buffer> {
+  let layout = BufferLayout {
+    width,
+    height,
+    stride: None,
+    format: None,
+  };
+  check_layout(&frame, &layout)?;
+  Ok(resize_frame(&frame, width, height, new_width, new_height, policy).into())
+}
@@ -0,0 +1,93 @@
+// Layers are blended into a premultiplied composite texture using the
+// frame's own (non-linear) color values, so results match the CPU compositor.
+
+struct Params {
+  // Non-zero when the texture is sRGB (layers) or the target is (present)
+  srgb: u32,
+  // Non-zero when the texture holds (layers) or the target wants (present)
+  // premultiplied colors
+  premultiplied: u32,
+  // Whole-window opacity, used by the present pass
+  opacity: f32,
+  _padding: u32,
+  // Rectangle of the target the composite is scaled into, as fractions of
+  // the target size: x, y, width, height (present pass)
+  placement: vec4<f32>,
+}
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(0) @binding(2) var<uniform> params: Params;
+
+struct Vertex {
+  @location(0) position: vec2<f32>,
+  @location(1) uv: vec2<f32>,
+  @location(2) color: vec4<f32>,
+}
+
+struct Varyings {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec4<f32>,
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+  let low = c * 12.92;
+  let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+  return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+  let low = c / 12.92;
+  let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+  return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+@vertex
+fn vs_layer(vertex: Vertex) -> Varyings {
+  var out: Varyings;
+  out.position = vec4<f32>(vertex.position, 0.0, 1.0);
+  out.uv = vertex.uv;
+  out.color = vertex.color;
+  return out;
+}
+
+@fragment
+fn fs_layer(in: Varyings) -> @location(0) vec4<f32> {
+  var texel = textureSample(source_texture, source_sampler, in.uv);
+  if params.srgb != 0u {
+    texel = vec4<f32>(linear_to_srgb(texel.rgb), texel.a);
+  }
+  if params.premultiplied == 0u {
+    texel = vec4<f32>(texel.rgb * texel.a, texel.a);
+  }
+  return texel * in.color;
+}
+
+// One triangle covering the target
+@vertex
+fn vs_present(@builtin(vertex_index) index: u32) -> Varyings {
+  let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+  var out: Varyings;
+  out.position = vec4<f32>(corner * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+  out.uv = corner;
+  out.color = vec4<f32>(1.0);
+  return out;
+}
+
+@fragment
+fn fs_present(in: Varyings) -> @location(0) vec4<f32> {
+  let uv = (in.uv - params.placement.xy) / params.placement.zw;
+  var color = textureSample(source_texture, source_sampler, uv) * params.opacity;
+  // Outside the placement the target is cleared
+  if any(uv < vec2<f32>(0.0)) || any(uv >= vec2<f32>(1.0)) {
+    color = vec4<f32>(0.0);
+  }
+  if params.premultiplied == 0u && color.a > 0.0 {
+    color = vec4<f32>(color.rgb / color.a, color.a);
+  }
+  if params.srgb != 0u {
+    color = vec4<f32>(srgb_to_linear(color.rgb), color.a);
+  }
+  return color;
+}
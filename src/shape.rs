@@ -0,0 +1,232 @@
+//! Window shapes: the region of the window that is drawn and receives input
+//!
+//! Shapes are reduced to a list of rectangles in window pixels, which is all
+//! the platform layer needs. Rows with identical spans are merged, so simple
+//! shapes stay small.
+
+use crate::types::Rect;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use winit::window::Window;
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MaskFormat {
+  /// One byte per pixel
+  #[default]
+  Alpha8,
+  /// One bit per pixel, most significant bit first, rows padded to whole bytes
+  Bit1,
+}
+
+/// A mask of the window area; pixels at or above the threshold are inside
+#[napi(object)]
+pub struct ShapeMask {
+  pub data: Buffer,
+  pub width: u32,
+  pub height: u32,
+  /// Bytes per row; tightly packed when omitted
+  pub stride: Option<u32>,
+  pub format: Option<MaskFormat>,
+  /// Smallest alpha counted as inside (default 128); ignored for 1-bit masks
+  pub threshold: Option<u8>,
+}
+
+/// A window shape: the union of a mask and a list of rectangles
+#[napi(object)]
+pub struct WindowShape {
+  pub mask: Option<ShapeMask>,
+  pub rects: Option<Vec<Rect>>,
+}
+
+/// Build rectangles from the inside spans of each row, merging a row into the
+/// rectangles above it when its spans are identical
+fn region_from_rows(height: u32, mut spans: impl FnMut(u32) -> Vec<(u32, u32)>) -> Vec<Rect> {
+  let mut region = Vec::new();
+  // Rectangles still growing downwards, one per span of the previous row
+  let mut open: Vec<Rect> = Vec::new();
+  let mut previous: Vec<(u32, u32)> = Vec::new();
+  for y in 0..height {
+    let row = spans(y);
+    if row == previous {
+      for rect in &mut open {
+        rect.height += 1;
+      }
+      continue;
+    }
+    region.append(&mut open);
+    open = row
+      .iter()
+      .map(|&(x0, x1)| Rect {
+        x: x0 as i32,
+        y: y as i32,
+        width: x1 - x0,
+        height: 1,
+      })
+      .collect();
+    previous = row;
+  }
+  region.append(&mut open);
+  region
+}
+
+/// Spans of consecutive pixels for which `inside` holds
+fn row_spans(width: u32, inside: impl Fn(u32) -> bool) -> Vec<(u32, u32)> {
+  let mut spans = Vec::new();
+  let mut start = None;
+  for x in 0..width {
+    match (inside(x), start) {
+      (true, None) => start = Some(x),
+      (false, Some(x0)) => {
+        spans.push((x0, x));
+        start = None;
+      }
+      _ => {}
+    }
+  }
+  if let Some(x0) = start {
+    spans.push((x0, width));
+  }
+  spans
+}
+
+/// The region covered by a mask
+pub fn mask_region(mask: &ShapeMask) -> std::result::Result<Vec<Rect>, String> {
+  let format = mask.format.unwrap_or_default();
+  let row_bytes = match format {
+    MaskFormat::Alpha8 => mask.width as usize,
+    MaskFormat::Bit1 => (mask.width as usize).div_ceil(8),
+  };
+  let stride = mask.stride.map_or(row_bytes, |s| s as usize);
+  if stride < row_bytes {
+    return Err(format!(
+      "Mask stride {} is smaller than its row of {} bytes",
+      stride, row_bytes
+    ));
+  }
+  let required = match mask.height {
+    0 => 0,
+    h => (h as usize - 1) * stride + row_bytes,
+  };
+  if mask.data.len() < required {
+    return Err(format!(
+      "Mask too small for {}x{} (stride {}): expected at least {} bytes, got {}",
+      mask.width,
+      mask.height,
+      stride,
+      required,
+      mask.data.len()
+    ));
+  }
+  let threshold = mask.threshold.unwrap_or(128);
+  let data: &[u8] = &mask.data;
+  Ok(region_from_rows(mask.height, |y| {
+    let row = &data[y as usize * stride..];
+    match format {
+      MaskFormat::Alpha8 => row_spans(mask.width, |x| row[x as usize] >= threshold),
+      MaskFormat::Bit1 => row_spans(mask.width, |x| row[x as usize / 8] & (0x80 >> (x % 8)) != 0),
+    }
+  }))
+}
+
+/// The region of a `width` x `height` rectangle with rounded corners; a pixel
+/// is inside when at least half of it is covered
+pub fn rounded_region(width: u32, height: u32, radius: f64) -> Vec<Rect> {
+  let rect = Rect {
+    x: 0,
+    y: 0,
+    width,
+    height,
+  };
+  region_from_rows(height, |y| {
+    row_spans(width, |x| {
+      crate::clip::rounded_rect_coverage(&rect, radius, x, y) >= 128
+    })
+  })
+}
+
+/// The region of a shape
+pub fn shape_region(shape: &WindowShape) -> std::result::Result<Vec<Rect>, String> {
+  let mut region = match &shape.mask {
+    Some(mask) => mask_region(mask)?,
+    None => Vec::new(),
+  };
+  if let Some(rects) = &shape.rects {
+    region.extend(rects.iter().filter(|r| r.width > 0 && r.height > 0));
+  }
+  Ok(region)
+}
+
+pub fn validate_corner_radius(radius: f64) -> Result<f64> {
+  if radius.is_finite() && radius >= 0.0 {
+    Ok(radius)
+  } else {
+    Err(Error::new(
+      Status::InvalidArg,
+      "Corner radius must be a non-negative number",
+    ))
+  }
+}
+
+/// Set the window region, or restore the full window with `None`. Returns
+/// false where shaping isn't supported.
+#[cfg(target_os = "windows")]
+pub fn apply_region(window: &Window, region: Option<&[Rect]>) -> bool {
+  use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+  use windows_sys::Win32::Graphics::Gdi::{
+    CombineRgn, CreateRectRgn, DeleteObject, SetWindowRgn, RGN_OR,
+  };
+
+  let Ok(handle) = window.window_handle() else {
+    return false;
+  };
+  let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+    return false;
+  };
+  let hwnd = handle.hwnd.get() as *mut std::ffi::c_void;
+  unsafe {
+    let Some(rects) = region else {
+      return SetWindowRgn(hwnd, std::ptr::null_mut(), 1) != 0;
+    };
+    let combined = CreateRectRgn(0, 0, 0, 0);
+    for rect in rects {
+      let part = CreateRectRgn(
+        rect.x,
+        rect.y,
+        rect.x + rect.width as i32,
+        rect.y + rect.height as i32,
+      );
+      CombineRgn(combined, combined, part, RGN_OR);
+      DeleteObject(part);
+    }
+    // The system owns the region once it is set
+    if SetWindowRgn(hwnd, combined, 1) == 0 {
+      DeleteObject(combined);
+      return false;
+    }
+  }
+  true
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn apply_region(_window: &Window, _region: Option<&[Rect]>) -> bool {
+  // On non-Windows platforms, this is a no-op
+  false
+}
+
+// NAPI exports
+/// The rectangles a shape reduces to, as passed to the platform
+#[napi(catch_unwind)]
+pub fn compute_shape_region(shape: WindowShape) -> Result<Vec<Rect>> {
+  shape_region(&shape).map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// The rectangles of a window with rounded corners
+#[napi(catch_unwind)]
+pub fn compute_rounded_region(width: u32, height: u32, radius: f64) -> Result<Vec<Rect>> {
+  Ok(rounded_region(
+    width,
+    height,
+    validate_corner_radius(radius)?,
+  ))
+}
@@ -0,0 +1,557 @@
+//! Vectorized RGBA8 pixel kernels with runtime CPU feature detection
+//!
+//! Every kernel has a portable version in [`scalar`] that defines its exact
+//! output, and the vector paths are bit-identical to it. Straight-alpha blend
+//! and unpremultiply are vectorized on x86_64 only; NEON uses the scalar
+//! versions for those two.
+//!
+//! The benchmarks in `benches/` compare these kernels with the per-pixel
+//! `Color` methods they replace.
+
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+  Scalar,
+  #[cfg(target_arch = "x86_64")]
+  Sse2,
+  #[cfg(target_arch = "x86_64")]
+  Avx2,
+  #[cfg(target_arch = "aarch64")]
+  Neon,
+}
+
+impl SimdLevel {
+  pub fn name(&self) -> &'static str {
+    match self {
+      SimdLevel::Scalar => "scalar",
+      #[cfg(target_arch = "x86_64")]
+      SimdLevel::Sse2 => "sse2",
+      #[cfg(target_arch = "x86_64")]
+      SimdLevel::Avx2 => "avx2",
+      #[cfg(target_arch = "aarch64")]
+      SimdLevel::Neon => "neon",
+    }
+  }
+}
+
+/// Best instruction set available on this CPU, detected once
+pub fn level() -> SimdLevel {
+  static LEVEL: OnceLock<SimdLevel> = OnceLock::new();
+  *LEVEL.get_or_init(detect)
+}
+
+#[allow(unreachable_code)]
+fn detect() -> SimdLevel {
+  #[cfg(target_arch = "x86_64")]
+  {
+    if is_x86_feature_detected!("avx2") {
+      return SimdLevel::Avx2;
+    }
+    if is_x86_feature_detected!("sse2") {
+      return SimdLevel::Sse2;
+    }
+  }
+  #[cfg(target_arch = "aarch64")]
+  {
+    if std::arch::is_aarch64_feature_detected!("neon") {
+      return SimdLevel::Neon;
+    }
+  }
+  SimdLevel::Scalar
+}
+
+/// Fill every whole pixel of `dst` with `rgba`. The compiler vectorizes the
+/// plain loop at least as well as hand-written stores, so there is no
+/// dispatch.
+pub fn fill(dst: &mut [u8], rgba: [u8; 4]) {
+  scalar::fill(dst, rgba);
+}
+
+/// Convert straight-alpha pixels to premultiplied alpha
+pub fn premultiply(pixels: &mut [u8]) {
+  match level() {
+    #[cfg(target_arch = "x86_64")]
+    SimdLevel::Avx2 => unsafe { x86::premultiply_avx2(pixels) },
+    #[cfg(target_arch = "x86_64")]
+    SimdLevel::Sse2 => unsafe { x86::premultiply_sse2(pixels) },
+    #[cfg(target_arch = "aarch64")]
+    SimdLevel::Neon => unsafe { neon::premultiply(pixels) },
+    _ => scalar::premultiply(pixels),
+  }
+}
+
+/// Convert premultiplied pixels back to straight alpha
+pub fn unpremultiply(pixels: &mut [u8]) {
+  match level() {
+    #[cfg(target_arch = "x86_64")]
+    SimdLevel::Avx2 | SimdLevel::Sse2 => unsafe { x86::unpremultiply_sse2(pixels) },
+    _ => scalar::unpremultiply(pixels),
+  }
+}
+
+/// Source-over blend straight-alpha `src` pixels onto `dst`
+pub fn blend_straight(src: &[u8], dst: &mut [u8]) {
+  match level() {
+    #[cfg(target_arch = "x86_64")]
+    SimdLevel::Avx2 | SimdLevel::Sse2 => unsafe { x86::blend_straight_sse2(src, dst) },
+    _ => scalar::blend_straight(src, dst),
+  }
+}
+
+/// Source-over blend premultiplied `src` pixels onto `dst`
+pub fn blend_premultiplied(src: &[u8], dst: &mut [u8]) {
+  match level() {
+    #[cfg(target_arch = "x86_64")]
+    SimdLevel::Avx2 => unsafe { x86::blend_premultiplied_avx2(src, dst) },
+    #[cfg(target_arch = "x86_64")]
+    SimdLevel::Sse2 => unsafe { x86::blend_premultiplied_sse2(src, dst) },
+    #[cfg(target_arch = "aarch64")]
+    SimdLevel::Neon => unsafe { neon::blend_premultiplied(src, dst) },
+    _ => scalar::blend_premultiplied(src, dst),
+  }
+}
+
+/// Swap the red and blue channels (RGBA <-> BGRA)
+pub fn swizzle_rb(pixels: &mut [u8]) {
+  match level() {
+    #[cfg(target_arch = "x86_64")]
+    SimdLevel::Avx2 => unsafe { x86::swizzle_rb_avx2(pixels) },
+    #[cfg(target_arch = "x86_64")]
+    SimdLevel::Sse2 => unsafe { x86::swizzle_rb_sse2(pixels) },
+    #[cfg(target_arch = "aarch64")]
+    SimdLevel::Neon => unsafe { neon::swizzle_rb(pixels) },
+    _ => scalar::swizzle_rb(pixels),
+  }
+}
+
+/// Reference implementations, one pixel at a time
+pub mod scalar {
+  /// `a * b / 255` rounded to nearest, exact for all 8-bit inputs
+  #[inline]
+  pub fn mul_div_255(a: u32, b: u32) -> u32 {
+    let t = a * b + 128;
+    (t + (t >> 8)) >> 8
+  }
+
+  #[inline]
+  pub fn premultiply_pixel([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    let alpha = a as u32;
+    [
+      mul_div_255(r as u32, alpha) as u8,
+      mul_div_255(g as u32, alpha) as u8,
+      mul_div_255(b as u32, alpha) as u8,
+      a,
+    ]
+  }
+
+  #[inline]
+  pub fn unpremultiply_pixel([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    if a == 0 {
+      return [0, 0, 0, 0];
+    }
+    let alpha = a as u32;
+    let channel = |c: u8| ((c as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+    [channel(r), channel(g), channel(b), a]
+  }
+
+  /// Straight-alpha source-over in `f32`, rounding half away from zero
+  #[inline]
+  pub fn blend_straight_pixel(src: [u8; 4], dst: [u8; 4]) -> [u8; 4] {
+    match src[3] {
+      0 => return dst,
+      255 => return src,
+      _ => {}
+    }
+    let src_a = src[3] as f32 / 255.0;
+    let dst_a = dst[3] as f32 / 255.0 * (1.0 - src_a);
+    let out_a = src_a + dst_a;
+    if out_a <= 0.0 {
+      return [0, 0, 0, 0];
+    }
+    let mix = |s: u8, d: u8| ((s as f32 * src_a + d as f32 * dst_a) / out_a).round() as u8;
+    [
+      mix(src[0], dst[0]),
+      mix(src[1], dst[1]),
+      mix(src[2], dst[2]),
+      (out_a * 255.0).round() as u8,
+    ]
+  }
+
+  #[inline]
+  pub fn blend_premultiplied_pixel(src: [u8; 4], dst: [u8; 4]) -> [u8; 4] {
+    let inv = 255 - src[3] as u32;
+    let mix = |s: u8, d: u8| (s as u32 + mul_div_255(d as u32, inv)).min(255) as u8;
+    [
+      mix(src[0], dst[0]),
+      mix(src[1], dst[1]),
+      mix(src[2], dst[2]),
+      mix(src[3], dst[3]),
+    ]
+  }
+
+  fn map(pixels: &mut [u8], f: impl Fn([u8; 4]) -> [u8; 4]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+      let rgba = f([pixel[0], pixel[1], pixel[2], pixel[3]]);
+      pixel.copy_from_slice(&rgba);
+    }
+  }
+
+  fn zip(src: &[u8], dst: &mut [u8], f: impl Fn([u8; 4], [u8; 4]) -> [u8; 4]) {
+    for (s, d) in src.chunks_exact(4).zip(dst.chunks_exact_mut(4)) {
+      let rgba = f([s[0], s[1], s[2], s[3]], [d[0], d[1], d[2], d[3]]);
+      d.copy_from_slice(&rgba);
+    }
+  }
+
+  pub fn fill(dst: &mut [u8], rgba: [u8; 4]) {
+    for chunk in dst.chunks_exact_mut(4) {
+      chunk.copy_from_slice(&rgba);
+    }
+  }
+
+  pub fn premultiply(pixels: &mut [u8]) {
+    map(pixels, premultiply_pixel);
+  }
+
+  pub fn unpremultiply(pixels: &mut [u8]) {
+    map(pixels, unpremultiply_pixel);
+  }
+
+  pub fn blend_straight(src: &[u8], dst: &mut [u8]) {
+    zip(src, dst, blend_straight_pixel);
+  }
+
+  pub fn blend_premultiplied(src: &[u8], dst: &mut [u8]) {
+    zip(src, dst, blend_premultiplied_pixel);
+  }
+
+  pub fn swizzle_rb(pixels: &mut [u8]) {
+    map(pixels, |[r, g, b, a]| [b, g, r, a]);
+  }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+  use super::scalar;
+  use std::arch::x86_64::*;
+
+  /// Per 16-bit lane `x * y / 255`, rounded like `scalar::mul_div_255`
+  #[inline(always)]
+  unsafe fn mul_div_255_sse2(x: __m128i, y: __m128i) -> __m128i {
+    let t = _mm_add_epi16(_mm_mullo_epi16(x, y), _mm_set1_epi16(128));
+    _mm_srli_epi16(_mm_add_epi16(t, _mm_srli_epi16(t, 8)), 8)
+  }
+
+  #[inline(always)]
+  unsafe fn mul_div_255_avx2(x: __m256i, y: __m256i) -> __m256i {
+    let t = _mm256_add_epi16(_mm256_mullo_epi16(x, y), _mm256_set1_epi16(128));
+    _mm256_srli_epi16(_mm256_add_epi16(t, _mm256_srli_epi16(t, 8)), 8)
+  }
+
+  /// Broadcast each pixel's alpha to its four 16-bit lanes
+  #[inline(always)]
+  unsafe fn alpha_sse2(x: __m128i) -> __m128i {
+    _mm_shufflehi_epi16(_mm_shufflelo_epi16(x, 0xFF), 0xFF)
+  }
+
+  #[inline(always)]
+  unsafe fn alpha_avx2(x: __m256i) -> __m256i {
+    _mm256_shufflehi_epi16(_mm256_shufflelo_epi16(x, 0xFF), 0xFF)
+  }
+
+  #[target_feature(enable = "sse2")]
+  pub unsafe fn premultiply_sse2(pixels: &mut [u8]) {
+    let zero = _mm_setzero_si128();
+    let alpha_lanes = _mm_set1_epi64x(0xFFFF_0000_0000_0000u64 as i64);
+    let mut chunks = pixels.chunks_exact_mut(16);
+    for chunk in &mut chunks {
+      let ptr = chunk.as_mut_ptr() as *mut __m128i;
+      let x = _mm_loadu_si128(ptr);
+      let premultiply = |half: __m128i| {
+        let scaled = mul_div_255_sse2(half, alpha_sse2(half));
+        _mm_or_si128(
+          _mm_andnot_si128(alpha_lanes, scaled),
+          _mm_and_si128(alpha_lanes, half),
+        )
+      };
+      let lo = premultiply(_mm_unpacklo_epi8(x, zero));
+      let hi = premultiply(_mm_unpackhi_epi8(x, zero));
+      _mm_storeu_si128(ptr, _mm_packus_epi16(lo, hi));
+    }
+    scalar::premultiply(chunks.into_remainder());
+  }
+
+  #[target_feature(enable = "avx2")]
+  pub unsafe fn premultiply_avx2(pixels: &mut [u8]) {
+    let zero = _mm256_setzero_si256();
+    let alpha_lanes = _mm256_set1_epi64x(0xFFFF_0000_0000_0000u64 as i64);
+    let mut chunks = pixels.chunks_exact_mut(32);
+    for chunk in &mut chunks {
+      let ptr = chunk.as_mut_ptr() as *mut __m256i;
+      let x = _mm256_loadu_si256(ptr);
+      let premultiply = |half: __m256i| {
+        let scaled = mul_div_255_avx2(half, alpha_avx2(half));
+        _mm256_or_si256(
+          _mm256_andnot_si256(alpha_lanes, scaled),
+          _mm256_and_si256(alpha_lanes, half),
+        )
+      };
+      // unpack and pack both work within 128-bit lanes, so pixel order is kept
+      let lo = premultiply(_mm256_unpacklo_epi8(x, zero));
+      let hi = premultiply(_mm256_unpackhi_epi8(x, zero));
+      _mm256_storeu_si256(ptr, _mm256_packus_epi16(lo, hi));
+    }
+    scalar::premultiply(chunks.into_remainder());
+  }
+
+  /// Unpremultiply one pixel held as four `i32` lanes. The float quotient is
+  /// corrected by at most one so the result equals the integer division.
+  #[inline(always)]
+  unsafe fn unpremultiply_pixel_sse2(c: __m128i) -> __m128i {
+    let alpha = _mm_shuffle_epi32(c, 0xFF);
+    let alpha_f = _mm_cvtepi32_ps(alpha);
+    let n = _mm_cvtepi32_ps(_mm_add_epi32(
+      _mm_sub_epi32(_mm_slli_epi32(c, 8), c),
+      _mm_srli_epi32(alpha, 1),
+    ));
+    let one = _mm_set1_ps(1.0);
+    let mut q = _mm_cvtepi32_ps(_mm_cvttps_epi32(_mm_div_ps(n, alpha_f)));
+    q = _mm_add_ps(
+      q,
+      _mm_and_ps(
+        _mm_cmple_ps(_mm_mul_ps(_mm_add_ps(q, one), alpha_f), n),
+        one,
+      ),
+    );
+    q = _mm_sub_ps(q, _mm_and_ps(_mm_cmpgt_ps(_mm_mul_ps(q, alpha_f), n), one));
+    let q = _mm_min_ps(q, _mm_set1_ps(255.0));
+
+    // Keep alpha itself, and zero everything when alpha is zero
+    let alpha_lane = _mm_set_epi32(-1, 0, 0, 0);
+    let result = _mm_or_si128(
+      _mm_andnot_si128(alpha_lane, _mm_cvttps_epi32(q)),
+      _mm_and_si128(alpha_lane, c),
+    );
+    _mm_andnot_si128(_mm_cmpeq_epi32(alpha, _mm_setzero_si128()), result)
+  }
+
+  #[target_feature(enable = "sse2")]
+  pub unsafe fn unpremultiply_sse2(pixels: &mut [u8]) {
+    let zero = _mm_setzero_si128();
+    let mut chunks = pixels.chunks_exact_mut(16);
+    for chunk in &mut chunks {
+      let ptr = chunk.as_mut_ptr() as *mut __m128i;
+      let x = _mm_loadu_si128(ptr);
+      let (lo, hi) = (_mm_unpacklo_epi8(x, zero), _mm_unpackhi_epi8(x, zero));
+      let p0 = unpremultiply_pixel_sse2(_mm_unpacklo_epi16(lo, zero));
+      let p1 = unpremultiply_pixel_sse2(_mm_unpackhi_epi16(lo, zero));
+      let p2 = unpremultiply_pixel_sse2(_mm_unpacklo_epi16(hi, zero));
+      let p3 = unpremultiply_pixel_sse2(_mm_unpackhi_epi16(hi, zero));
+      _mm_storeu_si128(
+        ptr,
+        _mm_packus_epi16(_mm_packs_epi32(p0, p1), _mm_packs_epi32(p2, p3)),
+      );
+    }
+    scalar::unpremultiply(chunks.into_remainder());
+  }
+
+  /// `f32::round` for non-negative lanes: round half away from zero
+  #[inline(always)]
+  unsafe fn round_sse2(x: __m128) -> __m128i {
+    let truncated = _mm_cvttps_epi32(x);
+    let fraction = _mm_sub_ps(x, _mm_cvtepi32_ps(truncated));
+    let up = _mm_castps_si128(_mm_cmpge_ps(fraction, _mm_set1_ps(0.5)));
+    // `up` lanes are -1 where rounding up
+    _mm_sub_epi32(truncated, up)
+  }
+
+  /// Straight-alpha source-over of one pixel held as four `i32` lanes,
+  /// performing the same `f32` operations as `scalar::blend_straight_pixel`
+  #[inline(always)]
+  unsafe fn blend_straight_pixel_sse2(s: __m128i, d: __m128i) -> __m128i {
+    let s_f = _mm_cvtepi32_ps(s);
+    let d_f = _mm_cvtepi32_ps(d);
+    let scale = _mm_set1_ps(255.0);
+    let src_a = _mm_div_ps(_mm_shuffle_ps(s_f, s_f, 0xFF), scale);
+    let dst_a = _mm_mul_ps(
+      _mm_div_ps(_mm_shuffle_ps(d_f, d_f, 0xFF), scale),
+      _mm_sub_ps(_mm_set1_ps(1.0), src_a),
+    );
+    let out_a = _mm_add_ps(src_a, dst_a);
+    let mix = _mm_div_ps(
+      _mm_add_ps(_mm_mul_ps(s_f, src_a), _mm_mul_ps(d_f, dst_a)),
+      out_a,
+    );
+    let alpha_lane = _mm_set_epi32(-1, 0, 0, 0);
+    let blended = _mm_or_si128(
+      _mm_andnot_si128(alpha_lane, round_sse2(mix)),
+      _mm_and_si128(alpha_lane, round_sse2(_mm_mul_ps(out_a, scale))),
+    );
+
+    // Fully transparent sources leave the destination untouched
+    let src_alpha = _mm_shuffle_epi32(s, 0xFF);
+    let keep = _mm_cmpeq_epi32(src_alpha, _mm_setzero_si128());
+    _mm_or_si128(_mm_and_si128(keep, d), _mm_andnot_si128(keep, blended))
+  }
+
+  #[target_feature(enable = "sse2")]
+  pub unsafe fn blend_straight_sse2(src: &[u8], dst: &mut [u8]) {
+    let zero = _mm_setzero_si128();
+    let len = src.len().min(dst.len()) / 16 * 16;
+    for offset in (0..len).step_by(16) {
+      let s = _mm_loadu_si128(src.as_ptr().add(offset) as *const __m128i);
+      let dst_ptr = dst.as_mut_ptr().add(offset) as *mut __m128i;
+      let d = _mm_loadu_si128(dst_ptr);
+      let (s_lo, s_hi) = (_mm_unpacklo_epi8(s, zero), _mm_unpackhi_epi8(s, zero));
+      let (d_lo, d_hi) = (_mm_unpacklo_epi8(d, zero), _mm_unpackhi_epi8(d, zero));
+      let p0 = blend_straight_pixel_sse2(
+        _mm_unpacklo_epi16(s_lo, zero),
+        _mm_unpacklo_epi16(d_lo, zero),
+      );
+      let p1 = blend_straight_pixel_sse2(
+        _mm_unpackhi_epi16(s_lo, zero),
+        _mm_unpackhi_epi16(d_lo, zero),
+      );
+      let p2 = blend_straight_pixel_sse2(
+        _mm_unpacklo_epi16(s_hi, zero),
+        _mm_unpacklo_epi16(d_hi, zero),
+      );
+      let p3 = blend_straight_pixel_sse2(
+        _mm_unpackhi_epi16(s_hi, zero),
+        _mm_unpackhi_epi16(d_hi, zero),
+      );
+      _mm_storeu_si128(
+        dst_ptr,
+        _mm_packus_epi16(_mm_packs_epi32(p0, p1), _mm_packs_epi32(p2, p3)),
+      );
+    }
+    scalar::blend_straight(&src[len..], &mut dst[len..]);
+  }
+
+  #[target_feature(enable = "sse2")]
+  pub unsafe fn blend_premultiplied_sse2(src: &[u8], dst: &mut [u8]) {
+    let zero = _mm_setzero_si128();
+    let max = _mm_set1_epi16(255);
+    let len = src.len().min(dst.len()) / 16 * 16;
+    for offset in (0..len).step_by(16) {
+      let s = _mm_loadu_si128(src.as_ptr().add(offset) as *const __m128i);
+      let dst_ptr = dst.as_mut_ptr().add(offset) as *mut __m128i;
+      let d = _mm_loadu_si128(dst_ptr);
+      let scale = |s: __m128i, d: __m128i| mul_div_255_sse2(d, _mm_sub_epi16(max, alpha_sse2(s)));
+      let lo = scale(_mm_unpacklo_epi8(s, zero), _mm_unpacklo_epi8(d, zero));
+      let hi = scale(_mm_unpackhi_epi8(s, zero), _mm_unpackhi_epi8(d, zero));
+      _mm_storeu_si128(dst_ptr, _mm_adds_epu8(s, _mm_packus_epi16(lo, hi)));
+    }
+    scalar::blend_premultiplied(&src[len..], &mut dst[len..]);
+  }
+
+  #[target_feature(enable = "avx2")]
+  pub unsafe fn blend_premultiplied_avx2(src: &[u8], dst: &mut [u8]) {
+    let zero = _mm256_setzero_si256();
+    let max = _mm256_set1_epi16(255);
+    let len = src.len().min(dst.len()) / 32 * 32;
+    for offset in (0..len).step_by(32) {
+      let s = _mm256_loadu_si256(src.as_ptr().add(offset) as *const __m256i);
+      let dst_ptr = dst.as_mut_ptr().add(offset) as *mut __m256i;
+      let d = _mm256_loadu_si256(dst_ptr);
+      let scale =
+        |s: __m256i, d: __m256i| mul_div_255_avx2(d, _mm256_sub_epi16(max, alpha_avx2(s)));
+      let lo = scale(_mm256_unpacklo_epi8(s, zero), _mm256_unpacklo_epi8(d, zero));
+      let hi = scale(_mm256_unpackhi_epi8(s, zero), _mm256_unpackhi_epi8(d, zero));
+      _mm256_storeu_si256(dst_ptr, _mm256_adds_epu8(s, _mm256_packus_epi16(lo, hi)));
+    }
+    scalar::blend_premultiplied(&src[len..], &mut dst[len..]);
+  }
+
+  #[target_feature(enable = "sse2")]
+  pub unsafe fn swizzle_rb_sse2(pixels: &mut [u8]) {
+    let keep = _mm_set1_epi32(0xFF00_FF00u32 as i32);
+    let low = _mm_set1_epi32(0xFF);
+    let mut chunks = pixels.chunks_exact_mut(16);
+    for chunk in &mut chunks {
+      let ptr = chunk.as_mut_ptr() as *mut __m128i;
+      let x = _mm_loadu_si128(ptr);
+      let swapped = _mm_or_si128(
+        _mm_and_si128(x, keep),
+        _mm_or_si128(
+          _mm_and_si128(_mm_srli_epi32(x, 16), low),
+          _mm_slli_epi32(_mm_and_si128(x, low), 16),
+        ),
+      );
+      _mm_storeu_si128(ptr, swapped);
+    }
+    scalar::swizzle_rb(chunks.into_remainder());
+  }
+
+  #[target_feature(enable = "avx2")]
+  pub unsafe fn swizzle_rb_avx2(pixels: &mut [u8]) {
+    let order = _mm256_setr_epi8(
+      2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11, 14, 13, 12, 15, 2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11,
+      14, 13, 12, 15,
+    );
+    let mut chunks = pixels.chunks_exact_mut(32);
+    for chunk in &mut chunks {
+      let ptr = chunk.as_mut_ptr() as *mut __m256i;
+      _mm256_storeu_si256(ptr, _mm256_shuffle_epi8(_mm256_loadu_si256(ptr), order));
+    }
+    scalar::swizzle_rb(chunks.into_remainder());
+  }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+  use super::scalar;
+  use std::arch::aarch64::*;
+
+  /// Per lane `x * y / 255`, rounded like `scalar::mul_div_255`
+  #[inline(always)]
+  unsafe fn mul_div_255(x: uint8x16_t, y: uint8x16_t) -> uint8x16_t {
+    let round = vdupq_n_u16(128);
+    let lo = vaddq_u16(vmull_u8(vget_low_u8(x), vget_low_u8(y)), round);
+    let hi = vaddq_u16(vmull_u8(vget_high_u8(x), vget_high_u8(y)), round);
+    vcombine_u8(
+      vshrn_n_u16::<8>(vaddq_u16(lo, vshrq_n_u16::<8>(lo))),
+      vshrn_n_u16::<8>(vaddq_u16(hi, vshrq_n_u16::<8>(hi))),
+    )
+  }
+
+  #[target_feature(enable = "neon")]
+  pub unsafe fn premultiply(pixels: &mut [u8]) {
+    let mut chunks = pixels.chunks_exact_mut(64);
+    for chunk in &mut chunks {
+      let mut p = vld4q_u8(chunk.as_ptr());
+      p.0 = mul_div_255(p.0, p.3);
+      p.1 = mul_div_255(p.1, p.3);
+      p.2 = mul_div_255(p.2, p.3);
+      vst4q_u8(chunk.as_mut_ptr(), p);
+    }
+    scalar::premultiply(chunks.into_remainder());
+  }
+
+  #[target_feature(enable = "neon")]
+  pub unsafe fn blend_premultiplied(src: &[u8], dst: &mut [u8]) {
+    let len = src.len().min(dst.len()) / 64 * 64;
+    for offset in (0..len).step_by(64) {
+      let s = vld4q_u8(src.as_ptr().add(offset));
+      let mut d = vld4q_u8(dst.as_ptr().add(offset));
+      let inv = vmvnq_u8(s.3);
+      d.0 = vqaddq_u8(s.0, mul_div_255(d.0, inv));
+      d.1 = vqaddq_u8(s.1, mul_div_255(d.1, inv));
+      d.2 = vqaddq_u8(s.2, mul_div_255(d.2, inv));
+      d.3 = vqaddq_u8(s.3, mul_div_255(d.3, inv));
+      vst4q_u8(dst.as_mut_ptr().add(offset), d);
+    }
+    scalar::blend_premultiplied(&src[len..], &mut dst[len..]);
+  }
+
+  #[target_feature(enable = "neon")]
+  pub unsafe fn swizzle_rb(pixels: &mut [u8]) {
+    let mut chunks = pixels.chunks_exact_mut(64);
+    for chunk in &mut chunks {
+      let p = vld4q_u8(chunk.as_ptr());
+      vst4q_u8(chunk.as_mut_ptr(), uint8x16x4_t(p.2, p.1, p.0, p.3));
+    }
+    scalar::swizzle_rb(chunks.into_remainder());
+  }
+}
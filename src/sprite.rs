@@ -0,0 +1,526 @@
+//! Process-wide image registry so images cross N-API once and are then drawn
+//! by id
+//!
+//! Small images are packed into shared atlas pages with a shelf allocator;
+//! larger ones get their own storage. A page's space is reclaimed once all of
+//! its images are gone. When the memory held by pages and standalone images
+//! exceeds the cache limit, the least recently drawn images are evicted.
+
+use crate::buffer::{with_layout_surface, BufferLayout};
+use crate::color::{blend_pixel, mul_div_255, premultiply_pixel, AlphaMode};
+use crate::error::{IntoJsResult, OverlayResult};
+use crate::surface::Surface;
+use crate::types::DecodedImage;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+/// Side length of an atlas page in pixels
+const ATLAS_SIZE: u32 = 1024;
+/// Images up to this size on both sides are packed into atlas pages
+const MAX_ATLAS_SPRITE: u32 = 256;
+const DEFAULT_LIMIT_BYTES: u64 = 64 * 1024 * 1024;
+/// Memory held by one atlas page
+const PAGE_BYTES: u64 = ATLAS_SIZE as u64 * ATLAS_SIZE as u64 * 4;
+
+pub static SPRITES: LazyLock<Mutex<SpriteCache>> =
+  LazyLock::new(|| Mutex::new(SpriteCache::default()));
+
+/// Lock the registry for a call from JS. After a panic it may be half
+/// updated, so it is emptied and the call fails with `StatePoisoned`.
+pub fn registry() -> OverlayResult<MutexGuard<'static, SpriteCache>> {
+  crate::recovery::lock(&SPRITES, SpriteCache::reset)
+}
+
+/// Lock the registry while presenting, emptying it after a panic
+pub fn registry_or_reset() -> MutexGuard<'static, SpriteCache> {
+  crate::recovery::lock_or_repair(&SPRITES, SpriteCache::reset)
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpriteDrawOptions {
+  /// Destination size; the sprite is scaled (nearest neighbor) when it differs
+  pub width: Option<u32>,
+  pub height: Option<u32>,
+  /// Source-over blend instead of replacing the destination pixels
+  pub blend: Option<bool>,
+  /// Alpha multiplier from 0 to 1, applied when blending
+  pub opacity: Option<f64>,
+}
+
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteStats {
+  /// Registered images
+  pub count: u32,
+  /// Images packed into atlas pages
+  pub atlas_sprites: u32,
+  pub atlas_pages: u32,
+  /// Memory held by atlas pages, including unused space
+  pub atlas_bytes: f64,
+  /// Pixel data of all registered images
+  pub sprite_bytes: f64,
+  /// Memory held by atlas pages and standalone images; this is what the limit
+  /// applies to
+  pub cached_bytes: f64,
+  pub limit_bytes: f64,
+  /// Images evicted to stay under the limit since startup
+  pub evictions: u32,
+}
+
+/// A row of equally tall slots in an atlas page, filled left to right
+#[derive(Debug)]
+struct Shelf {
+  y: u32,
+  height: u32,
+  /// Next free column
+  x: u32,
+}
+
+#[derive(Debug)]
+struct AtlasPage {
+  pixels: Vec<u8>,
+  shelves: Vec<Shelf>,
+  /// Sprites still stored in this page
+  live: u32,
+}
+
+impl AtlasPage {
+  fn new() -> Self {
+    Self {
+      pixels: vec![0; PAGE_BYTES as usize],
+      shelves: Vec::new(),
+      live: 0,
+    }
+  }
+
+  /// Reserve a `width` x `height` slot, preferring the shelf that wastes the
+  /// least height
+  fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+    let shelf = self
+      .shelves
+      .iter_mut()
+      .filter(|shelf| shelf.height >= height && ATLAS_SIZE - shelf.x >= width)
+      .min_by_key(|shelf| shelf.height - height);
+    let shelf = match shelf {
+      Some(shelf) => shelf,
+      None => {
+        let y = self
+          .shelves
+          .last()
+          .map_or(0, |shelf| shelf.y + shelf.height);
+        if ATLAS_SIZE - y < height {
+          return None;
+        }
+        self.shelves.push(Shelf { y, height, x: 0 });
+        self.shelves.last_mut()?
+      }
+    };
+    let position = (shelf.x, shelf.y);
+    shelf.x += width;
+    self.live += 1;
+    Some(position)
+  }
+}
+
+#[derive(Debug)]
+enum Storage {
+  Atlas { page: usize, x: u32, y: u32 },
+  Standalone(Vec<u8>),
+}
+
+#[derive(Debug)]
+struct Sprite {
+  storage: Storage,
+  width: u32,
+  height: u32,
+  last_used: u64,
+  /// Changes whenever an id is registered again, so copies can be refreshed
+  generation: u64,
+}
+
+impl Sprite {
+  fn bytes(&self) -> u64 {
+    self.width as u64 * self.height as u64 * 4
+  }
+}
+
+/// Straight RGBA pixels of a sprite with their row stride
+pub struct SpriteView<'a> {
+  pixels: &'a [u8],
+  stride: usize,
+  pub width: u32,
+  pub height: u32,
+  pub generation: u64,
+}
+
+impl SpriteView<'_> {
+  #[inline]
+  fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+    let index = y as usize * self.stride + x as usize * 4;
+    [
+      self.pixels[index],
+      self.pixels[index + 1],
+      self.pixels[index + 2],
+      self.pixels[index + 3],
+    ]
+  }
+
+  /// Tightly packed copy of the pixels
+  pub fn packed(&self) -> Vec<u8> {
+    let row_bytes = self.width as usize * 4;
+    let mut out = Vec::with_capacity(row_bytes * self.height as usize);
+    for y in 0..self.height as usize {
+      out.extend_from_slice(&self.pixels[y * self.stride..][..row_bytes]);
+    }
+    out
+  }
+
+  /// Draw at `(x, y)`, clipped to the surface bounds
+  pub fn draw(
+    &self,
+    surface: &mut Surface,
+    x: i64,
+    y: i64,
+    options: &SpriteDrawOptions,
+    mode: AlphaMode,
+  ) {
+    let width = options.width.unwrap_or(self.width);
+    let height = options.height.unwrap_or(self.height);
+    if self.width == 0 || self.height == 0 {
+      return;
+    }
+    let Some((cx, cy, cw, ch)) = surface.clip(x, y, width, height) else {
+      return;
+    };
+    let blend = options.blend.unwrap_or(false);
+    let opacity = (options.opacity.unwrap_or(1.0).clamp(0.0, 1.0) * 255.0).round() as u32;
+    let source = |d: i64, dst_size: u32, src_size: u32| {
+      ((d * 2 + 1) * src_size as i64 / (dst_size as i64 * 2)) as u32
+    };
+    surface.for_rows(cy, cy + ch, |py, row| {
+      let sy = source(py as i64 - y, height, self.height);
+      for px in cx..cx + cw {
+        let sx = source(px as i64 - x, width, self.width);
+        let mut src = self.pixel(sx, sy);
+        let index = px as usize * 4;
+        let dst = &mut row[index..index + 4];
+        if blend {
+          src[3] = mul_div_255(src[3] as u32, opacity) as u8;
+          if mode == AlphaMode::Premultiplied {
+            src = premultiply_pixel(src);
+          }
+          let out = blend_pixel(src, [dst[0], dst[1], dst[2], dst[3]], mode);
+          dst.copy_from_slice(&out);
+        } else {
+          if mode == AlphaMode::Premultiplied {
+            src = premultiply_pixel(src);
+          }
+          dst.copy_from_slice(&src);
+        }
+      }
+    });
+  }
+}
+
+#[derive(Debug)]
+pub struct SpriteCache {
+  sprites: HashMap<String, Sprite>,
+  /// Freed pages are `None` so page indices stay stable
+  pages: Vec<Option<AtlasPage>>,
+  limit_bytes: u64,
+  sprite_bytes: u64,
+  /// Pixel data of the images stored outside atlas pages
+  standalone_bytes: u64,
+  /// Monotonic draw counter for least-recently-used eviction
+  clock: u64,
+  evictions: u32,
+}
+
+impl Default for SpriteCache {
+  fn default() -> Self {
+    Self {
+      sprites: HashMap::new(),
+      pages: Vec::new(),
+      limit_bytes: DEFAULT_LIMIT_BYTES,
+      sprite_bytes: 0,
+      standalone_bytes: 0,
+      clock: 0,
+      evictions: 0,
+    }
+  }
+}
+
+impl SpriteCache {
+  /// Drop every image, keeping the limit. The clock keeps running so GPU
+  /// textures of the dropped images never match new ones.
+  fn reset(&mut self) {
+    *self = SpriteCache {
+      limit_bytes: self.limit_bytes,
+      clock: self.clock,
+      ..SpriteCache::default()
+    };
+  }
+
+  /// Store straight RGBA pixels under `id`, replacing any previous image
+  pub fn register(
+    &mut self,
+    id: &str,
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+  ) -> std::result::Result<(), String> {
+    let bytes = width as u64 * height as u64 * 4;
+    if self.limit_bytes > 0 && bytes > self.limit_bytes {
+      return Err(format!(
+        "Image '{}' needs {} bytes, more than the sprite cache limit of {}",
+        id, bytes, self.limit_bytes
+      ));
+    }
+    self.remove(id);
+
+    let storage = if width == 0 || height == 0 {
+      Storage::Standalone(Vec::new())
+    } else if self.packs_into_atlas(width, height) {
+      let (page, x, y) = self.allocate(width, height);
+      let atlas = self.pages[page].as_mut().expect("allocated page exists");
+      let (row_bytes, stride) = (width as usize * 4, ATLAS_SIZE as usize * 4);
+      for (row, src) in rgba
+        .chunks_exact(row_bytes)
+        .take(height as usize)
+        .enumerate()
+      {
+        let start = (y as usize + row) * stride + x as usize * 4;
+        atlas.pixels[start..start + row_bytes].copy_from_slice(src);
+      }
+      Storage::Atlas { page, x, y }
+    } else {
+      self.standalone_bytes += bytes;
+      Storage::Standalone(rgba[..bytes as usize].to_vec())
+    };
+    #[cfg(feature = "test-hooks")]
+    crate::recovery::check(crate::recovery::PanicSite::SpriteRegistry);
+
+    self.clock += 1;
+    self.sprite_bytes += bytes;
+    self.sprites.insert(
+      id.to_string(),
+      Sprite {
+        storage,
+        width,
+        height,
+        last_used: self.clock,
+        generation: self.clock,
+      },
+    );
+    self.evict_to_limit(id);
+    Ok(())
+  }
+
+  /// Whether an image goes into an atlas page. Under a limit smaller than a
+  /// page, a page could never be kept, so every image is stored on its own.
+  fn packs_into_atlas(&self, width: u32, height: u32) -> bool {
+    width <= MAX_ATLAS_SPRITE
+      && height <= MAX_ATLAS_SPRITE
+      && (self.limit_bytes == 0 || self.limit_bytes >= PAGE_BYTES)
+  }
+
+  fn allocate(&mut self, width: u32, height: u32) -> (usize, u32, u32) {
+    for (index, page) in self.pages.iter_mut().enumerate() {
+      if let Some((x, y)) = page.as_mut().and_then(|page| page.allocate(width, height)) {
+        return (index, x, y);
+      }
+    }
+    let index = match self.pages.iter().position(Option::is_none) {
+      Some(index) => index,
+      None => {
+        self.pages.push(None);
+        self.pages.len() - 1
+      }
+    };
+    let mut page = AtlasPage::new();
+    let (x, y) = page
+      .allocate(width, height)
+      .expect("atlas sprites fit an empty page");
+    self.pages[index] = Some(page);
+    (index, x, y)
+  }
+
+  /// Remove an image; returns false when it wasn't registered
+  pub fn remove(&mut self, id: &str) -> bool {
+    let Some(sprite) = self.sprites.remove(id) else {
+      return false;
+    };
+    self.sprite_bytes -= sprite.bytes();
+    match sprite.storage {
+      Storage::Atlas { page, .. } => {
+        if let Some(atlas) = &mut self.pages[page] {
+          atlas.live -= 1;
+          if atlas.live == 0 {
+            self.pages[page] = None;
+          }
+        }
+      }
+      Storage::Standalone(_) => self.standalone_bytes -= sprite.bytes(),
+    }
+    true
+  }
+
+  pub fn clear(&mut self) {
+    self.sprites.clear();
+    self.pages.clear();
+    self.sprite_bytes = 0;
+    self.standalone_bytes = 0;
+  }
+
+  /// Memory held by atlas pages, unused space included
+  fn atlas_bytes(&self) -> u64 {
+    self.pages.iter().flatten().count() as u64 * PAGE_BYTES
+  }
+
+  /// Memory the limit applies to
+  fn cached_bytes(&self) -> u64 {
+    self.atlas_bytes() + self.standalone_bytes
+  }
+
+  /// Set the limit in bytes (0 for none), evicting as needed
+  pub fn set_limit(&mut self, bytes: u64) {
+    self.limit_bytes = bytes;
+    self.evict_to_limit("");
+  }
+
+  /// Evict least recently used images, never `keep`, until under the limit.
+  /// An atlas page is freed once its last image is evicted.
+  fn evict_to_limit(&mut self, keep: &str) {
+    while self.limit_bytes > 0 && self.cached_bytes() > self.limit_bytes {
+      let oldest = self
+        .sprites
+        .iter()
+        .filter(|(id, _)| id.as_str() != keep)
+        .min_by_key(|(_, sprite)| sprite.last_used)
+        .map(|(id, _)| id.clone());
+      match oldest {
+        Some(id) => {
+          self.remove(&id);
+          self.evictions += 1;
+        }
+        None => break,
+      }
+    }
+  }
+
+  /// Look up an image for drawing and mark it as recently used
+  pub fn get(&mut self, id: &str) -> Result<SpriteView<'_>> {
+    self.clock += 1;
+    let sprite = self.sprites.get_mut(id).ok_or_else(|| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Image '{}' is not registered", id),
+      )
+    })?;
+    sprite.last_used = self.clock;
+    let (pixels, stride) = match &sprite.storage {
+      Storage::Atlas { page, x, y } => {
+        let stride = ATLAS_SIZE as usize * 4;
+        let atlas = self.pages[*page]
+          .as_ref()
+          .expect("atlas page of live sprite");
+        (
+          &atlas.pixels[*y as usize * stride + *x as usize * 4..],
+          stride,
+        )
+      }
+      Storage::Standalone(pixels) => (&pixels[..], sprite.width as usize * 4),
+    };
+    Ok(SpriteView {
+      pixels,
+      stride,
+      width: sprite.width,
+      height: sprite.height,
+      generation: sprite.generation,
+    })
+  }
+
+  pub fn stats(&self) -> SpriteStats {
+    SpriteStats {
+      count: self.sprites.len() as u32,
+      atlas_sprites: self
+        .sprites
+        .values()
+        .filter(|sprite| matches!(sprite.storage, Storage::Atlas { .. }))
+        .count() as u32,
+      atlas_pages: self.pages.iter().flatten().count() as u32,
+      atlas_bytes: self.atlas_bytes() as f64,
+      sprite_bytes: self.sprite_bytes as f64,
+      cached_bytes: self.cached_bytes() as f64,
+      limit_bytes: self.limit_bytes as f64,
+      evictions: self.evictions,
+    }
+  }
+}
+
+// NAPI exports
+/// Store an image natively under `id` so it can be drawn with `drawSprite`
+/// without sending its pixels again. Re-registering an id replaces it.
+#[napi(catch_unwind)]
+pub fn register_image(env: Env, id: String, image: DecodedImage) -> Result<()> {
+  let format = image.format.unwrap_or_default();
+  let rgba = crate::format::to_rgba(&image.data, image.width, image.height, format, image.stride)
+    .map_err(|e| Error::new(Status::InvalidArg, e))?;
+  registry()
+    .into_js(&env)?
+    .register(&id, &rgba, image.width, image.height)
+    .map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+/// Remove a registered image; returns false when it wasn't registered
+#[napi(catch_unwind)]
+pub fn unregister_image(env: Env, id: String) -> Result<bool> {
+  Ok(registry().into_js(&env)?.remove(&id))
+}
+
+#[napi(catch_unwind)]
+pub fn clear_images(env: Env) -> Result<()> {
+  registry().into_js(&env)?.clear();
+  Ok(())
+}
+
+/// Limit the memory held by registered images in bytes, whole atlas pages
+/// included; 0 removes the limit. Least recently drawn images are evicted
+/// first.
+#[napi(catch_unwind)]
+pub fn set_sprite_cache_limit(env: Env, bytes: f64) -> Result<()> {
+  registry().into_js(&env)?.set_limit(bytes.max(0.0) as u64);
+  Ok(())
+}
+
+#[napi(catch_unwind)]
+pub fn get_sprite_stats(env: Env) -> Result<SpriteStats> {
+  Ok(registry().into_js(&env)?.stats())
+}
+
+/// Draw a registered image into the caller's straight-alpha buffer
+#[napi(catch_unwind)]
+pub fn draw_sprite_in_place(
+  env: Env,
+  mut buffer: Buffer,
+  layout: BufferLayout,
+  id: String,
+  x: i32,
+  y: i32,
+  options: Option<SpriteDrawOptions>,
+) -> Result<()> {
+  let mut sprites = registry().into_js(&env)?;
+  let sprite = sprites.get(&id)?;
+  with_layout_surface(&mut buffer, &layout, |surface| {
+    sprite.draw(
+      surface,
+      x as i64,
+      y as i64,
+      &options.unwrap_or_default(),
+      AlphaMode::Straight,
+    )
+  })
+}
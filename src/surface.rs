@@ -0,0 +1,493 @@
+//! Mutable RGBA8 pixel surfaces with an explicit row stride
+
+use crate::color::{blend_pixel, AlphaMode};
+use crate::gradient::Paint;
+use crate::parallel::for_each_row;
+use crate::simd;
+
+/// A borrowed RGBA8 pixel buffer that drawing operations write into
+pub struct Surface<'a> {
+  data: &'a mut [u8],
+  width: u32,
+  height: u32,
+  stride: usize,
+  /// Writable area as `(x0, y0, x1, y1)`; drawing outside it is discarded
+  bounds: (u32, u32, u32, u32),
+  /// Rows saved before their first write, see `save_writes`
+  saved: Option<SavedRows>,
+}
+
+/// Pixels of columns `x0..x1` of the rows from `y0` on, each saved before it
+/// was first written to
+pub struct SavedRows {
+  pub x0: u32,
+  pub x1: u32,
+  pub y0: u32,
+  pub rows: Vec<Option<Vec<u8>>>,
+}
+
+impl<'a> Surface<'a> {
+  /// Wrap a buffer, validating that `height` rows of `stride` bytes fit in it.
+  /// `stride` defaults to `width * 4`.
+  pub fn new(
+    data: &'a mut [u8],
+    width: u32,
+    height: u32,
+    stride: Option<u32>,
+  ) -> std::result::Result<Self, String> {
+    let row_bytes = width as usize * 4;
+    let stride = stride.map(|s| s as usize).unwrap_or(row_bytes);
+    if stride < row_bytes {
+      return Err(format!(
+        "Stride {} is smaller than width * 4 ({})",
+        stride, row_bytes
+      ));
+    }
+    // The last row only needs its pixels, not the trailing padding
+    let required = match height {
+      0 => 0,
+      h => (h as usize - 1) * stride + row_bytes,
+    };
+    if data.len() < required {
+      return Err(format!(
+        "Buffer too small for {}x{} (stride {}): expected at least {} bytes, got {}",
+        width,
+        height,
+        stride,
+        required,
+        data.len()
+      ));
+    }
+    Ok(Self {
+      data,
+      width,
+      height,
+      stride,
+      bounds: (0, 0, width, height),
+      saved: None,
+    })
+  }
+
+  /// Wrap a tightly packed buffer, clipping to the rows that actually fit
+  pub fn packed(data: &'a mut [u8], width: u32, height: u32) -> Self {
+    let row_bytes = width as usize * 4;
+    let rows = data
+      .len()
+      .checked_div(row_bytes)
+      .map_or(0, |rows| rows.min(height as usize) as u32);
+    Self {
+      data,
+      width,
+      height: rows,
+      stride: row_bytes,
+      bounds: (0, 0, width, rows),
+      saved: None,
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  /// Writable area as `(x0, y0, x1, y1)`
+  pub fn bounds(&self) -> (u32, u32, u32, u32) {
+    self.bounds
+  }
+
+  /// Narrow the writable area to its intersection with `(x0, y0, x1, y1)`
+  pub fn restrict(&mut self, x0: u32, y0: u32, x1: u32, y1: u32) {
+    let (bx0, by0, bx1, by1) = self.bounds;
+    let (x0, y0) = (x0.clamp(bx0, bx1), y0.clamp(by0, by1));
+    self.bounds = (x0, y0, x1.clamp(x0, bx1), y1.clamp(y0, by1));
+  }
+
+  /// Save columns `x0..x1` of rows `y0..y1` as they are before the first
+  /// write to each row, until `take_saved` is called. Lets a clip blend over
+  /// the rows a draw touched instead of a copy of its whole area.
+  pub fn save_writes(&mut self, x0: u32, y0: u32, x1: u32, y1: u32) {
+    self.saved = Some(SavedRows {
+      x0,
+      x1,
+      y0,
+      rows: vec![None; y1.saturating_sub(y0) as usize],
+    });
+  }
+
+  /// Stop saving rows and return the ones saved so far
+  pub fn take_saved(&mut self) -> Option<SavedRows> {
+    self.saved.take()
+  }
+
+  /// Save the rows `y0..y1` about to be written that weren't saved yet
+  #[inline]
+  fn before_write(&mut self, y0: u32, y1: u32) {
+    let Some(saved) = &mut self.saved else {
+      return;
+    };
+    let end = saved.y0 + saved.rows.len() as u32;
+    let (start, span) = (saved.x0 as usize * 4, (saved.x1 - saved.x0) as usize * 4);
+    for y in y0.max(saved.y0)..y1.min(end) {
+      let row = &mut saved.rows[(y - saved.y0) as usize];
+      if row.is_none() {
+        let offset = y as usize * self.stride + start;
+        *row = Some(self.data[offset..offset + span].to_vec());
+      }
+    }
+  }
+
+  /// Pixel bytes of one row, without padding
+  #[inline]
+  pub fn row(&self, y: u32) -> &[u8] {
+    let start = y as usize * self.stride;
+    &self.data[start..start + self.width as usize * 4]
+  }
+
+  /// Pixel bytes of one row, without padding
+  #[inline]
+  pub fn row_mut(&mut self, y: u32) -> &mut [u8] {
+    self.before_write(y, y + 1);
+    let start = y as usize * self.stride;
+    &mut self.data[start..start + self.width as usize * 4]
+  }
+
+  #[inline]
+  pub fn contains(&self, x: i64, y: i64) -> bool {
+    let (x0, y0, x1, y1) = self.bounds;
+    x >= x0 as i64 && y >= y0 as i64 && x < x1 as i64 && y < y1 as i64
+  }
+
+  /// Write one pixel, ignoring coordinates outside the bounds
+  #[inline]
+  pub fn put<P: Paint + ?Sized>(&mut self, x: i64, y: i64, paint: &P) {
+    if self.contains(x, y) {
+      let (x, y) = (x as u32, y as u32);
+      self.before_write(y, y + 1);
+      let index = y as usize * self.stride + x as usize * 4;
+      self.data[index..index + 4].copy_from_slice(&paint.color_at(x, y));
+    }
+  }
+
+  /// Fill the whole surface
+  pub fn fill<P: Paint + ?Sized>(&mut self, paint: &P) {
+    self.fill_rect(0, 0, self.width as i64, self.height as i64, paint);
+  }
+
+  /// Bytes from the start of row `y0` to the last pixel of row `y1 - 1`
+  fn rows_mut(&mut self, y0: u32, y1: u32) -> &mut [u8] {
+    if y1 <= y0 {
+      return &mut [];
+    }
+    self.before_write(y0, y1);
+    let start = y0 as usize * self.stride;
+    let end = (y1 as usize - 1) * self.stride + self.width as usize * 4;
+    &mut self.data[start..end]
+  }
+
+  /// Clamp a span `start..start + len` to `min..max`
+  #[inline]
+  fn clamp_span(start: i64, len: i64, min: u32, max: u32) -> (i64, i64) {
+    let (min, max) = (min as i64, max as i64);
+    (
+      start.clamp(min, max),
+      start.saturating_add(len.max(0)).clamp(min, max),
+    )
+  }
+
+  /// Clamp a rectangle to the bounds, returning `(x, y, width, height)` or
+  /// `None` when nothing is left
+  pub fn clip(&self, x: i64, y: i64, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let (bx0, by0, bx1, by1) = self.bounds;
+    let (x0, x1) = Self::clamp_span(x, width as i64, bx0, bx1);
+    let (y0, y1) = Self::clamp_span(y, height as i64, by0, by1);
+    (x1 > x0 && y1 > y0).then(|| (x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32))
+  }
+
+  /// Copy a rectangle that lies inside the surface into packed RGBA
+  pub fn read_rect(&self, x: u32, y: u32, width: u32, height: u32) -> Vec<u8> {
+    let span = width as usize * 4;
+    let mut out = Vec::with_capacity(span * height as usize);
+    for row in y..y + height {
+      let start = row as usize * self.stride + x as usize * 4;
+      out.extend_from_slice(&self.data[start..start + span]);
+    }
+    out
+  }
+
+  /// Fill a rectangle, clamped to the bounds
+  pub fn fill_rect<P: Paint + ?Sized>(
+    &mut self,
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+    paint: &P,
+  ) {
+    let (bx0, by0, bx1, by1) = self.bounds;
+    let (x0, x1) = Self::clamp_span(x, width, bx0, bx1);
+    let (y0, y1) = Self::clamp_span(y, height, by0, by1);
+    let (x0, x1, y0, y1) = (x0 as usize, x1 as usize, y0 as u32, y1 as u32);
+
+    let stride = self.stride;
+    if let Some(rgba) = paint.solid() {
+      for_each_row(self.rows_mut(y0, y1), stride, |_, bytes| {
+        simd::fill(&mut bytes[x0 * 4..x1 * 4], rgba)
+      });
+      return;
+    }
+    for_each_row(self.rows_mut(y0, y1), stride, |row, bytes| {
+      let py = y0 + row as u32;
+      for px in x0..x1 {
+        let index = px * 4;
+        bytes[index..index + 4].copy_from_slice(&paint.color_at(px as u32, py));
+      }
+    });
+  }
+
+  /// Run `work(y, row)` over rows `y0..y1` in parallel; each row holds the
+  /// row's pixels without padding. Callers keep columns within the bounds,
+  /// e.g. via `clip`.
+  pub fn for_rows(&mut self, y0: u32, y1: u32, work: impl Fn(u32, &mut [u8]) + Sync) {
+    let (_, by0, _, by1) = self.bounds;
+    let (y0, y1) = (y0.max(by0), y1.min(by1));
+    let (stride, row_bytes) = (self.stride, self.width as usize * 4);
+    for_each_row(self.rows_mut(y0, y1), stride, |row, bytes| {
+      work(y0 + row as u32, &mut bytes[..row_bytes])
+    });
+  }
+
+  /// Copy an RGBA image with tightly packed rows to `(x, y)`, clipped to the
+  /// bounds. Rows missing from a short `src` are skipped.
+  pub fn blit(&mut self, x: i64, y: i64, src: &[u8], src_width: u32, src_height: u32) {
+    let src_stride = src_width as usize * 4;
+    let src_rows = src
+      .len()
+      .checked_div(src_stride)
+      .map_or(0, |rows| rows.min(src_height as usize)) as i64;
+    let (bx0, by0, bx1, by1) = self.bounds;
+    let (x0, x1) = Self::clamp_span(x, src_width as i64, bx0, bx1);
+    let (y0, y1) = Self::clamp_span(y, src_rows, by0, by1);
+    if x1 <= x0 {
+      return;
+    }
+
+    let stride = self.stride;
+    let span = (x1 - x0) as usize * 4;
+    let dst_start = x0 as usize * 4;
+    let src_start = (x0 - x) as usize * 4;
+    for_each_row(self.rows_mut(y0 as u32, y1 as u32), stride, |row, bytes| {
+      let start = (y0 - y) as usize * src_stride + row * src_stride + src_start;
+      bytes[dst_start..dst_start + span].copy_from_slice(&src[start..start + span]);
+    });
+  }
+
+  /// Bresenham's line algorithm, walked only over the steps that can reach
+  /// the bounds
+  pub fn draw_line<P: Paint + ?Sized>(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, paint: &P) {
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+
+    let steps = dx.max(-dy);
+    let Some((first, last)) = self.line_steps(x0, y0, x1, y1, steps) else {
+      return;
+    };
+
+    // After `k` steps along the major axis the minor axis has moved the
+    // rounded share of `k`, which fixes the error term as well
+    let (major, minor) = (steps as i128, dx.min(-dy) as i128);
+    let k = first as i128;
+    let moved = if major == 0 {
+      0
+    } else {
+      (2 * k * minor + major) / (2 * major)
+    };
+    let (kx, ky) = if dx >= -dy { (k, moved) } else { (moved, k) };
+    let mut x = x0 + sx * kx as i64;
+    let mut y = y0 + sy * ky as i64;
+    let mut error = (dx + dy) as i128 + kx * dy as i128 + ky * dx as i128;
+    let (dx, dy) = (dx as i128, dy as i128);
+
+    for step in first..=last {
+      self.put(x, y, paint);
+
+      if step == last {
+        break;
+      }
+
+      let e2 = 2 * error;
+      if e2 >= dy {
+        error += dy;
+        x += sx;
+      }
+      if e2 <= dx {
+        error += dx;
+        y += sy;
+      }
+    }
+  }
+
+  /// Liang–Barsky clip of a line to the bounds grown by one pixel, as the
+  /// range of major-axis steps out of `steps` that can land inside them
+  fn line_steps(&self, x0: i64, y0: i64, x1: i64, y1: i64, steps: i64) -> Option<(i64, i64)> {
+    let (bx0, by0, bx1, by1) = self.bounds;
+    if bx1 <= bx0 || by1 <= by0 {
+      return None;
+    }
+    let (fx, fy) = (x0 as f64, y0 as f64);
+    let (dx, dy) = (x1 as f64 - fx, y1 as f64 - fy);
+
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (p, q) in [
+      (-dx, fx - (bx0 as f64 - 1.0)),
+      (dx, bx1 as f64 - fx),
+      (-dy, fy - (by0 as f64 - 1.0)),
+      (dy, by1 as f64 - fy),
+    ] {
+      if p == 0.0 {
+        if q < 0.0 {
+          return None;
+        }
+      } else if p < 0.0 {
+        t0 = t0.max(q / p);
+      } else {
+        t1 = t1.min(q / p);
+      }
+    }
+    if t0 > t1 {
+      return None;
+    }
+
+    // One step of slack either way covers rounding in `t`
+    let first = ((t0 * steps as f64).floor() as i64 - 1).max(0);
+    let last = ((t1 * steps as f64).ceil() as i64 + 1).min(steps);
+    Some((first, last))
+  }
+
+  /// Bresenham's circle algorithm (outline). Only the steps whose points can
+  /// land inside the bounds are walked.
+  pub fn draw_circle<P: Paint + ?Sized>(&mut self, cx: i64, cy: i64, radius: i64, paint: &P) {
+    let (bx0, by0, bx1, by1) = self.bounds;
+    if radius < 0 || bx1 <= bx0 || by1 <= by0 {
+      return;
+    }
+    let (bx0, by0, bx1, by1) = (bx0 as i64, by0 as i64, bx1 as i64 - 1, by1 as i64 - 1);
+
+    // Each octant moves one coordinate by `±x`, so the steps it can draw on
+    // form one range of `x`
+    let window = |center: i64, sign: i64, lo: i64, hi: i64| {
+      let (a, b) = ((lo - center) * sign, (hi - center) * sign);
+      (a.min(b).max(0), a.max(b))
+    };
+    let mut ranges = Vec::with_capacity(4);
+    for sign in [1, -1] {
+      ranges.push(window(cx, sign, bx0, bx1));
+      ranges.push(window(cy, sign, by0, by1));
+    }
+    ranges.retain(|&(lo, hi)| lo <= hi);
+    ranges.sort_unstable();
+
+    let mut done = -1;
+    for (lo, hi) in ranges {
+      let lo = lo.max(done + 1);
+      if lo > hi {
+        continue;
+      }
+      done = hi;
+
+      // The closed form is exact away from the diagonal, so jump a few steps
+      // early and let the recurrence take over from there
+      let mut x = (lo - 4).max(0);
+      let mut y = Self::circle_y(radius, x);
+      let mut d = Self::circle_d(radius, x, y);
+
+      while y >= x && x <= hi {
+        if x >= lo {
+          // Draw 8 symmetric points
+          let points = [
+            (cx + x, cy + y),
+            (cx - x, cy + y),
+            (cx + x, cy - y),
+            (cx - x, cy - y),
+            (cx + y, cy + x),
+            (cx - y, cy + x),
+            (cx + y, cy - x),
+            (cx - y, cy - x),
+          ];
+
+          for (px, py) in points {
+            self.put(px, py, paint);
+          }
+        }
+
+        x += 1;
+        if d > 0 {
+          y -= 1;
+          d += 4 * (x - y) as i128 + 10;
+        } else {
+          d += 4 * x as i128 + 6;
+        }
+      }
+    }
+  }
+
+  /// The circle algorithm's decision variable at step `(x, y)`
+  fn circle_d(radius: i64, x: i64, y: i64) -> i128 {
+    let (r, x, y) = (radius as i128, x as i128, y as i128);
+    2 * x * x + 8 * x + 2 * y * y - 6 * y + 3 + 4 * r - 2 * r * r
+  }
+
+  /// The circle algorithm's `y` on reaching step `x`: the largest `y` that
+  /// kept the decision variable from going positive on the step before
+  fn circle_y(radius: i64, x: i64) -> i64 {
+    if x == 0 {
+      return radius;
+    }
+    let fits = |y: i64| Self::circle_d(radius, x - 1, y) <= 0;
+    let k = Self::circle_d(radius, x - 1, 0);
+    let mut y = ((3.0 + (9.0 - 2.0 * k as f64).max(0.0).sqrt()) / 2.0) as i64;
+    while y > 0 && !fits(y) {
+      y -= 1;
+    }
+    while fits(y + 1) {
+      y += 1;
+    }
+    y.min(radius)
+  }
+
+  /// Filled circle, drawn as horizontal spans over the rows in the bounds
+  pub fn fill_circle<P: Paint + ?Sized>(&mut self, cx: i64, cy: i64, radius: i64, paint: &P) {
+    let (_, by0, _, by1) = self.bounds;
+    let r_sq = radius as i128 * radius as i128;
+    for dy in (by0 as i64 - cy).max(-radius)..(by1 as i64 - cy).min(radius + 1) {
+      let half = ((r_sq - dy as i128 * dy as i128) as f64).sqrt() as i64;
+      self.fill_rect(cx - half, cy + dy, half * 2 + 1, 1, paint);
+    }
+  }
+
+  /// Source-over blend a paint onto the pixels `x0..x1` of row `y`
+  pub fn blend_span<P: Paint + ?Sized>(
+    &mut self,
+    y: u32,
+    x0: u32,
+    x1: u32,
+    paint: &P,
+    mode: AlphaMode,
+  ) {
+    let (bx0, by0, bx1, by1) = self.bounds;
+    if y < by0 || y >= by1 {
+      return;
+    }
+    let (x0, x1) = (x0.max(bx0), x1.min(bx1));
+    let row = self.row_mut(y);
+    for x in x0..x1 {
+      let index = x as usize * 4;
+      let dst = [row[index], row[index + 1], row[index + 2], row[index + 3]];
+      row[index..index + 4].copy_from_slice(&blend_pixel(paint.color_at(x, y), dst, mode));
+    }
+  }
+}
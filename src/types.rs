@@ -36,6 +36,15 @@ pub enum OverlayEvent {
   Restored,
   MouseEnter,
   MouseLeave,
+  FadeInCompleted,
+  FadeOutCompleted,
+  /// Presenting failed, or the GPU reported a validation error; failures are
+  /// retried with backoff, see `getRenderStatus()`
+  RenderError,
+  /// The GPU device was lost and is being created again
+  DeviceLost,
+  /// A fade was cancelled before completing, e.g. by `setOpacity` or another fade
+  FadeCancelled,
 }
 
 #[napi(object)]
@@ -52,6 +61,16 @@ pub struct WindowSize {
   pub height: u32,
 }
 
+/// A rectangle in pixels
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+  pub x: i32,
+  pub y: i32,
+  pub width: u32,
+  pub height: u32,
+}
+
 #[napi(object)]
 #[derive(Debug, Clone, PartialEq)]
 pub struct LineParams {
@@ -69,6 +88,22 @@ pub struct DecodedImage {
   pub data: Buffer,
   pub width: u32,
   pub height: u32,
+  /// Layout of `data` (default RGBA8)
+  pub format: Option<crate::format::PixelFormat>,
+  /// Bytes per row of `data`; tightly packed when omitted
+  pub stride: Option<u32>,
+}
+
+impl std::fmt::Debug for DecodedImage {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("DecodedImage")
+      .field("data", &format_args!("{} bytes", self.data.len()))
+      .field("width", &self.width)
+      .field("height", &self.height)
+      .field("format", &self.format)
+      .field("stride", &self.stride)
+      .finish()
+  }
 }
 
 impl Clone for DecodedImage {
@@ -77,10 +112,47 @@ impl Clone for DecodedImage {
       data: Buffer::from(self.data.as_ref().to_vec()),
       width: self.width,
       height: self.height,
+      format: self.format,
+      stride: self.stride,
     }
   }
 }
 
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowTheme {
+  Light,
+  Dark,
+}
+
+impl From<WindowTheme> for winit::window::Theme {
+  fn from(theme: WindowTheme) -> Self {
+    match theme {
+      WindowTheme::Light => winit::window::Theme::Light,
+      WindowTheme::Dark => winit::window::Theme::Dark,
+    }
+  }
+}
+
+/// Title bar buttons; omitted buttons stay enabled
+#[napi(object)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WindowButtons {
+  pub close: Option<bool>,
+  pub minimize: Option<bool>,
+  pub maximize: Option<bool>,
+}
+
+impl From<WindowButtons> for winit::window::WindowButtons {
+  fn from(buttons: WindowButtons) -> Self {
+    let mut flags = winit::window::WindowButtons::empty();
+    flags.set(Self::CLOSE, buttons.close.unwrap_or(true));
+    flags.set(Self::MINIMIZE, buttons.minimize.unwrap_or(true));
+    flags.set(Self::MAXIMIZE, buttons.maximize.unwrap_or(true));
+    flags
+  }
+}
+
 #[napi(object)]
 #[derive(Debug, Clone)]
 pub struct WindowConfig {
@@ -97,21 +169,63 @@ pub struct WindowConfig {
   pub minimized: Option<bool>,
   pub maximized: Option<bool>,
   pub render_when_occluded: Option<bool>,
+  /// Keep presenting while the system runs a modal move/resize loop (Windows),
+  /// so content follows the window as it is dragged (default true)
   pub handle_event_loop_modal: Option<bool>,
+  /// Alpha convention of frame data supplied to this window (default straight)
+  pub alpha_mode: Option<crate::color::AlphaMode>,
+  /// Compositing backend (default auto)
+  pub compositor: Option<crate::compositor::CompositorBackend>,
+  /// How the frame maps onto the window when their sizes differ (default
+  /// match window)
+  pub scale_mode: Option<crate::scale::ScaleMode>,
+  /// Frame contents after a window resize (default preserve top-left)
+  pub resize_policy: Option<crate::scale::ResizePolicy>,
+  /// Round the window corners by this many pixels (default 0)
+  pub corner_radius: Option<f64>,
+  /// Smallest and largest inner size the user can resize to
+  pub min_size: Option<WindowSize>,
+  pub max_size: Option<WindowSize>,
+  /// Show the window once created (default true)
+  pub visible: Option<bool>,
+  /// Focus the window once created (default true)
+  pub active: Option<bool>,
+  pub icon: Option<DecodedImage>,
+  /// Light or dark window theme; the system theme when omitted
+  pub theme: Option<WindowTheme>,
+  /// Keep the window contents out of screenshots and screen capture
+  pub content_protected: Option<bool>,
+  /// Blur what is behind the transparent parts of the window, where supported
+  pub blur: Option<bool>,
+  /// Native handle of the parent window: an HWND on Windows, an X11 window id
+  /// on Linux
+  pub parent_window: Option<i64>,
+  /// Resize in steps of this size
+  pub resize_increments: Option<WindowSize>,
+  pub enabled_buttons: Option<WindowButtons>,
 }
 
 // Constructor functions
-#[napi]
+#[napi(catch_unwind)]
 pub fn create_position(x: i32, y: i32) -> WindowPosition {
   WindowPosition { x, y }
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn create_size(width: u32, height: u32) -> WindowSize {
   WindowSize { width, height }
 }
 
-#[napi]
+/// Check a window config without creating a window; throws on invalid values
+/// and impossible combinations such as `minimized` with `maximized`
+#[napi(catch_unwind)]
+pub fn validate_window_config(config: WindowConfig) -> Result<()> {
+  crate::window::window_attributes(&config)?;
+  crate::shape::validate_corner_radius(config.corner_radius.unwrap_or(0.0))?;
+  Ok(())
+}
+
+#[napi(catch_unwind)]
 pub fn create_window_config() -> WindowConfig {
   WindowConfig {
     width: Some(800),
@@ -128,5 +242,21 @@ pub fn create_window_config() -> WindowConfig {
     maximized: Some(false),
     render_when_occluded: Some(true),
     handle_event_loop_modal: Some(true),
+    alpha_mode: Some(crate::color::AlphaMode::Straight),
+    compositor: Some(crate::compositor::CompositorBackend::Auto),
+    scale_mode: Some(crate::scale::ScaleMode::MatchWindow),
+    resize_policy: Some(crate::scale::ResizePolicy::PreserveTopLeft),
+    corner_radius: Some(0.0),
+    min_size: None,
+    max_size: None,
+    visible: Some(true),
+    active: Some(true),
+    icon: None,
+    theme: None,
+    content_protected: Some(false),
+    blur: Some(false),
+    parent_window: None,
+    resize_increments: None,
+    enabled_buttons: None,
   }
 }
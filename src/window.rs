@@ -1,14 +1,38 @@
-use crate::color::Color;
-use crate::types::{OverlayEvent, WindowConfig, WindowLevel, WindowPosition, WindowSize};
-use napi::bindgen_prelude::Buffer;
+use crate::animation::{
+  clamp_opacity, Animator, Easing, FadeDirection, Tween, TweenOptions, TweenProperty, TweenTarget,
+  TweenValue, FRAME_INTERVAL,
+};
+use crate::clip::{ClipShape, ClipStack};
+use crate::color::{AlphaMode, Color};
+use crate::command::Op;
+use crate::compositor::CompositorBackend;
+use crate::device::{RenderRecovery, RenderRecoveryOptions, RenderStatus};
+use crate::element::{Element, ElementOptions};
+use crate::error::{OverlayError, OverlayResult};
+use crate::filter::{Effect, Filtered};
+use crate::format::PixelFormat;
+use crate::gpu::GpuCompositor;
+use crate::gradient::{compile_gradient, Gradient, Premultiplied};
+use crate::icon::ProgressState;
+use crate::platform::{Backend, Feature};
+use crate::scale::{ResizePolicy, ScaleMode};
+use crate::shape::WindowShape;
+use crate::sprite::SpriteDrawOptions;
+use crate::surface::Surface;
+use crate::types::{
+  DecodedImage, OverlayEvent, Rect, WindowConfig, WindowLevel, WindowPosition, WindowSize,
+};
+use crate::yuv::{YuvDrawOptions, YuvFrame};
+use napi::bindgen_prelude::{Buffer, FnArgs};
 use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
 use napi::{Error, Result, Status};
 use pixels::{Pixels, SurfaceTexture};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
 use winit::dpi::{LogicalPosition, LogicalSize};
 use winit::event::WindowEvent;
-use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::window::{Fullscreen, Window, WindowAttributes, WindowId};
 
 #[cfg(target_os = "windows")]
@@ -35,6 +59,51 @@ pub struct WindowState {
   pub occluded: bool,
   pub exclude_from_capture: bool,
   pub pending_resize: bool,
+  /// CPU-side frame, composited onto the surface at present time
+  pub frame: Vec<u8>,
+  /// Whole-window opacity applied when compositing (0..1)
+  pub opacity: f32,
+  pub animator: Animator,
+  /// Retained elements drawn over the frame when compositing
+  pub elements: Vec<Element>,
+  pub next_element_id: u32,
+  /// Alpha convention of the frame contents
+  pub alpha_mode: AlphaMode,
+  /// Clip applied to drawing into the frame
+  pub clip: ClipStack,
+  /// Requested compositing backend
+  pub compositor: CompositorBackend,
+  /// GPU compositor, created on the first GPU present
+  pub gpu: Option<GpuCompositor>,
+  /// Set when the GPU compositor couldn't be created; presents use the CPU
+  pub gpu_failed: bool,
+  /// How the frame maps onto the surface when their sizes differ
+  pub scale_mode: ScaleMode,
+  /// Size of the window surface in physical pixels
+  pub surface_width: u32,
+  pub surface_height: u32,
+  /// What happens to the frame contents when it follows a window resize
+  pub resize_policy: ResizePolicy,
+  /// Called with the new frame size after a window resize
+  pub resize_callback: Option<ThreadsafeFunction<WindowSize>>,
+  /// Presents wait until the resize callback returns or this time passes
+  pub repaint_deadline: Option<Instant>,
+  /// Explicit window region set with `setShape`
+  pub shape: Option<Vec<Rect>>,
+  /// Corner radius applied when there is no explicit shape
+  pub corner_radius: f64,
+  /// Present directly on resize, as the event loop may be inside a modal loop
+  pub handle_event_loop_modal: bool,
+  /// Windowing backend the window was created on
+  pub backend: Backend,
+  /// Unsupported platform features are errors rather than no-ops
+  pub strict: bool,
+  /// Set once the window is closed; it can't be used afterwards
+  pub destroyed: bool,
+  /// The surface is cleared to transparent, also when it is created again
+  pub transparent: bool,
+  /// Retries of failed presents and lost devices
+  pub render_recovery: RenderRecovery,
 }
 
 impl WindowState {
@@ -50,26 +119,386 @@ impl WindowState {
       occluded: false,
       exclude_from_capture: false,
       pending_resize: false,
+      frame: Vec::new(),
+      opacity: 1.0,
+      animator: Animator::default(),
+      elements: Vec::new(),
+      next_element_id: 0,
+      alpha_mode: AlphaMode::Straight,
+      clip: ClipStack::default(),
+      compositor: CompositorBackend::Auto,
+      gpu: None,
+      gpu_failed: false,
+      scale_mode: ScaleMode::MatchWindow,
+      surface_width: 0,
+      surface_height: 0,
+      resize_policy: ResizePolicy::PreserveTopLeft,
+      resize_callback: None,
+      repaint_deadline: None,
+      shape: None,
+      corner_radius: 0.0,
+      handle_event_loop_modal: true,
+      backend: Backend::native(),
+      strict: false,
+      destroyed: false,
+      transparent: true,
+      render_recovery: RenderRecovery::default(),
+    }
+  }
+
+  /// The error for a call that needs the window when there is none
+  pub fn window_error(&self) -> OverlayError {
+    if self.destroyed {
+      OverlayError::WindowDestroyed
+    } else {
+      OverlayError::WindowNotInitialized
+    }
+  }
+
+  /// Whether `feature` works for this window; an error in strict mode if not
+  pub fn require(&self, feature: Feature) -> OverlayResult<bool> {
+    feature.check(self.backend, self.strict)
+  }
+
+  /// Restore what a panic halfway through a change may have broken. The
+  /// frame keeps its contents if its size still matches.
+  pub fn repair(&mut self) {
+    let size = crate::buffer::calculate_buffer_size(self.width, self.height);
+    if self.frame.len() != size {
+      self.frame = vec![0; size];
+    }
+    self.clip.clear();
+    self.pending_resize = false;
+    self.repaint_deadline = None;
+    // Recreated at the next GPU present
+    self.gpu = None;
+  }
+
+  /// Apply the explicit shape, or the rounded corners when there is none, to
+  /// the window. Returns false where shaping isn't supported.
+  pub fn apply_shape(&self) -> bool {
+    let Some(window) = &self.window else {
+      return false;
+    };
+    match &self.shape {
+      Some(region) => crate::shape::apply_region(window, Some(region)),
+      None if self.corner_radius > 0.0 => {
+        let region =
+          crate::shape::rounded_region(self.surface_width, self.surface_height, self.corner_radius);
+        crate::shape::apply_region(window, Some(&region))
+      }
+      None => crate::shape::apply_region(window, None),
+    }
+  }
+
+  /// Follow a new surface size. The `pixels` texture is fitted at the next
+  /// present; in `MatchWindow` mode the frame is resized as well.
+  pub fn resize_surface(&mut self, width: u32, height: u32) {
+    self.surface_width = width;
+    self.surface_height = height;
+    if let Some(pixels) = &mut self.pixels {
+      if let Err(e) = pixels.resize_surface(width, height) {
+        eprintln!("Failed to resize surface: {}", e);
+      }
+    }
+    if self.scale_mode == ScaleMode::MatchWindow {
+      self.resize_frame(width, height);
+    }
+    if self.shape.is_none() && self.corner_radius > 0.0 {
+      self.apply_shape();
+    }
+  }
+
+  /// Resize the frame, carrying its contents over per the resize policy
+  pub fn resize_frame(&mut self, width: u32, height: u32) {
+    if (width, height) == (self.width, self.height) {
+      return;
+    }
+    self.frame = crate::scale::resize_frame(
+      &self.frame,
+      self.width,
+      self.height,
+      width,
+      height,
+      self.resize_policy,
+    );
+    self.width = width;
+    self.height = height;
+  }
+
+  /// Send an event to the registered JS callback, if any
+  pub fn emit(&self, event: OverlayEvent) {
+    if let Some(cb) = &self.event_callback {
+      cb.call(Ok(event), ThreadsafeFunctionCallMode::NonBlocking);
+    }
+  }
+
+  /// Read the current value of an animatable property
+  fn read_property(&self, target: TweenTarget, property: TweenProperty) -> Option<TweenValue> {
+    match target {
+      TweenTarget::Window => {
+        let window = self.window.as_ref()?;
+        let scale = window.scale_factor();
+        let value = match property {
+          TweenProperty::X | TweenProperty::Y => {
+            let pos: LogicalPosition<f64> = window.outer_position().ok()?.to_logical(scale);
+            if property == TweenProperty::X {
+              pos.x
+            } else {
+              pos.y
+            }
+          }
+          TweenProperty::Width | TweenProperty::Height => {
+            let size: LogicalSize<f64> = window.inner_size().to_logical(scale);
+            if property == TweenProperty::Width {
+              size.width
+            } else {
+              size.height
+            }
+          }
+          TweenProperty::Opacity => self.opacity as f64,
+          TweenProperty::Color => return None,
+        };
+        Some(TweenValue::Number(value))
+      }
+      TweenTarget::Element(id) => {
+        let element = self.elements.iter().find(|e| e.id == id)?;
+        Some(match property {
+          TweenProperty::X => TweenValue::Number(element.x),
+          TweenProperty::Y => TweenValue::Number(element.y),
+          TweenProperty::Width => TweenValue::Number(element.width),
+          TweenProperty::Height => TweenValue::Number(element.height),
+          TweenProperty::Opacity => TweenValue::Number(element.opacity as f64),
+          TweenProperty::Color => TweenValue::Color(element.color),
+        })
+      }
+    }
+  }
+
+  /// Write an animated value back to its property
+  fn write_property(&mut self, target: TweenTarget, property: TweenProperty, value: TweenValue) {
+    match (target, value) {
+      (TweenTarget::Window, TweenValue::Number(value)) => {
+        if property == TweenProperty::Opacity {
+          self.opacity = value.clamp(0.0, 1.0) as f32;
+          return;
+        }
+        let Some(window) = &self.window else {
+          return;
+        };
+        let scale = window.scale_factor();
+        match property {
+          TweenProperty::X | TweenProperty::Y => {
+            let Ok(pos) = window.outer_position() else {
+              return;
+            };
+            let mut pos: LogicalPosition<f64> = pos.to_logical(scale);
+            if property == TweenProperty::X {
+              pos.x = value;
+            } else {
+              pos.y = value;
+            }
+            window.set_outer_position(pos);
+          }
+          TweenProperty::Width | TweenProperty::Height => {
+            let mut size: LogicalSize<f64> = window.inner_size().to_logical(scale);
+            if property == TweenProperty::Width {
+              size.width = value.max(1.0);
+            } else {
+              size.height = value.max(1.0);
+            }
+            let _ = window.request_inner_size(size);
+          }
+          _ => {}
+        }
+      }
+      (TweenTarget::Element(id), value) => {
+        let Some(element) = self.elements.iter_mut().find(|e| e.id == id) else {
+          return;
+        };
+        match (property, value) {
+          (TweenProperty::X, TweenValue::Number(v)) => element.x = v,
+          (TweenProperty::Y, TweenValue::Number(v)) => element.y = v,
+          (TweenProperty::Width, TweenValue::Number(v)) => element.width = v.max(0.0),
+          (TweenProperty::Height, TweenValue::Number(v)) => element.height = v.max(0.0),
+          (TweenProperty::Opacity, TweenValue::Number(v)) => {
+            element.opacity = v.clamp(0.0, 1.0) as f32
+          }
+          (TweenProperty::Color, TweenValue::Color(c)) => element.color = c,
+          _ => {}
+        }
+      }
+      _ => {}
+    }
+  }
+
+  /// Advance every running tween, firing completion events and callbacks
+  pub fn tick_animations(&mut self, now: Instant) {
+    let mut tweens = std::mem::take(&mut self.animator.tweens);
+    let mut completed = Vec::new();
+
+    for tween in tweens.iter_mut() {
+      let Some((eased, finished)) = tween.progress(now) else {
+        continue;
+      };
+
+      let from = match tween.from {
+        Some(from) => from,
+        None => match self.read_property(tween.target, tween.property) {
+          Some(from) => *tween.from.insert(from),
+          None => {
+            // Target disappeared (e.g. element removed); cancel the tween
+            completed.push((tween.id, false));
+            continue;
+          }
+        },
+      };
+
+      let value = if finished {
+        tween.to
+      } else {
+        from.interpolate(&tween.to, eased)
+      };
+      self.write_property(tween.target, tween.property, value);
+
+      if finished {
+        completed.push((tween.id, true));
+      }
+    }
+
+    self.animator.tweens = tweens;
+
+    for (id, notify) in completed {
+      if !notify {
+        let cancelled = self.animator.cancel(id);
+        self.notify_cancelled(cancelled);
+        continue;
+      }
+      let Some(index) = self.animator.tweens.iter().position(|t| t.id == id) else {
+        continue;
+      };
+      let tween = self.animator.tweens.remove(index);
+      self.animator.release_dependents(id, now);
+      if let Some(ev) = tween.completion_event {
+        self.emit(ev);
+      }
+      if let Some(cb) = &tween.callback {
+        cb.call(
+          Ok((id, false).into()),
+          ThreadsafeFunctionCallMode::NonBlocking,
+        );
+      }
+    }
+  }
+
+  /// Tell JS about tweens that were cancelled before completing
+  pub fn notify_cancelled(&self, tweens: Vec<Tween>) {
+    for tween in tweens {
+      if tween.completion_event.is_some() {
+        self.emit(OverlayEvent::FadeCancelled);
+      }
+      if let Some(cb) = &tween.callback {
+        cb.call(
+          Ok((tween.id, true).into()),
+          ThreadsafeFunctionCallMode::NonBlocking,
+        );
+      }
     }
   }
 }
 
+/// Longest a present waits for the resize callback to repaint
+const RESIZE_REPAINT_TIMEOUT: Duration = Duration::from_millis(100);
+
 pub struct OverlayApplication<'a> {
   pub windows: &'a [Arc<Mutex<WindowState>>],
   pub exit_requested: bool,
 }
 
+/// Run an event handler without letting a panic unwind into the windowing
+/// system, which would abort the process. The panic hook still reports it,
+/// and locks it poisoned are repaired when next taken.
+fn contain(handler: impl FnOnce()) {
+  let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(handler));
+}
+
 impl<'a> ApplicationHandler for OverlayApplication<'a> {
   fn resumed(&mut self, _event_loop: &ActiveEventLoop) {}
 
+  fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+    contain(|| self.tick(event_loop));
+  }
+
   fn window_event(
     &mut self,
     event_loop: &ActiveEventLoop,
     window_id: WindowId,
     event: WindowEvent,
+  ) {
+    contain(|| self.handle_window_event(event_loop, window_id, event));
+  }
+}
+
+impl<'a> OverlayApplication<'a> {
+  /// Advance animations and schedule the next wakeup
+  fn tick(&mut self, event_loop: &ActiveEventLoop) {
+    let now = Instant::now();
+    let mut animating = false;
+    let mut wake_at: Option<Instant> = None;
+
+    for state_arc in self.windows {
+      let mut state = recover(state_arc);
+      match state.repaint_deadline {
+        Some(deadline) if deadline <= now => {
+          // The resize callback took too long; present what there is
+          state.repaint_deadline = None;
+          if let Some(window) = &state.window {
+            window.request_redraw();
+          }
+        }
+        Some(deadline) => wake_at = Some(wake_at.map_or(deadline, |at| at.min(deadline))),
+        None => {}
+      }
+      match state.render_recovery.retry_at {
+        Some(retry) if retry <= now => {
+          // Backoff after a failed present is over
+          state.render_recovery.retry_at = None;
+          if let Some(window) = &state.window {
+            window.request_redraw();
+          }
+        }
+        Some(retry) => wake_at = Some(wake_at.map_or(retry, |at| at.min(retry))),
+        None => {}
+      }
+      if !state.animator.is_active() {
+        continue;
+      }
+
+      animating = true;
+      state.tick_animations(now);
+      if let Some(window) = &state.window {
+        window.request_redraw();
+      }
+    }
+
+    // Keep ticking at frame rate while something is animating
+    if animating {
+      wake_at = Some(wake_at.map_or(now + FRAME_INTERVAL, |at| at.min(now + FRAME_INTERVAL)));
+    }
+    match wake_at {
+      Some(at) => event_loop.set_control_flow(ControlFlow::WaitUntil(at)),
+      None => event_loop.set_control_flow(ControlFlow::Wait),
+    }
+  }
+
+  fn handle_window_event(
+    &mut self,
+    event_loop: &ActiveEventLoop,
+    window_id: WindowId,
+    event: WindowEvent,
   ) {
     let target_window = self.windows.iter().find(|w| {
-      let guard = w.lock().unwrap();
+      let guard = recover(w);
       guard
         .window
         .as_ref()
@@ -88,43 +517,51 @@ impl<'a> ApplicationHandler for OverlayApplication<'a> {
         }
         WindowEvent::Resized(size) => {
           overlay_event = Some(OverlayEvent::Resized);
-          let mut state = state_arc.lock().unwrap();
+          let mut state = recover(state_arc);
 
           // Skip invalid resize events
           if size.width == 0 || size.height == 0 {
             return;
           }
 
-          let old_width = state.width;
-          let old_height = state.height;
-
           // Skip if size hasn't actually changed
-          if old_width == size.width && old_height == size.height {
+          if state.surface_width == size.width && state.surface_height == size.height {
             return;
           }
 
-          state.width = size.width;
-          state.height = size.height;
+          // A display change often comes with a resize, so try again now
+          state.render_recovery.resume();
           state.pending_resize = true;
+          state.resize_surface(size.width, size.height);
+          state.pending_resize = false;
 
-          if let Some(pixels) = &mut state.pixels {
-            // IMPORTANT: Resize surface FIRST, then buffer
-            // This order is critical to avoid graphical artifacts
-            if let Err(e) = pixels.resize_surface(size.width, size.height) {
-              eprintln!("Failed to resize surface: {}", e);
-            }
-
-            if let Err(e) = pixels.resize_buffer(size.width, size.height) {
-              eprintln!("Failed to resize buffer: {}", e);
-            }
-
-            // Clear the buffer to avoid artifacts from old content
-            let frame = pixels.frame_mut();
-            // Fill with transparent black to avoid garbage
-            frame.fill(0);
+          // Hold presents until JS has repainted for the new size
+          let state = &mut *state;
+          if let Some(callback) = &state.resize_callback {
+            state.repaint_deadline = Some(Instant::now() + RESIZE_REPAINT_TIMEOUT);
+            let frame_size = WindowSize {
+              width: state.width,
+              height: state.height,
+            };
+            let state_arc = state_arc.clone();
+            callback.call_with_return_value(
+              Ok(frame_size),
+              ThreadsafeFunctionCallMode::NonBlocking,
+              move |_, _| {
+                let mut state = recover(&state_arc);
+                state.repaint_deadline = None;
+                if let Some(window) = &state.window {
+                  window.request_redraw();
+                }
+                Ok(())
+              },
+            );
           }
 
-          state.pending_resize = false;
+          // Redraws may not be delivered until a modal move/resize loop ends
+          if state.handle_event_loop_modal && state.repaint_deadline.is_none() {
+            let _ = crate::device::present_recovering(state);
+          }
 
           // Request redraw after resize
           if let Some(window) = &state.window {
@@ -153,71 +590,64 @@ impl<'a> ApplicationHandler for OverlayApplication<'a> {
           } else {
             OverlayEvent::Restored
           });
-          let mut state = state_arc.lock().unwrap();
+          let mut state = recover(state_arc);
           state.occluded = occluded;
         }
         WindowEvent::RedrawRequested => {
-          let mut state = state_arc.lock().unwrap();
-          if let Some(pixels) = &mut state.pixels {
-            if pixels.render().is_err() {
-              // eprintln!("Failed to render");
-            }
+          let mut state = recover(state_arc);
+          #[cfg(feature = "test-hooks")]
+          crate::recovery::check(crate::recovery::PanicSite::EventLoop);
+          if state.repaint_deadline.is_some() {
+            // Presented once the resize callback returns or times out
+            return;
           }
+          // Failures are retried and reported through events
+          let _ = crate::device::present_recovering(&mut state);
         }
         _ => {}
       }
 
       if let Some(ev) = overlay_event {
-        let state = state_arc.lock().unwrap();
-        if let Some(cb) = &state.event_callback {
-          cb.call(Ok(ev), ThreadsafeFunctionCallMode::NonBlocking);
-        }
+        let state = recover(state_arc);
+        state.emit(ev);
       }
     }
   }
 }
 
+/// Lock a window's state where a poisoned lock can't be reported, like in
+/// the event loop, repairing it after a panic
+pub fn recover(state: &Mutex<WindowState>) -> MutexGuard<'_, WindowState> {
+  crate::recovery::lock_or_repair(state, WindowState::repair)
+}
+
 /// Create overlay window from Loop
+#[cfg(target_os = "windows")]
 pub fn create_overlay_window_from_loop(
   event_loop: &mut EventLoop<()>,
   config: &WindowConfig,
 ) -> Result<(Arc<Window>, Pixels<'static>)> {
-  let mut window_pixels = None;
-
-  {
-    struct Loader<'a> {
-      config: &'a WindowConfig,
-      result: &'a mut Option<Result<(Arc<Window>, Pixels<'static>)>>,
-    }
+  struct Loader<'a> {
+    config: &'a WindowConfig,
+    result: &'a mut Option<Result<(Arc<Window>, Pixels<'static>)>>,
+  }
 
-    impl<'a> ApplicationHandler for Loader<'a> {
-      fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        if self.result.is_none() {
-          *self.result = Some(create_overlay_window(event_loop, self.config));
-        }
+  impl<'a> ApplicationHandler for Loader<'a> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+      if self.result.is_none() {
+        *self.result = Some(create_overlay_window(event_loop, self.config));
       }
-      fn window_event(&mut self, _el: &ActiveEventLoop, _id: WindowId, _ev: WindowEvent) {}
-    }
-
-    let mut loader = Loader {
-      config,
-      result: &mut window_pixels,
-    };
-
-    #[cfg(target_os = "windows")]
-    {
-      let _ = event_loop.pump_app_events(None, &mut loader);
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    {
-      return Err(Error::new(
-        Status::GenericFailure,
-        "create_window_from_loop only supported on Windows in this build",
-      ));
     }
+    fn window_event(&mut self, _el: &ActiveEventLoop, _id: WindowId, _ev: WindowEvent) {}
   }
 
+  let mut window_pixels = None;
+  let mut loader = Loader {
+    config,
+    result: &mut window_pixels,
+  };
+  let _ = event_loop.pump_app_events(None, &mut loader);
+
   if let Some(res) = window_pixels {
     res
   } else {
@@ -228,57 +658,167 @@ pub fn create_overlay_window_from_loop(
   }
 }
 
-/// Create overlay window with optimized configuration
-pub fn create_overlay_window(
-  event_loop: &ActiveEventLoop,
-  config: &WindowConfig,
+#[cfg(not(target_os = "windows"))]
+pub fn create_overlay_window_from_loop(
+  _event_loop: &mut EventLoop<()>,
+  _config: &WindowConfig,
 ) -> Result<(Arc<Window>, Pixels<'static>)> {
+  Err(Error::new(
+    Status::GenericFailure,
+    "create_window_from_loop only supported on Windows in this build",
+  ))
+}
+
+/// Check a window config for invalid values and impossible combinations and
+/// translate it to winit attributes
+pub fn window_attributes(config: &WindowConfig) -> Result<WindowAttributes> {
+  let invalid = |message: String| Error::new(Status::InvalidArg, message);
   let width = config.width.unwrap_or(800);
   let height = config.height.unwrap_or(600);
+  let minimized = config.minimized.unwrap_or(false);
+  if minimized && config.maximized.unwrap_or(false) {
+    return Err(invalid(
+      "`minimized` and `maximized` can't both be set".into(),
+    ));
+  }
+  if minimized && config.fullscreen.unwrap_or(false) {
+    return Err(invalid(
+      "`minimized` and `fullscreen` can't both be set".into(),
+    ));
+  }
+  if minimized && !config.visible.unwrap_or(true) {
+    return Err(invalid("`minimized` requires `visible`".into()));
+  }
+  if let (Some(min), Some(max)) = (config.min_size, config.max_size) {
+    if min.width > max.width || min.height > max.height {
+      return Err(invalid(format!(
+        "`minSize` {}x{} is larger than `maxSize` {}x{}",
+        min.width, min.height, max.width, max.height
+      )));
+    }
+  }
+  if let Some(min) = config.min_size {
+    if width < min.width || height < min.height {
+      return Err(invalid(format!(
+        "Size {}x{} is smaller than `minSize` {}x{}",
+        width, height, min.width, min.height
+      )));
+    }
+  }
+  if let Some(max) = config.max_size {
+    if width > max.width || height > max.height {
+      return Err(invalid(format!(
+        "Size {}x{} is larger than `maxSize` {}x{}",
+        width, height, max.width, max.height
+      )));
+    }
+  }
+  if let Some(step) = config.resize_increments {
+    if step.width == 0 || step.height == 0 {
+      return Err(invalid("`resizeIncrements` must be at least 1x1".into()));
+    }
+  }
+  let icon = config
+    .icon
+    .as_ref()
+    .map(|icon| {
+      let rgba = crate::format::to_rgba(
+        &icon.data,
+        icon.width,
+        icon.height,
+        icon.format.unwrap_or_default(),
+        icon.stride,
+      )?;
+      winit::window::Icon::from_rgba(rgba.into_owned(), icon.width, icon.height)
+        .map_err(|e| e.to_string())
+    })
+    .transpose()
+    .map_err(|e| invalid(format!("Invalid `icon`: {}", e)))?;
+  let parent = config.parent_window.map(parent_window_handle).transpose()?;
+
   let title = config
     .title
     .clone()
     .unwrap_or_else(|| "Overlay NAPI".to_string());
-  let transparent = config.transparent.unwrap_or(true);
-  let decorations = config.decorations.unwrap_or(false);
-  let always_on_top = config.always_on_top.unwrap_or(true);
-  let resizable = config.resizable.unwrap_or(true);
-
-  let attributes = WindowAttributes::default()
-    .with_transparent(transparent)
-    .with_decorations(decorations)
-    .with_title(&title)
-    .with_resizable(resizable)
-    .with_inner_size(LogicalSize::new(width, height));
-
-  let attributes = match always_on_top {
-    true => attributes.with_window_level(winit::window::WindowLevel::AlwaysOnTop),
-    false => attributes.with_window_level(winit::window::WindowLevel::Normal),
+  let level = match config.always_on_top.unwrap_or(true) {
+    true => winit::window::WindowLevel::AlwaysOnTop,
+    false => winit::window::WindowLevel::Normal,
   };
+  let mut attributes = WindowAttributes::default()
+    .with_transparent(config.transparent.unwrap_or(true))
+    .with_decorations(config.decorations.unwrap_or(false))
+    .with_title(&title)
+    .with_resizable(config.resizable.unwrap_or(true))
+    .with_inner_size(LogicalSize::new(width, height))
+    .with_window_level(level)
+    .with_maximized(config.maximized.unwrap_or(false))
+    .with_visible(config.visible.unwrap_or(true))
+    .with_active(config.active.unwrap_or(true))
+    .with_window_icon(icon)
+    .with_theme(config.theme.map(Into::into))
+    .with_content_protected(config.content_protected.unwrap_or(false))
+    .with_blur(config.blur.unwrap_or(false))
+    .with_enabled_buttons(config.enabled_buttons.unwrap_or_default().into());
+
+  if let (Some(x), Some(y)) = (config.x, config.y) {
+    attributes = attributes.with_position(LogicalPosition::new(x, y));
+  }
+  if config.fullscreen.unwrap_or(false) {
+    attributes = attributes.with_fullscreen(Some(Fullscreen::Borderless(None)));
+  }
+  if let Some(min) = config.min_size {
+    attributes = attributes.with_min_inner_size(LogicalSize::new(min.width, min.height));
+  }
+  if let Some(max) = config.max_size {
+    attributes = attributes.with_max_inner_size(LogicalSize::new(max.width, max.height));
+  }
+  if let Some(step) = config.resize_increments {
+    attributes = attributes.with_resize_increments(LogicalSize::new(step.width, step.height));
+  }
+  if let Some(parent) = parent {
+    // SAFETY: the handle is only used while creating the window; the caller
+    // keeps the parent window alive
+    attributes = unsafe { attributes.with_parent_window(Some(parent)) };
+  }
+  Ok(attributes)
+}
 
-  // Set position if specified
-  let attributes = if let (Some(x), Some(y)) = (config.x, config.y) {
-    attributes.with_position(LogicalPosition::new(x, y))
-  } else {
-    attributes
+/// Interpret a native handle passed from JS as a parent window
+fn parent_window_handle(handle: i64) -> Result<raw_window_handle::RawWindowHandle> {
+  let invalid = || {
+    Error::new(
+      Status::InvalidArg,
+      "`parentWindow` must be a non-zero native window handle",
+    )
   };
+  if handle <= 0 {
+    return Err(invalid());
+  }
+  #[cfg(target_os = "windows")]
+  {
+    let hwnd = std::num::NonZeroIsize::new(handle as isize).ok_or_else(invalid)?;
+    Ok(raw_window_handle::Win32WindowHandle::new(hwnd).into())
+  }
+  #[cfg(target_os = "macos")]
+  {
+    let view =
+      std::ptr::NonNull::new(handle as usize as *mut std::ffi::c_void).ok_or_else(invalid)?;
+    Ok(raw_window_handle::AppKitWindowHandle::new(view).into())
+  }
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  {
+    Ok(raw_window_handle::XlibWindowHandle::new(handle as std::ffi::c_ulong).into())
+  }
+}
 
-  // Set initial state
-  let attributes = if config.fullscreen.unwrap_or(false) {
-    attributes.with_fullscreen(Some(Fullscreen::Borderless(None)))
-  } else {
-    attributes
-  };
-  let attributes = if config.maximized.unwrap_or(false) {
-    attributes.with_maximized(true)
-  } else {
-    attributes
-  };
-  let attributes = if config.minimized.unwrap_or(false) {
-    attributes.with_visible(false)
-  } else {
-    attributes
-  };
+/// Create overlay window with optimized configuration
+#[cfg(target_os = "windows")]
+pub fn create_overlay_window(
+  event_loop: &ActiveEventLoop,
+  config: &WindowConfig,
+) -> Result<(Arc<Window>, Pixels<'static>)> {
+  let transparent = config.transparent.unwrap_or(true);
+  let attributes = window_attributes(config)?;
 
   let window = Arc::new(event_loop.create_window(attributes).map_err(|e| {
     Error::new(
@@ -286,19 +826,29 @@ pub fn create_overlay_window(
       format!("Failed to create window: {}", e),
     )
   })?);
+  // winit has no minimized attribute
+  if config.minimized.unwrap_or(false) {
+    window.set_minimized(true);
+  }
 
-  // Get window size
-  let window_size = window.inner_size();
+  let pixels = create_pixels(&window, transparent).map_err(|e| {
+    Error::new(
+      Status::GenericFailure,
+      format!("Failed to create pixels: {}", e),
+    )
+  })?;
+
+  Ok((window, pixels))
+}
 
-  // Create pixels surface
+/// Create the surface and GPU device for a window, sized to fit it
+pub fn create_pixels(
+  window: &Arc<Window>,
+  transparent: bool,
+) -> std::result::Result<Pixels<'static>, pixels::Error> {
+  let window_size = window.inner_size();
   let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, window.clone());
-  let mut pixels =
-    Pixels::new(window_size.width, window_size.height, surface_texture).map_err(|e| {
-      Error::new(
-        Status::GenericFailure,
-        format!("Failed to create pixels: {}", e),
-      )
-    })?;
+  let mut pixels = Pixels::new(window_size.width, window_size.height, surface_texture)?;
 
   // FIX: Set transparent clear color if requested
   if transparent {
@@ -311,11 +861,10 @@ pub fn create_overlay_window(
   }
 
   // Unsafe cast to 'static because the window is owned by the same state
-  let pixels_static = unsafe { std::mem::transmute::<Pixels<'_>, Pixels<'static>>(pixels) };
-
-  Ok((window, pixels_static))
+  Ok(unsafe { std::mem::transmute::<Pixels<'_>, Pixels<'static>>(pixels) })
 }
 
+#[cfg(target_os = "windows")]
 pub fn poll_event_loop(
   event_loop: &mut EventLoop<()>,
   windows: &[Arc<Mutex<WindowState>>],
@@ -324,15 +873,19 @@ pub fn poll_event_loop(
     windows,
     exit_requested: false,
   };
-
-  #[cfg(target_os = "windows")]
-  {
-    let _ = event_loop.pump_app_events(None, &mut app);
-  }
-
+  let _ = event_loop.pump_app_events(None, &mut app);
   app.exit_requested
 }
 
+#[cfg(not(target_os = "windows"))]
+pub fn poll_event_loop(
+  _event_loop: &mut EventLoop<()>,
+  _windows: &[Arc<Mutex<WindowState>>],
+) -> bool {
+  // Pumping the event loop is only wired up on Windows
+  false
+}
+
 pub fn run_event_loop(event_loop: EventLoop<()>, windows: Vec<Arc<Mutex<WindowState>>>) -> ! {
   let mut app = OverlayApplication {
     windows: &windows,
@@ -355,64 +908,78 @@ impl WindowController {
     Self { state }
   }
 
-  pub fn set_event_callback(&self, callback: ThreadsafeFunction<OverlayEvent>) {
-    let mut state = self.state.lock().unwrap();
+  fn lock(&self) -> OverlayResult<MutexGuard<'_, WindowState>> {
+    crate::recovery::lock(&self.state, WindowState::repair)
+  }
+
+  pub fn set_event_callback(
+    &self,
+    callback: ThreadsafeFunction<OverlayEvent>,
+  ) -> OverlayResult<()> {
+    let mut state = self.lock()?;
     state.event_callback = Some(callback);
+    Ok(())
+  }
+
+  pub fn set_resize_callback(&self, callback: ThreadsafeFunction<WindowSize>) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    state.resize_callback = Some(callback);
+    Ok(())
   }
 
-  pub fn show(&self) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn show(&self) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       window.set_visible(true);
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn hide(&self) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn hide(&self) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       window.set_visible(false);
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn minimize(&self) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn minimize(&self) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       window.set_minimized(true);
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn maximize(&self) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn maximize(&self) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       window.set_maximized(true);
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn restore(&self) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn restore(&self) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       window.set_minimized(false);
       window.set_maximized(false);
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn set_fullscreen(&self, fullscreen: bool) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn set_fullscreen(&self, fullscreen: bool) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       if fullscreen {
         window.set_fullscreen(Some(Fullscreen::Borderless(None)));
@@ -421,62 +988,57 @@ impl WindowController {
       }
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn is_fullscreen(&self) -> Result<bool> {
-    let state = self.state.lock().unwrap();
+  pub fn is_fullscreen(&self) -> OverlayResult<bool> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       Ok(window.fullscreen().is_some())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn set_position(&self, x: i32, y: i32) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn set_position(&self, x: i32, y: i32) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       window.set_outer_position(LogicalPosition::new(x, y));
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn get_position(&self) -> Result<WindowPosition> {
-    let state = self.state.lock().unwrap();
+  pub fn get_position(&self) -> OverlayResult<WindowPosition> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
-      let pos = window.outer_position().map_err(|e| {
-        Error::new(
-          Status::GenericFailure,
-          format!("Failed to get position: {}", e),
-        )
-      })?;
+      let pos = window
+        .outer_position()
+        .map_err(|e| OverlayError::platform(format!("Failed to get position: {}", e)))?;
       Ok(WindowPosition { x: pos.x, y: pos.y })
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn set_size(&self, width: u32, height: u32) -> Result<()> {
-    let mut state = self.state.lock().unwrap();
+  pub fn set_size(&self, width: u32, height: u32) -> OverlayResult<()> {
+    let mut state = self.lock()?;
     if let Some(window) = &state.window {
-      let _ = window.request_inner_size(LogicalSize::new(width, height));
-      state.width = width;
-      state.height = height;
-      if let Some(pixels) = &mut state.pixels {
-        let _ = pixels.resize_surface(width, height);
-        let _ = pixels.resize_buffer(width, height);
+      // The surface follows in physical pixels: now if the platform applies
+      // the size at once, otherwise from the `Resized` event
+      if let Some(size) = window.request_inner_size(LogicalSize::new(width, height)) {
+        state.resize_surface(size.width, size.height);
       }
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn get_size(&self) -> Result<WindowSize> {
-    let state = self.state.lock().unwrap();
+  pub fn get_size(&self) -> OverlayResult<WindowSize> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       let size = window.inner_size();
       Ok(WindowSize {
@@ -484,23 +1046,26 @@ impl WindowController {
         height: size.height,
       })
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn set_title(&self, title: &str) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn set_title(&self, title: &str) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       window.set_title(title);
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn set_window_level(&self, level: WindowLevel) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn set_window_level(&self, level: WindowLevel) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
+      if level == WindowLevel::AlwaysOnBottom {
+        state.require(Feature::AlwaysOnBottom)?;
+      }
       match level {
         WindowLevel::AlwaysOnTop => {
           window.set_window_level(winit::window::WindowLevel::AlwaysOnTop)
@@ -512,86 +1077,96 @@ impl WindowController {
       }
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn is_visible(&self) -> Result<bool> {
-    let state = self.state.lock().unwrap();
+  pub fn is_visible(&self) -> OverlayResult<bool> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       Ok(window.is_visible().unwrap_or(false))
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn request_redraw(&self) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn request_redraw(&self) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       window.request_redraw();
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn set_cursor_visible(&self, visible: bool) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn set_cursor_visible(&self, visible: bool) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
       window.set_cursor_visible(visible);
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn set_ignore_mouse_events(&self, ignore: bool) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  pub fn set_ignore_mouse_events(&self, ignore: bool) -> OverlayResult<()> {
+    let state = self.lock()?;
     if let Some(window) = &state.window {
-      window.set_cursor_hittest(!ignore).map_err(|e| {
-        Error::new(
-          Status::GenericFailure,
-          format!("Failed to set hittest: {}", e),
-        )
-      })?;
+      if !state.require(Feature::ClickThrough)? {
+        return Ok(());
+      }
+      window
+        .set_cursor_hittest(!ignore)
+        .map_err(|e| OverlayError::platform(format!("Failed to set hittest: {}", e)))?;
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn set_render_when_occluded(&self, render: bool) {
-    let mut state = self.state.lock().unwrap();
+  pub fn set_render_when_occluded(&self, render: bool) -> OverlayResult<()> {
+    let mut state = self.lock()?;
     state.render_when_occluded = render;
+    Ok(())
   }
 
-  pub fn is_occluded(&self) -> bool {
-    let state = self.state.lock().unwrap();
-    state.occluded
+  pub fn is_occluded(&self) -> OverlayResult<bool> {
+    let state = self.lock()?;
+    Ok(state.occluded)
+  }
+
+  pub fn set_render_recovery(&self, options: &RenderRecoveryOptions) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    state.render_recovery.configure(options)?;
+    if let Some(window) = &state.window {
+      window.request_redraw();
+    }
+    Ok(())
+  }
+
+  pub fn get_render_status(&self) -> OverlayResult<RenderStatus> {
+    let state = self.lock()?;
+    Ok(state.render_recovery.status())
   }
 
   /// Set whether the window should be excluded from screen capture (OBS, etc)
   /// When set to false, OBS and other capture tools can capture this window
   /// When set to true, the window will appear black/invisible in captures
   #[cfg(target_os = "windows")]
-  pub fn set_exclude_from_capture(&self, exclude: bool) -> Result<()> {
-    let mut state = self.state.lock().unwrap();
+  pub fn set_exclude_from_capture(&self, exclude: bool) -> OverlayResult<()> {
+    let mut state = self.lock()?;
     if let Some(window) = &state.window {
       let hwnd = match window.window_handle() {
         Ok(handle) => match handle.as_raw() {
           RawWindowHandle::Win32(h) => h.hwnd.get() as *mut std::ffi::c_void,
-          _ => {
-            return Err(Error::new(
-              Status::GenericFailure,
-              "Not a Win32 window handle",
-            ))
-          }
+          _ => return Err(OverlayError::platform("Not a Win32 window handle")),
         },
         Err(e) => {
-          return Err(Error::new(
-            Status::GenericFailure,
-            format!("Failed to get window handle: {}", e),
-          ))
+          return Err(OverlayError::platform(format!(
+            "Failed to get window handle: {}",
+            e
+          )))
         }
       };
 
@@ -604,51 +1179,278 @@ impl WindowController {
       let result = unsafe { SetWindowDisplayAffinity(hwnd, affinity) };
 
       if result == 0 {
-        return Err(Error::new(
-          Status::GenericFailure,
-          "SetWindowDisplayAffinity failed",
-        ));
+        return Err(OverlayError::platform("SetWindowDisplayAffinity failed"));
       }
 
       state.exclude_from_capture = exclude;
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
   #[cfg(not(target_os = "windows"))]
-  pub fn set_exclude_from_capture(&self, exclude: bool) -> Result<()> {
-    let mut state = self.state.lock().unwrap();
+  pub fn set_exclude_from_capture(&self, exclude: bool) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    state.require(Feature::CaptureExclusion)?;
     state.exclude_from_capture = exclude;
     // On non-Windows platforms, this is a no-op
     Ok(())
   }
 
-  pub fn is_excluded_from_capture(&self) -> bool {
-    let state = self.state.lock().unwrap();
-    state.exclude_from_capture
+  /// Set the window region, or restore the full window with `None`
+  pub fn set_shape(&self, shape: Option<WindowShape>) -> OverlayResult<bool> {
+    let region = shape
+      .map(|shape| crate::shape::shape_region(&shape))
+      .transpose()
+      .map_err(OverlayError::invalid_arg)?;
+    let mut state = self.lock()?;
+    if state.window.is_none() {
+      return Err(state.window_error());
+    }
+    state.require(Feature::PerPixelHitTest)?;
+    state.shape = region;
+    Ok(state.apply_shape())
+  }
+
+  pub fn set_corner_radius(&self, radius: f64) -> OverlayResult<bool> {
+    let radius = crate::shape::validate_corner_radius(radius)?;
+    let mut state = self.lock()?;
+    if state.window.is_none() {
+      return Err(state.window_error());
+    }
+    state.require(Feature::PerPixelHitTest)?;
+    state.corner_radius = radius;
+    Ok(state.shape.is_some() || state.apply_shape())
+  }
+
+  pub fn get_corner_radius(&self) -> OverlayResult<f64> {
+    let state = self.lock()?;
+    Ok(state.corner_radius)
+  }
+
+  /// Set the window and taskbar icons from an image, or reset them with `None`
+  pub fn set_icon(&self, image: Option<DecodedImage>) -> OverlayResult<bool> {
+    let variants = image
+      .map(|image| crate::icon::icon_variants(&image))
+      .transpose()
+      .map_err(|e| OverlayError::invalid_arg(format!("Invalid icon: {}", e)))?;
+    let state = self.lock()?;
+    let Some(window) = &state.window else {
+      return Err(state.window_error());
+    };
+    let supported = state.require(Feature::WindowIcon)?;
+    crate::icon::apply_icon(window, variants.as_deref());
+    Ok(supported)
+  }
+
+  pub fn set_progress(&self, value: f64, progress: ProgressState) -> OverlayResult<bool> {
+    let value = crate::icon::validate_progress(value)?;
+    let state = self.lock()?;
+    let Some(window) = &state.window else {
+      return Err(state.window_error());
+    };
+    Ok(
+      state.require(Feature::TaskbarProgress)?
+        && crate::icon::apply_progress(window, value, progress),
+    )
   }
 
-  pub fn set_skip_taskbar(&self, skip: bool) -> Result<()> {
-    let state = self.state.lock().unwrap();
+  /// Set the whole-window opacity, cancelling any running opacity animation
+  pub fn set_opacity(&self, opacity: f64) -> OverlayResult<()> {
+    let opacity = clamp_opacity(opacity)
+      .ok_or_else(|| OverlayError::invalid_arg("Opacity must be a number"))?;
+    let mut state = self.lock()?;
     if let Some(window) = &state.window {
-      #[cfg(target_os = "windows")]
-      {
-        window.set_skip_taskbar(skip);
-        Ok(())
+      window.request_redraw();
+      let cancelled = state
+        .animator
+        .cancel_property(TweenTarget::Window, TweenProperty::Opacity);
+      state.notify_cancelled(cancelled);
+      state.opacity = opacity;
+      Ok(())
+    } else {
+      Err(state.window_error())
+    }
+  }
+
+  pub fn get_opacity(&self) -> OverlayResult<f64> {
+    let state = self.lock()?;
+    Ok(state.opacity as f64)
+  }
+
+  /// Start a fade from the current opacity, driven by the event loop
+  pub fn fade(
+    &self,
+    direction: FadeDirection,
+    duration_ms: u32,
+    easing: Easing,
+  ) -> OverlayResult<()> {
+    let mut tween = Tween::new(
+      TweenTarget::Window,
+      TweenProperty::Opacity,
+      None,
+      TweenValue::Number(direction.target()),
+      duration_ms,
+      easing,
+    );
+    tween.completion_event = Some(direction.completion_event());
+
+    let mut state = self.lock()?;
+    let cancelled = state
+      .animator
+      .cancel_property(TweenTarget::Window, TweenProperty::Opacity);
+    state.notify_cancelled(cancelled);
+    self.start_tween(&mut state, tween)?;
+    Ok(())
+  }
+
+  /// Start a property animation and return its id
+  pub fn animate(
+    &self,
+    options: TweenOptions,
+    callback: Option<ThreadsafeFunction<FnArgs<(u32, bool)>>>,
+  ) -> OverlayResult<u32> {
+    let mut state = self.lock()?;
+
+    let target = match options.element {
+      Some(id) if state.elements.iter().any(|e| e.id == id) => TweenTarget::Element(id),
+      Some(id) => {
+        return Err(OverlayError::invalid_arg(format!(
+          "Element {} does not exist",
+          id
+        )))
       }
-      #[cfg(not(target_os = "windows"))]
-      {
-        Ok(())
+      None => TweenTarget::Window,
+    };
+
+    let (from, to) = if options.property == TweenProperty::Color {
+      if target == TweenTarget::Window {
+        return Err(OverlayError::invalid_arg(
+          "Color can only be animated on elements",
+        ));
       }
+      let to = options
+        .to_color
+        .ok_or_else(|| OverlayError::invalid_arg("Color animations require toColor"))?;
+      (
+        options.from_color.map(TweenValue::Color),
+        TweenValue::Color(to),
+      )
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      let to = options
+        .to
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| OverlayError::invalid_arg("Animations require a finite `to` value"))?;
+      (
+        options
+          .from
+          .filter(|v| v.is_finite())
+          .map(TweenValue::Number),
+        TweenValue::Number(to),
+      )
+    };
+
+    if let Some(after) = options.after {
+      if !state.animator.contains(after) {
+        return Err(OverlayError::invalid_arg(format!(
+          "Animation {} is not running",
+          after
+        )));
+      }
+    }
+
+    let mut tween = Tween::new(
+      target,
+      options.property,
+      from,
+      to,
+      options.duration_ms,
+      options.easing.unwrap_or_default(),
+    );
+    tween.delay = std::time::Duration::from_millis(options.delay_ms.unwrap_or(0) as u64);
+    tween.after = options.after;
+    tween.callback = callback;
+
+    self.start_tween(&mut state, tween)
+  }
+
+  fn start_tween(&self, state: &mut WindowState, tween: Tween) -> OverlayResult<u32> {
+    if let Some(window) = &state.window {
+      window.request_redraw();
+      let id = state.animator.add(tween);
+      // Zero-length tweens complete immediately
+      state.tick_animations(Instant::now());
+      Ok(id)
+    } else {
+      Err(state.window_error())
+    }
+  }
+
+  /// Cancel an animation and the animations chained after it
+  pub fn cancel_animation(&self, id: u32) -> OverlayResult<bool> {
+    let mut state = self.lock()?;
+    let cancelled = state.animator.cancel(id);
+    let found = !cancelled.is_empty();
+    state.notify_cancelled(cancelled);
+    Ok(found)
+  }
+
+  pub fn cancel_all_animations(&self) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    let cancelled = state.animator.clear();
+    state.notify_cancelled(cancelled);
+    Ok(())
+  }
+
+  pub fn is_animating(&self) -> OverlayResult<bool> {
+    let state = self.lock()?;
+    Ok(state.animator.is_active())
+  }
+
+  pub fn is_excluded_from_capture(&self) -> OverlayResult<bool> {
+    let state = self.lock()?;
+    Ok(state.exclude_from_capture)
+  }
+
+  /// Close the window and release its surface, even after a panic; the
+  /// window can't be used afterwards
+  pub fn close(&self) {
+    let mut state = recover(&self.state);
+    // The surface holds on to the window, so it goes first
+    state.gpu = None;
+    state.pixels = None;
+    state.window = None;
+    state.destroyed = true;
+  }
+
+  #[cfg(target_os = "windows")]
+  pub fn set_skip_taskbar(&self, skip: bool) -> OverlayResult<()> {
+    let state = self.lock()?;
+    if let Some(window) = &state.window {
+      state.require(Feature::SkipTaskbar)?;
+      window.set_skip_taskbar(skip);
+      Ok(())
+    } else {
+      Err(state.window_error())
+    }
+  }
+
+  #[cfg(not(target_os = "windows"))]
+  pub fn set_skip_taskbar(&self, _skip: bool) -> OverlayResult<()> {
+    let state = self.lock()?;
+    if state.window.is_some() {
+      // Unsupported here, so this reports the feature as missing
+      state.require(Feature::SkipTaskbar)?;
+      Ok(())
+    } else {
+      Err(state.window_error())
     }
   }
 }
 
 /// Frame buffer operations
+#[derive(Clone)]
 pub struct FrameController {
   state: Arc<Mutex<WindowState>>,
 }
@@ -658,59 +1460,48 @@ impl FrameController {
     Self { state }
   }
 
-  pub fn update_frame(&self, buffer_data: &[u8]) -> Result<()> {
-    let state = self.state.lock().unwrap();
-    if let Some(pixels) = &state.pixels {
-      let frame = pixels.frame();
-      if buffer_data.len() != frame.len() {
-        return Err(Error::new(
-          Status::GenericFailure,
-          format!(
-            "Buffer size mismatch: expected {}, got {}",
-            frame.len(),
-            buffer_data.len()
-          ),
-        ));
-      }
-
-      // Safe way to get mut frame since pixels is in Option
-      drop(state);
-      let mut state = self.state.lock().unwrap();
-      let pixels = state.pixels.as_mut().unwrap();
-      let frame = pixels.frame_mut();
+  fn lock(&self) -> OverlayResult<MutexGuard<'_, WindowState>> {
+    crate::recovery::lock(&self.state, WindowState::repair)
+  }
 
-      frame.copy_from_slice(buffer_data);
+  pub fn update_frame(
+    &self,
+    buffer_data: &[u8],
+    format: PixelFormat,
+    stride: Option<u32>,
+  ) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    if state.window.is_none() {
+      return Err(state.window_error());
+    }
 
-      if let Some(window) = &state.window {
-        window.request_redraw();
+    if format == PixelFormat::Rgba8 && stride.is_none() {
+      if buffer_data.len() != state.frame.len() {
+        return Err(OverlayError::BufferSizeMismatch {
+          expected: state.frame.len(),
+          got: buffer_data.len(),
+        });
       }
-      Ok(())
+      state.frame.copy_from_slice(buffer_data);
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      let (width, height) = (state.width, state.height);
+      crate::format::decode_into(buffer_data, width, height, format, stride, &mut state.frame)
+        .map_err(OverlayError::invalid_arg)?;
     }
+
+    if let Some(window) = &state.window {
+      window.request_redraw();
+    }
+    Ok(())
   }
 
-  pub fn get_frame_size(&self) -> Result<Vec<u32>> {
-    let state = self.state.lock().unwrap();
+  pub fn get_frame_size(&self) -> OverlayResult<Vec<u32>> {
+    let state = self.lock()?;
     Ok(vec![state.width, state.height])
   }
 
-  pub fn clear_frame(&self, color: &Color) -> Result<()> {
-    let mut state = self.state.lock().unwrap();
-    let frame_width = state.width;
-    let frame_height = state.height;
-
-    if let Some(pixels) = &mut state.pixels {
-      let frame = pixels.frame_mut();
-      crate::buffer::clear_buffer_optimized(frame, frame_width, frame_height, color);
-
-      if let Some(window) = &state.window {
-        window.request_redraw();
-      }
-      Ok(())
-    } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
-    }
+  pub fn clear_frame(&self, color: &Color) -> OverlayResult<()> {
+    self.with_surface(|surface, mode| surface.fill(&color.in_alpha_mode(mode)))
   }
 
   pub fn draw_rectangle(
@@ -720,90 +1511,297 @@ impl FrameController {
     width: u32,
     height: u32,
     color: &Color,
-  ) -> Result<()> {
-    let mut state = self.state.lock().unwrap();
-    let frame_width = state.width as usize;
-    let frame_height = state.height as usize;
-
-    if let Some(pixels) = &mut state.pixels {
-      let frame = pixels.frame_mut();
-
-      crate::buffer::draw_rectangle_optimized(
-        frame,
-        crate::buffer::RectangleParams {
-          x,
-          y,
-          width,
-          height,
-          frame_width,
-          frame_height,
-        },
-        color,
-      );
+  ) -> OverlayResult<()> {
+    self.with_surface(|surface, mode| {
+      let color = color.in_alpha_mode(mode);
+      surface.fill_rect(x as i64, y as i64, width as i64, height as i64, &color)
+    })
+  }
 
-      if let Some(window) = &state.window {
-        window.request_redraw();
+  pub fn clear_frame_gradient(&self, gradient: &Gradient) -> OverlayResult<()> {
+    let gradient = compile_gradient(gradient)?;
+    self.with_surface(|surface, mode| match mode {
+      AlphaMode::Straight => surface.fill(&gradient),
+      AlphaMode::Premultiplied => surface.fill(&Premultiplied(&gradient)),
+    })
+  }
+
+  pub fn draw_rectangle_gradient(
+    &self,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    gradient: &Gradient,
+  ) -> OverlayResult<()> {
+    let gradient = compile_gradient(gradient)?;
+    let (x, y, width, height) = (x as i64, y as i64, width as i64, height as i64);
+    self.with_surface(|surface, mode| match mode {
+      AlphaMode::Straight => surface.fill_rect(x, y, width, height, &gradient),
+      AlphaMode::Premultiplied => surface.fill_rect(x, y, width, height, &Premultiplied(&gradient)),
+    })
+  }
+
+  pub fn draw_image(
+    &self,
+    x: u32,
+    y: u32,
+    image: &crate::types::DecodedImage,
+    effects: &[Effect],
+  ) -> OverlayResult<()> {
+    let (width, height) = (image.width, image.height);
+    let format = image.format.unwrap_or_default();
+    let mut rgba = crate::format::to_rgba(image.data.as_ref(), width, height, format, image.stride)
+      .map_err(OverlayError::invalid_arg)?;
+    if !effects.is_empty() {
+      let filtered = Filtered::new(&rgba, width, height, effects);
+      return self.with_surface(|surface, mode| filtered.draw(surface, x as i64, y as i64, mode));
+    }
+    self.with_surface(|surface, mode| {
+      // Images are straight, so they're converted to the frame's convention
+      if mode == AlphaMode::Premultiplied {
+        crate::buffer::premultiply_in_place(rgba.to_mut());
       }
-      Ok(())
+      surface.blit(x as i64, y as i64, &rgba, width, height)
+    })
+  }
+
+  /// Draw a registered image
+  pub fn draw_sprite(
+    &self,
+    id: &str,
+    x: i32,
+    y: i32,
+    options: &SpriteDrawOptions,
+  ) -> OverlayResult<()> {
+    // The registry is locked after the window state, as when presenting
+    self.with_surface(|surface, mode| {
+      let mut sprites = crate::sprite::registry()?;
+      let sprite = sprites.get(id)?;
+      sprite.draw(surface, x as i64, y as i64, options, mode);
+      Ok::<_, OverlayError>(())
+    })?
+  }
+
+  /// Filter a region of the frame in place (the whole frame when `None`)
+  pub fn apply_filters(&self, region: Option<Rect>, effects: &[Effect]) -> OverlayResult<()> {
+    self
+      .with_surface(|surface, mode| crate::filter::apply_to_surface(surface, region, mode, effects))
+  }
+
+  /// Draw a YUV frame scaled into a rectangle of the frame
+  pub fn draw_yuv(&self, frame: &YuvFrame, options: Option<YuvDrawOptions>) -> OverlayResult<()> {
+    let image = crate::yuv::yuv_image(frame)?;
+    let options = options.unwrap_or_default();
+    self.with_surface(|surface, _| {
+      image.draw(
+        surface,
+        options.x.unwrap_or(0) as i64,
+        options.y.unwrap_or(0) as i64,
+        options.width.unwrap_or(frame.width),
+        options.height.unwrap_or(frame.height),
+      )
+    })
+  }
+
+  /// Replace the whole frame with a YUV frame scaled to fit it
+  pub fn update_frame_yuv(&self, frame: &YuvFrame) -> OverlayResult<()> {
+    let image = crate::yuv::yuv_image(frame)?;
+    self.with_surface(|surface, _| {
+      let (width, height) = (surface.width(), surface.height());
+      image.draw(surface, 0, 0, width, height)
+    })
+  }
+
+  /// Add a retained element drawn over the frame, returning its id
+  pub fn add_element(&self, options: &ElementOptions) -> OverlayResult<u32> {
+    let mut state = self.lock()?;
+    if let Some(window) = &state.window {
+      window.request_redraw();
+      state.next_element_id = state.next_element_id.wrapping_add(1).max(1);
+      let id = state.next_element_id;
+      state.elements.push(Element::new(id, options));
+      Ok(id)
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn draw_image(&self, x: u32, y: u32, image: &crate::types::DecodedImage) -> Result<()> {
-    let mut state = self.state.lock().unwrap();
-    let frame_width = state.width as usize;
-    let frame_height = state.height as usize;
+  pub fn update_element(&self, id: u32, options: &ElementOptions) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    let element = state
+      .elements
+      .iter_mut()
+      .find(|e| e.id == id)
+      .ok_or_else(|| OverlayError::invalid_arg(format!("Element {} does not exist", id)))?;
+    element.apply(options);
+    if let Some(window) = &state.window {
+      window.request_redraw();
+    }
+    Ok(())
+  }
 
-    if let Some(pixels) = &mut state.pixels {
-      let frame = pixels.frame_mut();
-      let img_data = image.data.as_ref();
-      let img_width = image.width as usize;
-      let img_height = image.height as usize;
+  /// Remove an element and cancel its animations
+  pub fn remove_element(&self, id: u32) -> OverlayResult<bool> {
+    let mut state = self.lock()?;
+    let before = state.elements.len();
+    state.elements.retain(|e| e.id != id);
+    let cancelled = state.animator.cancel_element(id);
+    state.notify_cancelled(cancelled);
+    if let Some(window) = &state.window {
+      window.request_redraw();
+    }
+    Ok(state.elements.len() != before)
+  }
 
-      // Simple blit with bounds checking
-      for iy in 0..img_height {
-        let py = y as usize + iy;
-        if py >= frame_height {
-          break;
-        }
+  pub fn clear_elements(&self) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    let ids: Vec<u32> = state.elements.iter().map(|e| e.id).collect();
+    for id in ids {
+      let cancelled = state.animator.cancel_element(id);
+      state.notify_cancelled(cancelled);
+    }
+    state.elements.clear();
+    if let Some(window) = &state.window {
+      window.request_redraw();
+    }
+    Ok(())
+  }
 
-        for ix in 0..img_width {
-          let px = x as usize + ix;
-          if px >= frame_width {
-            break;
-          }
+  /// Change how the frame contents are interpreted; existing contents are not converted
+  pub fn set_alpha_mode(&self, mode: AlphaMode) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    state.alpha_mode = mode;
+    if let Some(window) = &state.window {
+      window.request_redraw();
+    }
+    Ok(())
+  }
 
-          let src_idx = (iy * img_width + ix) * 4;
-          let dst_idx = (py * frame_width + px) * 4;
+  pub fn get_alpha_mode(&self) -> OverlayResult<AlphaMode> {
+    let state = self.lock()?;
+    Ok(state.alpha_mode)
+  }
 
-          if src_idx + 3 < img_data.len() && dst_idx + 3 < frame.len() {
-            frame[dst_idx..dst_idx + 4].copy_from_slice(&img_data[src_idx..src_idx + 4]);
-          }
-        }
-      }
+  /// Choose the compositing backend; a previous GPU failure is retried
+  pub fn set_compositor(&self, backend: CompositorBackend) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    state.compositor = backend;
+    state.gpu_failed = false;
+    if let Some(window) = &state.window {
+      window.request_redraw();
+    }
+    Ok(())
+  }
 
-      if let Some(window) = &state.window {
-        window.request_redraw();
-      }
-      Ok(())
-    } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+  /// The backend presents actually use
+  pub fn get_compositor(&self) -> OverlayResult<CompositorBackend> {
+    let state = self.lock()?;
+    Ok(crate::compositor::active_backend(&state))
+  }
+
+  pub fn set_scale_mode(&self, mode: ScaleMode) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    if state.window.is_none() {
+      return Err(state.window_error());
+    }
+    state.scale_mode = mode;
+    if mode == ScaleMode::MatchWindow {
+      let (width, height) = (state.surface_width, state.surface_height);
+      state.resize_frame(width, height);
     }
+    if let Some(window) = &state.window {
+      window.request_redraw();
+    }
+    Ok(())
   }
 
-  pub fn get_frame_buffer(&self) -> Result<Buffer> {
-    let state = self.state.lock().unwrap();
-    if let Some(pixels) = &state.pixels {
-      let frame = pixels.frame();
-      Ok(Buffer::from(frame.to_vec()))
+  pub fn get_scale_mode(&self) -> OverlayResult<ScaleMode> {
+    let state = self.lock()?;
+    Ok(state.scale_mode)
+  }
+
+  pub fn set_resize_policy(&self, policy: ResizePolicy) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    state.resize_policy = policy;
+    Ok(())
+  }
+
+  pub fn get_resize_policy(&self) -> OverlayResult<ResizePolicy> {
+    let state = self.lock()?;
+    Ok(state.resize_policy)
+  }
+
+  /// Execute a validated command list under a single lock with one redraw.
+  /// Shapes are clipped to the frame first, so the lock is held for work
+  /// proportional to the frame rather than to the command's coordinates.
+  pub fn draw_commands(&self, ops: Vec<Op>) -> OverlayResult<()> {
+    self.with_surface(|surface, mode| {
+      let ops: Vec<Op> = ops.into_iter().map(|op| op.in_alpha_mode(mode)).collect();
+      crate::command::execute(surface, &ops);
+    })
+  }
+
+  /// Push a clip that limits all following drawing into the frame
+  pub fn push_clip(&self, shape: ClipShape) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    let (width, height) = (state.width, state.height);
+    state
+      .clip
+      .push(shape, width, height)
+      .map_err(OverlayError::invalid_arg)
+  }
+
+  /// Drop the most recent clip; false when the stack was empty
+  pub fn pop_clip(&self) -> OverlayResult<bool> {
+    Ok(self.lock()?.clip.pop())
+  }
+
+  pub fn reset_clip(&self) -> OverlayResult<()> {
+    self.lock()?.clip.clear();
+    Ok(())
+  }
+
+  pub fn get_clip_depth(&self) -> OverlayResult<u32> {
+    Ok(self.lock()?.clip.depth())
+  }
+
+  /// Run drawing code against the frame, limited to the current clip, and
+  /// schedule a redraw
+  pub fn with_surface<R>(
+    &self,
+    draw: impl FnOnce(&mut Surface, AlphaMode) -> R,
+  ) -> OverlayResult<R> {
+    let mut guard = self.lock()?;
+    let state = &mut *guard;
+    if state.window.is_none() {
+      return Err(state.window_error());
+    }
+    #[cfg(feature = "test-hooks")]
+    crate::recovery::check(crate::recovery::PanicSite::Drawing);
+    let (width, height, mode) = (state.width, state.height, state.alpha_mode);
+    let mut surface = Surface::packed(&mut state.frame, width, height);
+    let result = match state.clip.current() {
+      Some(clip) => clip.draw(&mut surface, |surface| draw(surface, mode)),
+      None => draw(&mut surface, mode),
+    };
+    if let Some(window) = &state.window {
+      window.request_redraw();
+    }
+    Ok(result)
+  }
+
+  pub fn get_frame_buffer(&self) -> OverlayResult<Buffer> {
+    let state = self.lock()?;
+    if state.window.is_some() {
+      Ok(Buffer::from(state.frame.clone()))
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn render(&self) -> Result<()> {
-    let mut state = self.state.lock().unwrap();
+  pub fn render(&self) -> OverlayResult<()> {
+    let mut state = self.lock()?;
 
     let should_render = !state.occluded || state.render_when_occluded;
 
@@ -811,32 +1809,31 @@ impl FrameController {
       return Ok(());
     }
 
-    if let Some(pixels) = &mut state.pixels {
-      pixels
-        .render()
-        .map_err(|e| Error::new(Status::GenericFailure, format!("Render error: {}", e)))?;
-      Ok(())
+    if state.window.is_some() {
+      crate::device::present_recovering(&mut state)
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 
-  pub fn resize(&self, width: u32, height: u32) -> Result<()> {
-    let mut state = self.state.lock().unwrap();
-    if let Some(pixels) = &mut state.pixels {
-      pixels
-        .resize_buffer(width, height)
-        .map_err(|e| Error::new(Status::GenericFailure, format!("Failed to resize: {}", e)))?;
+  pub fn resize(&self, width: u32, height: u32) -> OverlayResult<()> {
+    let mut state = self.lock()?;
+    if state.window.is_some() {
+      // Without a surface, the buffer is fitted when it is created again
+      if let Some(pixels) = &mut state.pixels {
+        pixels
+          .resize_buffer(width, height)
+          .map_err(|e| OverlayError::platform(format!("Failed to resize: {}", e)))?;
+      }
 
-      state.width = width;
-      state.height = height;
+      state.resize_frame(width, height);
 
       if let Some(window) = &state.window {
         window.request_redraw();
       }
       Ok(())
     } else {
-      Err(Error::new(Status::GenericFailure, "Window not initialized"))
+      Err(state.window_error())
     }
   }
 }
@@ -0,0 +1,263 @@
+//! YUV (I420 / NV12) video frame conversion into RGBA
+
+use crate::surface::Surface;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuvLayout {
+  /// Three planes: Y, then U and V at half resolution
+  I420,
+  /// Two planes: Y, then interleaved UV at half resolution
+  Nv12,
+}
+
+/// Color matrix used to encode the video
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum YuvMatrix {
+  /// Standard definition video
+  #[default]
+  Bt601,
+  /// High definition video
+  Bt709,
+}
+
+#[napi]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum YuvRange {
+  /// Y in 16..235 and chroma in 16..240 (typical for video)
+  #[default]
+  Limited,
+  Full,
+}
+
+#[napi(object)]
+pub struct YuvFrame {
+  pub layout: YuvLayout,
+  pub width: u32,
+  pub height: u32,
+  pub y: Buffer,
+  /// U plane (I420)
+  pub u: Option<Buffer>,
+  /// V plane (I420)
+  pub v: Option<Buffer>,
+  /// Interleaved UV plane (NV12)
+  pub uv: Option<Buffer>,
+  /// Bytes per row of each plane; tightly packed when omitted
+  pub y_stride: Option<u32>,
+  pub u_stride: Option<u32>,
+  pub v_stride: Option<u32>,
+  pub uv_stride: Option<u32>,
+  pub matrix: Option<YuvMatrix>,
+  pub range: Option<YuvRange>,
+}
+
+/// Where to draw a YUV frame; it is scaled (nearest neighbor) to the size
+#[napi(object)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct YuvDrawOptions {
+  pub x: Option<i32>,
+  pub y: Option<i32>,
+  /// Destination size, defaulting to the frame's own size
+  pub width: Option<u32>,
+  pub height: Option<u32>,
+}
+
+/// Fixed-point (16.16) YUV to RGB coefficients
+#[derive(Debug, Clone, Copy)]
+struct Coefficients {
+  y_offset: i32,
+  y_scale: i32,
+  r_v: i32,
+  g_u: i32,
+  g_v: i32,
+  b_u: i32,
+}
+
+impl Coefficients {
+  fn new(matrix: YuvMatrix, range: YuvRange) -> Self {
+    let (kr, kb) = match matrix {
+      YuvMatrix::Bt601 => (0.299, 0.114),
+      YuvMatrix::Bt709 => (0.2126, 0.0722),
+    };
+    let kg = 1.0 - kr - kb;
+    let (y_offset, y_scale, c_scale) = match range {
+      YuvRange::Limited => (16, 255.0 / 219.0, 255.0 / 224.0),
+      YuvRange::Full => (0, 1.0, 1.0),
+    };
+    let fixed = |v: f64| (v * 65536.0).round() as i32;
+    Self {
+      y_offset,
+      y_scale: fixed(y_scale),
+      r_v: fixed(2.0 * (1.0 - kr) * c_scale),
+      g_u: fixed(2.0 * (1.0 - kb) * kb / kg * c_scale),
+      g_v: fixed(2.0 * (1.0 - kr) * kr / kg * c_scale),
+      b_u: fixed(2.0 * (1.0 - kb) * c_scale),
+    }
+  }
+
+  #[inline]
+  fn rgba(&self, y: u8, u: u8, v: u8) -> [u8; 4] {
+    let luma = (y as i32 - self.y_offset) * self.y_scale + (1 << 15);
+    let (u, v) = (u as i32 - 128, v as i32 - 128);
+    let channel = |c: i32| (c >> 16).clamp(0, 255) as u8;
+    [
+      channel(luma + self.r_v * v),
+      channel(luma - self.g_u * u - self.g_v * v),
+      channel(luma + self.b_u * u),
+      255,
+    ]
+  }
+}
+
+/// A plane with its validated row stride
+struct Plane<'a> {
+  data: &'a [u8],
+  stride: usize,
+}
+
+impl<'a> Plane<'a> {
+  fn new(
+    name: &str,
+    data: &'a [u8],
+    row_bytes: usize,
+    rows: usize,
+    stride: Option<u32>,
+  ) -> std::result::Result<Self, String> {
+    let stride = stride.map(|s| s as usize).unwrap_or(row_bytes);
+    if stride < row_bytes {
+      return Err(format!(
+        "{} stride {} is smaller than its row of {} bytes",
+        name, stride, row_bytes
+      ));
+    }
+    let required = match rows {
+      0 => 0,
+      rows => (rows - 1) * stride + row_bytes,
+    };
+    if data.len() < required {
+      return Err(format!(
+        "{} plane too small: expected at least {} bytes, got {}",
+        name,
+        required,
+        data.len()
+      ));
+    }
+    Ok(Self { data, stride })
+  }
+
+  #[inline]
+  fn at(&self, x: usize, y: usize) -> u8 {
+    self.data[y * self.stride + x]
+  }
+}
+
+/// A validated frame ready for sampling
+pub struct YuvImage<'a> {
+  width: u32,
+  height: u32,
+  y: Plane<'a>,
+  /// U and V planes; both are the interleaved plane for NV12
+  u: Plane<'a>,
+  v: Plane<'a>,
+  interleaved: bool,
+  coefficients: Coefficients,
+}
+
+impl YuvFrame {
+  pub fn image(&self) -> std::result::Result<YuvImage<'_>, String> {
+    let (w, h) = (self.width as usize, self.height as usize);
+    let (cw, ch) = (w.div_ceil(2), h.div_ceil(2));
+    let y = Plane::new("Y", &self.y, w, h, self.y_stride)?;
+    let (u, v, interleaved) = match self.layout {
+      YuvLayout::I420 => {
+        let (Some(u), Some(v)) = (&self.u, &self.v) else {
+          return Err("I420 frames require `u` and `v` planes".to_string());
+        };
+        (
+          Plane::new("U", u, cw, ch, self.u_stride)?,
+          Plane::new("V", v, cw, ch, self.v_stride)?,
+          false,
+        )
+      }
+      YuvLayout::Nv12 => {
+        let Some(uv) = &self.uv else {
+          return Err("NV12 frames require a `uv` plane".to_string());
+        };
+        let plane = || Plane::new("UV", uv, cw * 2, ch, self.uv_stride);
+        (plane()?, plane()?, true)
+      }
+    };
+    Ok(YuvImage {
+      width: self.width,
+      height: self.height,
+      y,
+      u,
+      v,
+      interleaved,
+      coefficients: Coefficients::new(
+        self.matrix.unwrap_or_default(),
+        self.range.unwrap_or_default(),
+      ),
+    })
+  }
+}
+
+impl YuvImage<'_> {
+  #[inline]
+  fn rgba_at(&self, x: usize, y: usize) -> [u8; 4] {
+    let (cx, cy) = (x / 2, y / 2);
+    let (u, v) = if self.interleaved {
+      (self.u.at(cx * 2, cy), self.v.at(cx * 2 + 1, cy))
+    } else {
+      (self.u.at(cx, cy), self.v.at(cx, cy))
+    };
+    self.coefficients.rgba(self.y.at(x, y), u, v)
+  }
+
+  /// Draw the frame scaled into the rectangle at `(x, y)`, clipped to the surface
+  pub fn draw(&self, surface: &mut Surface, x: i64, y: i64, width: u32, height: u32) {
+    if width == 0 || height == 0 || self.width == 0 || self.height == 0 {
+      return;
+    }
+    let Some((cx, cy, cw, ch)) = surface.clip(x, y, width, height) else {
+      return;
+    };
+    let (x0, x1) = (cx as i64, (cx + cw) as i64);
+
+    // Sample at pixel centers: src = (dst + 0.5) * src_size / dst_size
+    let source = |d: i64, dst_size: u32, src_size: u32| {
+      ((d * 2 + 1) * src_size as i64 / (dst_size as i64 * 2)) as usize
+    };
+    surface.for_rows(cy, cy + ch, |py, row| {
+      let sy = source(py as i64 - y, height, self.height);
+      for px in x0..x1 {
+        let sx = source(px - x, width, self.width);
+        let index = px as usize * 4;
+        row[index..index + 4].copy_from_slice(&self.rgba_at(sx, sy));
+      }
+    });
+  }
+}
+
+pub(crate) fn yuv_image(frame: &YuvFrame) -> Result<YuvImage<'_>> {
+  frame.image().map_err(|e| Error::new(Status::InvalidArg, e))
+}
+
+// NAPI exports
+/// Convert a YUV frame to a tightly packed RGBA buffer at its own size
+#[napi(catch_unwind)]
+pub fn convert_yuv_to_rgba(frame: YuvFrame) -> Result<Buffer> {
+  let image = yuv_image(&frame)?;
+  let mut data = vec![0; crate::buffer::calculate_buffer_size(frame.width, frame.height)];
+  image.draw(
+    &mut Surface::packed(&mut data, frame.width, frame.height),
+    0,
+    0,
+    frame.width,
+    frame.height,
+  );
+  Ok(Buffer::from(data))
+}